@@ -1,5 +1,10 @@
 //! Variable Selection Module
 
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
@@ -29,20 +34,919 @@ pub struct SelectionScore {
     
     /// Number of variables selected
     pub num_selected: usize,
+
+    /// Bootstrap confidence per variable: the fraction of resamples in which
+    /// it lands in the resample's own top-`num_selected` set. Empty unless
+    /// computed by [`VariableSelector::select_variables_matrix_with_confidence`].
+    pub confidence: Vec<f64>,
+
+    /// Indices of the variables that survived selection. Populated by
+    /// [`VariableSelector::select_indices`]'s configured strategy, and further
+    /// filtered by `min_confidence` when confidence was computed.
+    pub selected_indices: Vec<usize>,
+}
+
+/// Estimator used to compute mutual information over a variable's sample column
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MutualInformationEstimator {
+    /// Equal-width histogram binning (`MI = Σ p(x,y) · ln(p(x,y) / (p(x)·p(y)))`)
+    Histogram,
+    /// Kraskov-Stögbauer-Grassberger k-nearest-neighbour estimator for continuous data
+    Ksg,
+}
+
+/// Configuration for the weighted-product `Combined` score: each component
+/// method's normalized score is raised to its own weight and the results are
+/// multiplied together, so a variable weak on any single criterion is
+/// penalized multiplicatively rather than rescued by a strong one
+#[derive(Debug, Clone)]
+pub struct CombinedConfig {
+    /// Per-criterion weight; the weights of the methods actually combined
+    /// should sum to 1.0
+    weights: HashMap<SelectionMethod, f64>,
+    /// Small constant added to each normalized score before exponentiation,
+    /// so a component that normalizes to exactly 0 doesn't zero out the product
+    epsilon: f64,
+}
+
+impl CombinedConfig {
+    /// The four component methods the default weighting spreads evenly over
+    fn default_components() -> [SelectionMethod; 4] {
+        [
+            SelectionMethod::MutualInformation,
+            SelectionMethod::Variance,
+            SelectionMethod::Correlation,
+            SelectionMethod::FeatureImportance,
+        ]
+    }
+
+    /// Weight assigned to `method`, or `0.0` if not configured
+    fn weight(&self, method: SelectionMethod) -> f64 {
+        self.weights.get(&method).copied().unwrap_or(0.0)
+    }
+
+    /// Set (or replace) the weight for one component method
+    pub fn with_weight(mut self, method: SelectionMethod, weight: f64) -> Self {
+        self.weights.insert(method, weight);
+        self
+    }
+
+    /// Set the stabilizing epsilon added before exponentiation
+    pub fn with_epsilon(mut self, epsilon: f64) -> Self {
+        self.epsilon = epsilon;
+        self
+    }
+}
+
+impl Default for CombinedConfig {
+    fn default() -> Self {
+        let mut weights = HashMap::new();
+        for method in Self::default_components() {
+            weights.insert(method, 0.25);
+        }
+        Self { weights, epsilon: 1e-6 }
+    }
+}
+
+/// Hyperparameters for the gradient-boosted regression-tree ensemble that
+/// backs [`SelectionMethod::FeatureImportance`] over the matrix API
+#[derive(Debug, Clone)]
+pub struct GbtConfig {
+    /// Number of boosting rounds (trees)
+    pub num_trees: usize,
+    /// Maximum depth of each regression tree
+    pub max_depth: usize,
+    /// Shrinkage applied to each tree's contribution
+    pub learning_rate: f64,
+    /// Fraction of rows sampled (without replacement) to grow each tree
+    pub subsample_fraction: f64,
+    /// Below this many samples, fall back to the correlation/variance heuristic
+    /// instead of fitting an ensemble
+    pub min_samples_to_fit: usize,
+}
+
+impl Default for GbtConfig {
+    fn default() -> Self {
+        Self {
+            num_trees: 50,
+            max_depth: 3,
+            learning_rate: 0.1,
+            subsample_fraction: 0.8,
+            min_samples_to_fit: 20,
+        }
+    }
+}
+
+/// Strategy used by [`VariableSelector::select_indices`] to turn a
+/// [`SelectionScore`] into a concrete set of chosen variable indices
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SelectionStrategy {
+    /// Deterministically pick the `num_selected` highest-scoring indices
+    TopK,
+    /// Draw `num_selected` indices without replacement, with probability
+    /// proportional to score (a seedable weighted random sample)
+    WeightedRandom,
+}
+
+/// A node in a small CART-style regression tree grown on squared-error gain
+enum RegressionTreeNode {
+    Leaf {
+        value: f64,
+    },
+    Split {
+        feature: usize,
+        threshold: f64,
+        left: Box<RegressionTreeNode>,
+        right: Box<RegressionTreeNode>,
+    },
 }
 
 /// Variable selector
 pub struct VariableSelector {
     method: SelectionMethod,
+    /// Estimator used for [`SelectionMethod::MutualInformation`] over sample columns
+    mi_estimator: MutualInformationEstimator,
+    /// Histogram bin count per dimension; `None` uses `ceil(sqrt(num_samples))`
+    mi_bins: Option<usize>,
+    /// Number of nearest neighbours `k` used by the KSG estimator
+    mi_k: usize,
+    /// Per-criterion weights for [`SelectionMethod::Combined`]'s weighted-product score
+    combined_config: CombinedConfig,
+    /// Hyperparameters for the gradient-boosted ensemble behind [`SelectionMethod::FeatureImportance`]
+    gbt_config: GbtConfig,
+    /// Strategy used by [`Self::select_indices`] to turn scores into chosen indices
+    strategy: SelectionStrategy,
+    /// Seed for [`SelectionStrategy::WeightedRandom`]; `None` draws from the thread-local RNG
+    rng_seed: Option<u64>,
 }
 
 impl VariableSelector {
     /// Create a new selector with specified method
     pub fn new(method: SelectionMethod) -> Self {
-        Self { method }
+        Self {
+            method,
+            mi_estimator: MutualInformationEstimator::Histogram,
+            mi_bins: None,
+            mi_k: 3,
+            combined_config: CombinedConfig::default(),
+            gbt_config: GbtConfig::default(),
+            strategy: SelectionStrategy::TopK,
+            rng_seed: None,
+        }
     }
-    
+
+    /// Use the given mutual-information estimator instead of the default histogram one
+    pub fn with_mi_estimator(mut self, estimator: MutualInformationEstimator) -> Self {
+        self.mi_estimator = estimator;
+        self
+    }
+
+    /// Fix the histogram bin count per dimension instead of deriving it from `sqrt(N)`
+    pub fn with_mi_bins(mut self, bins: usize) -> Self {
+        self.mi_bins = Some(bins);
+        self
+    }
+
+    /// Set the number of nearest neighbours `k` used by the KSG estimator
+    pub fn with_mi_k(mut self, k: usize) -> Self {
+        self.mi_k = k.max(1);
+        self
+    }
+
+    /// Use the given criterion weights for [`SelectionMethod::Combined`]
+    /// instead of the default equal weighting
+    pub fn with_combined_config(mut self, config: CombinedConfig) -> Self {
+        self.combined_config = config;
+        self
+    }
+
+    /// Use the given gradient-boosted-tree hyperparameters for
+    /// [`SelectionMethod::FeatureImportance`] instead of the defaults
+    pub fn with_gbt_config(mut self, config: GbtConfig) -> Self {
+        self.gbt_config = config;
+        self
+    }
+
+    /// Use the given strategy in [`Self::select_indices`] instead of the default top-k
+    pub fn with_strategy(mut self, strategy: SelectionStrategy) -> Self {
+        self.strategy = strategy;
+        self
+    }
+
+    /// Seed the RNG used by [`SelectionStrategy::WeightedRandom`] for reproducible draws
+    pub fn with_rng_seed(mut self, seed: u64) -> Self {
+        self.rng_seed = Some(seed);
+        self
+    }
+
+    /// Turn a [`SelectionScore`] into a concrete set of chosen variable
+    /// indices, according to the configured [`SelectionStrategy`]:
+    /// deterministic top-`num_selected` by score, or a seedable weighted
+    /// random draw without replacement (probability proportional to score)
+    pub fn select_indices(&self, score: &SelectionScore) -> Vec<usize> {
+        let k = score.num_selected.min(score.scores.len());
+
+        match self.strategy {
+            SelectionStrategy::TopK => Self::top_k_indices(&score.scores, k),
+            SelectionStrategy::WeightedRandom => match self.rng_seed {
+                Some(seed) => {
+                    Self::weighted_sample_without_replacement(&score.scores, k, &mut StdRng::seed_from_u64(seed))
+                }
+                None => Self::weighted_sample_without_replacement(&score.scores, k, &mut rand::thread_rng()),
+            },
+        }
+    }
+
+    /// Indices of the `k` highest-scoring entries, descending by score
+    fn top_k_indices(scores: &[f64], k: usize) -> Vec<usize> {
+        let mut indices: Vec<usize> = (0..scores.len()).collect();
+        indices.sort_by(|&a, &b| scores[b].partial_cmp(&scores[a]).unwrap());
+        indices.truncate(k.min(scores.len()));
+        indices
+    }
+
+    /// Draw `k` indices without replacement from `scores`, with probability
+    /// proportional to each score: build the cumulative weight table over
+    /// the remaining candidates, draw a uniform sample, remove the picked
+    /// index, and repeat (an implicit renormalization each round)
+    fn weighted_sample_without_replacement(scores: &[f64], k: usize, rng: &mut impl Rng) -> Vec<usize> {
+        let weights: Vec<f64> = scores.iter().map(|&s| s.max(0.0) + 1e-9).collect();
+        let mut available: Vec<usize> = (0..scores.len()).collect();
+        let mut chosen = Vec::with_capacity(k.min(available.len()));
+
+        for _ in 0..k.min(available.len()) {
+            let total: f64 = available.iter().map(|&i| weights[i]).sum();
+            let draw = rng.gen::<f64>() * total;
+
+            let mut cumulative = 0.0;
+            let mut pick_pos = available.len() - 1;
+            for (pos, &i) in available.iter().enumerate() {
+                cumulative += weights[i];
+                if draw <= cumulative {
+                    pick_pos = pos;
+                    break;
+                }
+            }
+
+            chosen.push(available.remove(pick_pos));
+        }
+
+        chosen
+    }
+
+    /// Select variables from a samples×variables matrix
+    ///
+    /// `intervention` rows are samples and columns are candidate variables;
+    /// `response` has one entry per sample (`intervention.len() == response.len()`).
+    /// Unlike [`Self::select_variables`], which scores each index from a single
+    /// `(x, y)` pair, this scores each column across *all* samples: variance is
+    /// the real sample variance, correlation is the Pearson correlation between
+    /// a column and the response vector, and so on.
+    pub fn select_variables_matrix(
+        &self,
+        intervention: &[Vec<f64>],
+        response: &[f64],
+        context: Option<&HashMap<String, f64>>,
+        max_variables: usize,
+        min_variables: usize,
+    ) -> Result<SelectionScore, String> {
+        if intervention.is_empty() || response.is_empty() {
+            return Err("Intervention matrix or response vector is empty".to_string());
+        }
+
+        if intervention.len() != response.len() {
+            return Err(format!(
+                "Response length ({}) must match number of samples ({})",
+                response.len(),
+                intervention.len()
+            ));
+        }
+
+        let num_variables = intervention[0].len();
+        if num_variables == 0 {
+            return Err("Intervention matrix has no variable columns".to_string());
+        }
+        if intervention.iter().any(|row| row.len() != num_variables) {
+            return Err("All intervention rows must have the same number of columns".to_string());
+        }
+
+        let columns = Self::columns(intervention, num_variables);
+
+        let scores = match self.method {
+            SelectionMethod::MutualInformation => {
+                self.compute_mutual_information_scores_matrix(&columns, response)?
+            }
+            SelectionMethod::Variance => self.compute_variance_scores_matrix(&columns),
+            SelectionMethod::Correlation => self.compute_correlation_scores_matrix(&columns, response),
+            SelectionMethod::FeatureImportance => {
+                self.compute_feature_importance_scores_matrix(&columns, response, context)?
+            }
+            SelectionMethod::Combined => {
+                self.compute_combined_scores_matrix(&columns, response, context)?
+            }
+        };
+
+        let num_selected = num_variables.clamp(min_variables, max_variables);
+        let normalized_scores = self.normalize_scores(&scores);
+
+        let mut result = SelectionScore {
+            scores: normalized_scores,
+            method: self.method,
+            num_selected,
+            confidence: Vec::new(),
+            selected_indices: Vec::new(),
+        };
+        result.selected_indices = self.select_indices(&result);
+        Ok(result)
+    }
+
+    /// Bootstrap confidence for [`Self::select_variables_matrix`]: resample
+    /// the sample rows (with replacement) `bootstrap_resamples` times,
+    /// recompute scores on each resample, and set each variable's confidence
+    /// to the fraction of resamples in which it lands in that resample's own
+    /// top-`num_selected` set (by score, regardless of [`Self::strategy`]).
+    /// Variables scoring below `min_confidence` are dropped from
+    /// `selected_indices`, but never below `min_variables`.
+    pub fn select_variables_matrix_with_confidence(
+        &self,
+        intervention: &[Vec<f64>],
+        response: &[f64],
+        context: Option<&HashMap<String, f64>>,
+        max_variables: usize,
+        min_variables: usize,
+        bootstrap_resamples: usize,
+        min_confidence: f64,
+    ) -> Result<SelectionScore, String> {
+        let mut result = self.select_variables_matrix(intervention, response, context, max_variables, min_variables)?;
+
+        if bootstrap_resamples == 0 {
+            return Ok(result);
+        }
+
+        let num_variables = result.scores.len();
+        // Seeding each resample from a base seed plus its index (rather than
+        // sharing one RNG) keeps the bootstrap deterministic regardless of
+        // whether resamples run on one thread or are spread across the pool
+        let base_seed = self.rng_seed.unwrap_or_else(|| rand::thread_rng().gen());
+
+        let resample_hits = |resample_index: usize| -> Vec<usize> {
+            let mut rng = StdRng::seed_from_u64(base_seed.wrapping_add(resample_index as u64));
+            let num_samples = intervention.len();
+            let resample_rows: Vec<usize> = (0..num_samples).map(|_| rng.gen_range(0..num_samples)).collect();
+            let resampled_intervention: Vec<Vec<f64>> =
+                resample_rows.iter().map(|&i| intervention[i].clone()).collect();
+            let resampled_response: Vec<f64> = resample_rows.iter().map(|&i| response[i]).collect();
+
+            let mut local_hits = vec![0usize; num_variables];
+            if let Ok(resample_score) = self.select_variables_matrix(
+                &resampled_intervention,
+                &resampled_response,
+                context,
+                max_variables,
+                min_variables,
+            ) {
+                for i in Self::top_k_indices(&resample_score.scores, resample_score.num_selected) {
+                    local_hits[i] += 1;
+                }
+            }
+            local_hits
+        };
+
+        #[cfg(feature = "parallel")]
+        let hits = (0..bootstrap_resamples).into_par_iter().map(resample_hits).reduce(
+            || vec![0usize; num_variables],
+            |mut a, b| {
+                for (x, y) in a.iter_mut().zip(b.iter()) {
+                    *x += y;
+                }
+                a
+            },
+        );
+        #[cfg(not(feature = "parallel"))]
+        let hits = (0..bootstrap_resamples).fold(vec![0usize; num_variables], |mut acc, resample_index| {
+            for (x, y) in acc.iter_mut().zip(resample_hits(resample_index).iter()) {
+                *x += y;
+            }
+            acc
+        });
+
+        let confidence: Vec<f64> = hits.iter().map(|&h| h as f64 / bootstrap_resamples as f64).collect();
+
+        let mut surviving: Vec<usize> = result
+            .selected_indices
+            .iter()
+            .copied()
+            .filter(|&i| confidence[i] >= min_confidence)
+            .collect();
+
+        if surviving.len() < min_variables {
+            let mut dropped: Vec<usize> = result
+                .selected_indices
+                .iter()
+                .copied()
+                .filter(|i| !surviving.contains(i))
+                .collect();
+            dropped.sort_by(|&a, &b| confidence[b].partial_cmp(&confidence[a]).unwrap());
+            for i in dropped {
+                if surviving.len() >= min_variables {
+                    break;
+                }
+                surviving.push(i);
+            }
+        }
+
+        result.confidence = confidence;
+        result.selected_indices = surviving;
+        Ok(result)
+    }
+
+    /// Transpose a samples×variables matrix into one `Vec<f64>` per variable column
+    fn columns(intervention: &[Vec<f64>], num_variables: usize) -> Vec<Vec<f64>> {
+        let mut columns = vec![Vec::with_capacity(intervention.len()); num_variables];
+        for row in intervention {
+            for (column, &value) in columns.iter_mut().zip(row.iter()) {
+                column.push(value);
+            }
+        }
+        columns
+    }
+
+    /// Sample mean
+    fn mean(values: &[f64]) -> f64 {
+        if values.is_empty() {
+            return 0.0;
+        }
+        values.iter().sum::<f64>() / values.len() as f64
+    }
+
+    /// Unbiased (Bessel-corrected) sample variance; `0.0` for fewer than 2 samples
+    fn sample_variance(values: &[f64]) -> f64 {
+        if values.len() < 2 {
+            return 0.0;
+        }
+        let mean = Self::mean(values);
+        let sum_sq_dev: f64 = values.iter().map(|v| (v - mean).powi(2)).sum();
+        sum_sq_dev / (values.len() - 1) as f64
+    }
+
+    /// Pearson correlation coefficient between two equal-length samples;
+    /// `0.0` if either has zero variance or fewer than 2 samples
+    fn pearson_correlation(x: &[f64], y: &[f64]) -> f64 {
+        let n = x.len().min(y.len());
+        if n < 2 {
+            return 0.0;
+        }
+
+        let mean_x = Self::mean(&x[..n]);
+        let mean_y = Self::mean(&y[..n]);
+
+        let mut covariance = 0.0;
+        let mut variance_x = 0.0;
+        let mut variance_y = 0.0;
+
+        for i in 0..n {
+            let dx = x[i] - mean_x;
+            let dy = y[i] - mean_y;
+            covariance += dx * dy;
+            variance_x += dx * dx;
+            variance_y += dy * dy;
+        }
+
+        if variance_x == 0.0 || variance_y == 0.0 {
+            return 0.0;
+        }
+
+        covariance / (variance_x.sqrt() * variance_y.sqrt())
+    }
+
+    /// Compute real sample variance per variable column
+    fn compute_variance_scores_matrix(&self, columns: &[Vec<f64>]) -> Vec<f64> {
+        #[cfg(feature = "parallel")]
+        {
+            columns.par_iter().map(|column| Self::sample_variance(column)).collect()
+        }
+        #[cfg(not(feature = "parallel"))]
+        {
+            columns.iter().map(|column| Self::sample_variance(column)).collect()
+        }
+    }
+
+    /// Compute Pearson correlation (absolute value) between each column and the response
+    fn compute_correlation_scores_matrix(&self, columns: &[Vec<f64>], response: &[f64]) -> Vec<f64> {
+        #[cfg(feature = "parallel")]
+        {
+            columns
+                .par_iter()
+                .map(|column| Self::pearson_correlation(column, response).abs())
+                .collect()
+        }
+        #[cfg(not(feature = "parallel"))]
+        {
+            columns
+                .iter()
+                .map(|column| Self::pearson_correlation(column, response).abs())
+                .collect()
+        }
+    }
+
+    /// Estimate mutual information between each variable column and the
+    /// response using the configured [`MutualInformationEstimator`]
+    fn compute_mutual_information_scores_matrix(
+        &self,
+        columns: &[Vec<f64>],
+        response: &[f64],
+    ) -> Result<Vec<f64>, String> {
+        let score_column = |column: &Vec<f64>| match self.mi_estimator {
+            MutualInformationEstimator::Histogram => {
+                let bins = self.mi_bins.unwrap_or_else(|| (column.len() as f64).sqrt().ceil() as usize).max(1);
+                Self::histogram_mutual_information(column, response, bins)
+            }
+            MutualInformationEstimator::Ksg => Self::ksg_mutual_information(column, response, self.mi_k),
+        };
+
+        #[cfg(feature = "parallel")]
+        {
+            Ok(columns.par_iter().map(score_column).collect())
+        }
+        #[cfg(not(feature = "parallel"))]
+        {
+            Ok(columns.iter().map(score_column).collect())
+        }
+    }
+
+    /// Histogram-based mutual information estimator: discretize `x` and `y`
+    /// into `bins` equal-width bins each, build the joint count table, and
+    /// compute `MI = Σ p(x,y) · ln(p(x,y) / (p(x)·p(y)))` over non-empty
+    /// cells, clamped at 0
+    fn histogram_mutual_information(x: &[f64], y: &[f64], bins: usize) -> f64 {
+        let n = x.len().min(y.len());
+        if n == 0 || bins == 0 {
+            return 0.0;
+        }
+        let x = &x[..n];
+        let y = &y[..n];
+
+        let (x_min, x_max) = Self::min_max(x);
+        let (y_min, y_max) = Self::min_max(y);
+
+        let bin_index = |value: f64, min: f64, max: f64| -> usize {
+            if max <= min {
+                return 0;
+            }
+            let width = (max - min) / bins as f64;
+            (((value - min) / width) as usize).min(bins - 1)
+        };
+
+        let mut joint_counts = vec![0usize; bins * bins];
+        let mut x_counts = vec![0usize; bins];
+        let mut y_counts = vec![0usize; bins];
+
+        for i in 0..n {
+            let xi = bin_index(x[i], x_min, x_max);
+            let yi = bin_index(y[i], y_min, y_max);
+            joint_counts[xi * bins + yi] += 1;
+            x_counts[xi] += 1;
+            y_counts[yi] += 1;
+        }
+
+        let n_f = n as f64;
+        let mut mi = 0.0;
+
+        for xi in 0..bins {
+            for yi in 0..bins {
+                let joint = joint_counts[xi * bins + yi];
+                if joint == 0 {
+                    continue;
+                }
+                let p_xy = joint as f64 / n_f;
+                let p_x = x_counts[xi] as f64 / n_f;
+                let p_y = y_counts[yi] as f64 / n_f;
+                if p_x > 0.0 && p_y > 0.0 {
+                    mi += p_xy * (p_xy / (p_x * p_y)).ln();
+                }
+            }
+        }
+
+        mi.max(0.0)
+    }
+
+    /// Kraskov (KSG) k-nearest-neighbour mutual information estimator for
+    /// continuous data: for each sample, find the distance to its k-th
+    /// nearest neighbour in the joint `(x, y)` space under the Chebyshev
+    /// norm, count neighbours strictly closer than that distance along each
+    /// marginal, then `MI ≈ ψ(k) + ψ(N) − mean(ψ(n_x+1) + ψ(n_y+1))`
+    fn ksg_mutual_information(x: &[f64], y: &[f64], k: usize) -> f64 {
+        let n = x.len().min(y.len());
+        if n <= k {
+            return 0.0;
+        }
+        let x = &x[..n];
+        let y = &y[..n];
+
+        let mut digamma_sum = 0.0;
+
+        for i in 0..n {
+            let mut joint_distances: Vec<f64> = (0..n)
+                .filter(|&j| j != i)
+                .map(|j| (x[i] - x[j]).abs().max((y[i] - y[j]).abs()))
+                .collect();
+            joint_distances.sort_by(|a, b| a.partial_cmp(b).unwrap());
+            let epsilon = joint_distances[k - 1];
+
+            let n_x = (0..n).filter(|&j| j != i && (x[i] - x[j]).abs() < epsilon).count();
+            let n_y = (0..n).filter(|&j| j != i && (y[i] - y[j]).abs() < epsilon).count();
+
+            digamma_sum += Self::digamma((n_x + 1) as f64) + Self::digamma((n_y + 1) as f64);
+        }
+
+        let mi = Self::digamma(k as f64) + Self::digamma(n as f64) - digamma_sum / n as f64;
+        mi.max(0.0)
+    }
+
+    /// Small asymptotic-series approximation of the digamma function `ψ(x)`:
+    /// use the recurrence `ψ(x) = ψ(x+1) − 1/x` to shift `x` above 6, then
+    /// apply the standard asymptotic expansion
+    fn digamma(mut x: f64) -> f64 {
+        let mut result = 0.0;
+        while x < 6.0 {
+            result -= 1.0 / x;
+            x += 1.0;
+        }
+        let inv = 1.0 / x;
+        let inv2 = inv * inv;
+        result + x.ln() - 0.5 * inv - inv2 * (1.0 / 12.0 - inv2 * (1.0 / 120.0 - inv2 / 252.0))
+    }
+
+    /// Minimum and maximum of a slice
+    fn min_max(values: &[f64]) -> (f64, f64) {
+        let mut min = f64::INFINITY;
+        let mut max = f64::NEG_INFINITY;
+        for &v in values {
+            min = min.min(v);
+            max = max.max(v);
+        }
+        (min, max)
+    }
+
+    /// Feature importance derived from a gradient-boosted regression-tree
+    /// ensemble fit over the variable columns as features and the response
+    /// as target: each split's squared-error gain is credited to its
+    /// splitting feature and summed across all trees, then normalized to
+    /// sum to 1. Falls back to the correlation/variance heuristic when there
+    /// are too few samples to fit (see [`GbtConfig::min_samples_to_fit`]).
+    fn compute_feature_importance_scores_matrix(
+        &self,
+        columns: &[Vec<f64>],
+        response: &[f64],
+        context: Option<&HashMap<String, f64>>,
+    ) -> Result<Vec<f64>, String> {
+        let mut scores = if response.len() < self.gbt_config.min_samples_to_fit {
+            Self::heuristic_feature_importance_scores(columns, response)
+        } else {
+            self.fit_gbt_importance(columns, response)
+        };
+
+        if let Some(ctx) = context {
+            for (i, score) in scores.iter_mut().enumerate() {
+                *score += ctx.get(&format!("var_{}", i)).copied().unwrap_or(0.0);
+            }
+        }
+
+        Ok(scores)
+    }
+
+    /// Correlation-scaled-by-variance heuristic used when there isn't enough
+    /// data to reliably fit a gradient-boosted ensemble
+    fn heuristic_feature_importance_scores(columns: &[Vec<f64>], response: &[f64]) -> Vec<f64> {
+        let score_column = |column: &Vec<f64>| {
+            let correlation = Self::pearson_correlation(column, response).abs();
+            let variance = Self::sample_variance(column);
+            correlation * variance.sqrt()
+        };
+
+        #[cfg(feature = "parallel")]
+        {
+            columns.par_iter().map(score_column).collect()
+        }
+        #[cfg(not(feature = "parallel"))]
+        {
+            columns.iter().map(score_column).collect()
+        }
+    }
+
+    /// Fit [`GbtConfig::num_trees`] boosted regression trees against the
+    /// response (each tree grown on the residual of the current ensemble),
+    /// accumulating per-feature squared-error-gain importance along the way,
+    /// then normalize the importances to sum to 1
+    fn fit_gbt_importance(&self, columns: &[Vec<f64>], response: &[f64]) -> Vec<f64> {
+        let num_variables = columns.len();
+        let num_samples = response.len();
+        let mut importances = vec![0.0; num_variables];
+
+        let mean_response = Self::mean(response);
+        let mut predictions = vec![mean_response; num_samples];
+
+        let subsample_size = ((num_samples as f64) * self.gbt_config.subsample_fraction)
+            .ceil()
+            .max(1.0) as usize;
+        let subsample_size = subsample_size.min(num_samples);
+
+        // Seeded from `rng_seed` (falling back to a fresh seed only when
+        // unset) so fitting the same data twice with the same seed yields
+        // identical importances, matching the determinism bootstrap
+        // resampling already relies on in `select_variables_matrix_with_confidence`
+        let base_seed = self.rng_seed.unwrap_or_else(|| rand::thread_rng().gen());
+        let mut rng = StdRng::seed_from_u64(base_seed);
+
+        for _ in 0..self.gbt_config.num_trees {
+            let residuals: Vec<f64> = response
+                .iter()
+                .zip(predictions.iter())
+                .map(|(y, p)| y - p)
+                .collect();
+
+            let mut row_indices: Vec<usize> = (0..num_samples).collect();
+            row_indices.shuffle(&mut rng);
+            row_indices.truncate(subsample_size);
+
+            let tree = Self::build_regression_tree(
+                columns,
+                &residuals,
+                &row_indices,
+                0,
+                self.gbt_config.max_depth,
+                &mut importances,
+            );
+
+            for (i, prediction) in predictions.iter_mut().enumerate() {
+                *prediction += self.gbt_config.learning_rate * Self::predict_tree(&tree, columns, i);
+            }
+        }
+
+        let total: f64 = importances.iter().sum();
+        if total > 0.0 {
+            importances.iter().map(|v| v / total).collect()
+        } else {
+            vec![0.0; num_variables]
+        }
+    }
+
+    /// Sum of squared deviations from the mean, over the given row indices
+    fn sse(row_indices: &[usize], targets: &[f64]) -> f64 {
+        if row_indices.is_empty() {
+            return 0.0;
+        }
+        let values: Vec<f64> = row_indices.iter().map(|&i| targets[i]).collect();
+        let mean = Self::mean(&values);
+        values.iter().map(|v| (v - mean).powi(2)).sum()
+    }
+
+    /// The best threshold split for a single feature column against
+    /// `row_indices`, if any split improves on `parent_sse`; factored out of
+    /// [`Self::build_regression_tree`] so the per-feature search can run
+    /// over a rayon parallel iterator behind the `parallel` feature
+    fn best_split_for_feature(
+        feature: usize,
+        column: &[f64],
+        row_indices: &[usize],
+        targets: &[f64],
+        parent_sse: f64,
+    ) -> Option<(usize, f64, f64, Vec<usize>, Vec<usize>)> {
+        let mut values: Vec<f64> = row_indices.iter().map(|&i| column[i]).collect();
+        values.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        values.dedup();
+        if values.len() < 2 {
+            return None;
+        }
+
+        let mut best: Option<(usize, f64, f64, Vec<usize>, Vec<usize>)> = None;
+
+        for window in values.windows(2) {
+            let threshold = (window[0] + window[1]) / 2.0;
+            let (left_idx, right_idx): (Vec<usize>, Vec<usize>) =
+                row_indices.iter().partition(|&&i| column[i] <= threshold);
+            if left_idx.is_empty() || right_idx.is_empty() {
+                continue;
+            }
+
+            let gain = parent_sse - Self::sse(&left_idx, targets) - Self::sse(&right_idx, targets);
+            if gain > best.as_ref().map(|b| b.2).unwrap_or(0.0) {
+                best = Some((feature, threshold, gain, left_idx, right_idx));
+            }
+        }
+
+        best
+    }
+
+    /// Greedily grow one CART-style regression tree by picking, at each
+    /// node, the feature/threshold split that maximizes squared-error
+    /// reduction, crediting that reduction to the splitting feature
+    fn build_regression_tree(
+        columns: &[Vec<f64>],
+        targets: &[f64],
+        row_indices: &[usize],
+        depth: usize,
+        max_depth: usize,
+        importances: &mut [f64],
+    ) -> RegressionTreeNode {
+        let leaf_value = Self::mean(&row_indices.iter().map(|&i| targets[i]).collect::<Vec<f64>>());
+
+        if depth >= max_depth || row_indices.len() < 2 {
+            return RegressionTreeNode::Leaf { value: leaf_value };
+        }
+
+        let parent_sse = Self::sse(row_indices, targets);
+
+        #[cfg(feature = "parallel")]
+        let per_feature_best: Vec<Option<(usize, f64, f64, Vec<usize>, Vec<usize>)>> = columns
+            .par_iter()
+            .enumerate()
+            .map(|(feature, column)| Self::best_split_for_feature(feature, column, row_indices, targets, parent_sse))
+            .collect();
+        #[cfg(not(feature = "parallel"))]
+        let per_feature_best: Vec<Option<(usize, f64, f64, Vec<usize>, Vec<usize>)>> = columns
+            .iter()
+            .enumerate()
+            .map(|(feature, column)| Self::best_split_for_feature(feature, column, row_indices, targets, parent_sse))
+            .collect();
+
+        let best = per_feature_best
+            .into_iter()
+            .flatten()
+            .max_by(|a, b| a.2.partial_cmp(&b.2).unwrap());
+
+        match best {
+            Some((feature, threshold, gain, left_idx, right_idx)) if gain > 0.0 => {
+                importances[feature] += gain;
+                let left =
+                    Self::build_regression_tree(columns, targets, &left_idx, depth + 1, max_depth, importances);
+                let right =
+                    Self::build_regression_tree(columns, targets, &right_idx, depth + 1, max_depth, importances);
+                RegressionTreeNode::Split {
+                    feature,
+                    threshold,
+                    left: Box::new(left),
+                    right: Box::new(right),
+                }
+            }
+            _ => RegressionTreeNode::Leaf { value: leaf_value },
+        }
+    }
+
+    /// Walk a regression tree to predict the value for sample `row`
+    fn predict_tree(node: &RegressionTreeNode, columns: &[Vec<f64>], row: usize) -> f64 {
+        match node {
+            RegressionTreeNode::Leaf { value } => *value,
+            RegressionTreeNode::Split { feature, threshold, left, right } => {
+                if columns[*feature][row] <= *threshold {
+                    Self::predict_tree(left, columns, row)
+                } else {
+                    Self::predict_tree(right, columns, row)
+                }
+            }
+        }
+    }
+
+    /// Combine the four matrix-based scores as a weighted product: each
+    /// method's scores are normalized into `(0, 1]` (via `+ epsilon`), raised
+    /// to that method's configured weight, and multiplied together, so a
+    /// variable weak on any single criterion is penalized multiplicatively
+    /// rather than rescued by a strong one. Weights come from
+    /// [`Self::combined_config`]; see [`CombinedConfig`].
+    fn compute_combined_scores_matrix(
+        &self,
+        columns: &[Vec<f64>],
+        response: &[f64],
+        context: Option<&HashMap<String, f64>>,
+    ) -> Result<Vec<f64>, String> {
+        let mi_scores = self.normalize_scores(&self.compute_mutual_information_scores_matrix(columns, response)?);
+        let var_scores = self.normalize_scores(&self.compute_variance_scores_matrix(columns));
+        let corr_scores = self.normalize_scores(&self.compute_correlation_scores_matrix(columns, response));
+        let fi_scores = self.normalize_scores(&self.compute_feature_importance_scores_matrix(columns, response, context)?);
+
+        let epsilon = self.combined_config.epsilon;
+        let mi_weight = self.combined_config.weight(SelectionMethod::MutualInformation);
+        let var_weight = self.combined_config.weight(SelectionMethod::Variance);
+        let corr_weight = self.combined_config.weight(SelectionMethod::Correlation);
+        let fi_weight = self.combined_config.weight(SelectionMethod::FeatureImportance);
+
+        Ok((0..columns.len())
+            .map(|i| {
+                (mi_scores[i] + epsilon).powf(mi_weight)
+                    * (var_scores[i] + epsilon).powf(var_weight)
+                    * (corr_scores[i] + epsilon).powf(corr_weight)
+                    * (fi_scores[i] + epsilon).powf(fi_weight)
+            })
+            .collect())
+    }
+
     /// Select variables based on intervention and response data
+    ///
+    /// Legacy scalar-pair API retained for backward compatibility: each index
+    /// is scored from a single `(x, y)` pair rather than a sample column, so it
+    /// cannot compute real distributional statistics. Prefer
+    /// [`Self::select_variables_matrix`] when multiple samples per variable
+    /// are available.
     pub fn select_variables(
         &self,
         intervention_data: &[f64],
@@ -80,14 +984,18 @@ impl VariableSelector {
         
         // Normalize scores to 0-1 range
         let normalized_scores = self.normalize_scores(&scores);
-        
-        Ok(SelectionScore {
+
+        let mut result = SelectionScore {
             scores: normalized_scores,
             method: self.method,
             num_selected,
-        })
+            confidence: Vec::new(),
+            selected_indices: Vec::new(),
+        };
+        result.selected_indices = self.select_indices(&result);
+        Ok(result)
     }
-    
+
     /// Compute mutual information scores
     fn compute_mutual_information_scores(
         &self,
@@ -272,9 +1180,360 @@ mod tests {
     #[test]
     fn test_empty_data() {
         let selector = VariableSelector::new(SelectionMethod::MutualInformation);
-        
+
         let result = selector.select_variables(&[], &[], None, 5, 3);
-        
+
         assert!(result.is_err());
     }
+
+    #[test]
+    fn test_select_variables_matrix_computes_real_variance() {
+        let selector = VariableSelector::new(SelectionMethod::Variance);
+
+        // Column 0 is constant (zero variance), column 1 varies a lot
+        let intervention = vec![
+            vec![1.0, 0.0],
+            vec![1.0, 10.0],
+            vec![1.0, -10.0],
+            vec![1.0, 5.0],
+        ];
+        let response = vec![0.1, 0.2, 0.3, 0.4];
+
+        let result = selector
+            .select_variables_matrix(&intervention, &response, None, 2, 1)
+            .unwrap();
+
+        assert_eq!(result.scores.len(), 2);
+        // After normalization the constant column scores at the minimum
+        assert_eq!(result.scores[0], 0.0);
+        assert_eq!(result.scores[1], 1.0);
+    }
+
+    #[test]
+    fn test_select_variables_matrix_computes_real_correlation() {
+        let selector = VariableSelector::new(SelectionMethod::Correlation);
+
+        // Column 0 tracks the response exactly; column 1 is unrelated noise
+        let intervention = vec![
+            vec![1.0, 5.0],
+            vec![2.0, -3.0],
+            vec![3.0, 8.0],
+            vec![4.0, -1.0],
+        ];
+        let response = vec![1.0, 2.0, 3.0, 4.0];
+
+        let result = selector
+            .select_variables_matrix(&intervention, &response, None, 2, 1)
+            .unwrap();
+
+        assert!(result.scores[0] > result.scores[1]);
+    }
+
+    #[test]
+    fn test_select_variables_matrix_rejects_mismatched_lengths() {
+        let selector = VariableSelector::new(SelectionMethod::Variance);
+
+        let intervention = vec![vec![1.0, 2.0], vec![3.0, 4.0]];
+        let response = vec![1.0]; // wrong length
+
+        let result = selector.select_variables_matrix(&intervention, &response, None, 2, 1);
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_histogram_mutual_information_detects_dependence() {
+        // y = 2x, perfectly dependent; should score much higher than independent noise
+        let x: Vec<f64> = (0..50).map(|i| i as f64).collect();
+        let y: Vec<f64> = x.iter().map(|v| 2.0 * v).collect();
+        let noise: Vec<f64> = (0..50).map(|i| if i % 2 == 0 { 1.0 } else { -1.0 }).collect();
+
+        let dependent_mi = VariableSelector::histogram_mutual_information(&x, &y, 7);
+        let independent_mi = VariableSelector::histogram_mutual_information(&x, &noise, 7);
+
+        assert!(dependent_mi > independent_mi);
+        assert!(dependent_mi >= 0.0);
+        assert!(independent_mi >= 0.0);
+    }
+
+    #[test]
+    fn test_ksg_mutual_information_detects_dependence() {
+        let x: Vec<f64> = (0..60).map(|i| i as f64 * 0.1).collect();
+        let y: Vec<f64> = x.iter().map(|v| v * 3.0).collect();
+        let noise: Vec<f64> = (0..60).map(|i| ((i * 37) % 11) as f64).collect();
+
+        let dependent_mi = VariableSelector::ksg_mutual_information(&x, &y, 3);
+        let independent_mi = VariableSelector::ksg_mutual_information(&x, &noise, 3);
+
+        assert!(dependent_mi > independent_mi);
+    }
+
+    #[test]
+    fn test_select_variables_matrix_uses_ksg_estimator_when_configured() {
+        let selector = VariableSelector::new(SelectionMethod::MutualInformation)
+            .with_mi_estimator(MutualInformationEstimator::Ksg)
+            .with_mi_k(2);
+
+        let intervention = vec![
+            vec![1.0, 9.0],
+            vec![2.0, 1.0],
+            vec![3.0, 8.0],
+            vec![4.0, 2.0],
+            vec![5.0, 7.0],
+            vec![6.0, 3.0],
+        ];
+        let response: Vec<f64> = intervention.iter().map(|row| row[0]).collect();
+
+        let result = selector
+            .select_variables_matrix(&intervention, &response, None, 2, 1)
+            .unwrap();
+
+        assert_eq!(result.scores.len(), 2);
+        assert!(result.scores[0] >= result.scores[1]);
+    }
+
+    #[test]
+    fn test_combined_weighted_product_penalizes_single_weak_criterion() {
+        let selector = VariableSelector::new(SelectionMethod::Combined);
+
+        // Column 0: strong on everything. Column 1: extreme variance but zero
+        // correlation with the response (constant-derived response), so an
+        // arithmetic mean could still rate it highly while a weighted
+        // product should penalize its zero-correlation weakness harshly.
+        let intervention = vec![
+            vec![1.0, 100.0],
+            vec![2.0, -100.0],
+            vec![3.0, 100.0],
+            vec![4.0, -100.0],
+            vec![5.0, 100.0],
+        ];
+        let response: Vec<f64> = intervention.iter().map(|row| row[0] * 2.0).collect();
+
+        let result = selector
+            .select_variables_matrix(&intervention, &response, None, 2, 1)
+            .unwrap();
+
+        assert!(result.scores[0] > result.scores[1]);
+    }
+
+    #[test]
+    fn test_combined_config_custom_weights_favor_requested_criterion() {
+        let heavy_variance = CombinedConfig::default().with_weight(SelectionMethod::Variance, 1.0)
+            .with_weight(SelectionMethod::MutualInformation, 0.0)
+            .with_weight(SelectionMethod::Correlation, 0.0)
+            .with_weight(SelectionMethod::FeatureImportance, 0.0);
+        let selector = VariableSelector::new(SelectionMethod::Combined).with_combined_config(heavy_variance);
+
+        let intervention = vec![
+            vec![1.0, 1.0],
+            vec![1.0, 2.0],
+            vec![1.0, 3.0],
+            vec![1.0, 4.0],
+        ];
+        let response = vec![1.0, 2.0, 3.0, 4.0];
+
+        let result = selector
+            .select_variables_matrix(&intervention, &response, None, 2, 1)
+            .unwrap();
+
+        // Column 0 has zero variance, column 1 has the only variance, so a
+        // variance-only weighting must favor column 1 exclusively
+        assert!(result.scores[1] > result.scores[0]);
+    }
+
+    #[test]
+    fn test_gbt_feature_importance_ranks_predictive_column_higher() {
+        let selector = VariableSelector::new(SelectionMethod::FeatureImportance).with_gbt_config(GbtConfig {
+            num_trees: 20,
+            max_depth: 2,
+            learning_rate: 0.3,
+            subsample_fraction: 1.0,
+            min_samples_to_fit: 5,
+        });
+
+        // Column 0 drives the response; column 1 is irrelevant noise
+        let n = 40;
+        let intervention: Vec<Vec<f64>> = (0..n)
+            .map(|i| vec![i as f64, if i % 2 == 0 { 1.0 } else { -1.0 }])
+            .collect();
+        let response: Vec<f64> = intervention.iter().map(|row| row[0] * 2.0).collect();
+
+        let result = selector
+            .select_variables_matrix(&intervention, &response, None, 2, 1)
+            .unwrap();
+
+        assert!(result.scores[0] > result.scores[1]);
+    }
+
+    #[test]
+    fn test_gbt_feature_importance_is_deterministic_with_same_seed() {
+        let make_selector = || {
+            VariableSelector::new(SelectionMethod::FeatureImportance)
+                .with_gbt_config(GbtConfig {
+                    num_trees: 20,
+                    max_depth: 2,
+                    learning_rate: 0.3,
+                    subsample_fraction: 0.5,
+                    min_samples_to_fit: 5,
+                })
+                .with_rng_seed(123)
+        };
+
+        let n = 40;
+        let intervention: Vec<Vec<f64>> = (0..n)
+            .map(|i| vec![i as f64, if i % 2 == 0 { 1.0 } else { -1.0 }])
+            .collect();
+        let response: Vec<f64> = intervention.iter().map(|row| row[0] * 2.0).collect();
+
+        let first = make_selector().select_variables_matrix(&intervention, &response, None, 2, 1).unwrap();
+        let second = make_selector().select_variables_matrix(&intervention, &response, None, 2, 1).unwrap();
+
+        assert_eq!(first.scores, second.scores);
+    }
+
+    #[test]
+    fn test_gbt_falls_back_to_heuristic_below_min_samples() {
+        let selector = VariableSelector::new(SelectionMethod::FeatureImportance).with_gbt_config(GbtConfig {
+            min_samples_to_fit: 100,
+            ..GbtConfig::default()
+        });
+
+        let intervention = vec![vec![1.0, 5.0], vec![2.0, -3.0], vec![3.0, 8.0], vec![4.0, -1.0]];
+        let response = vec![1.0, 2.0, 3.0, 4.0];
+
+        let result = selector
+            .select_variables_matrix(&intervention, &response, None, 2, 1)
+            .unwrap();
+
+        assert_eq!(result.scores.len(), 2);
+        // The heuristic favors the column strongly correlated with the response
+        assert!(result.scores[0] > result.scores[1]);
+    }
+
+    #[test]
+    fn test_select_indices_top_k_picks_highest_scores() {
+        let selector = VariableSelector::new(SelectionMethod::Variance);
+        let score = SelectionScore {
+            scores: vec![0.1, 0.9, 0.5, 0.3],
+            method: SelectionMethod::Variance,
+            num_selected: 2,
+            confidence: Vec::new(),
+            selected_indices: Vec::new(),
+        };
+
+        let indices = selector.select_indices(&score);
+
+        assert_eq!(indices, vec![1, 2]);
+    }
+
+    #[test]
+    fn test_select_indices_weighted_random_is_reproducible_with_seed() {
+        let selector = VariableSelector::new(SelectionMethod::Variance)
+            .with_strategy(SelectionStrategy::WeightedRandom)
+            .with_rng_seed(42);
+        let score = SelectionScore {
+            scores: vec![0.1, 0.9, 0.5, 0.3, 0.7],
+            method: SelectionMethod::Variance,
+            num_selected: 3,
+            confidence: Vec::new(),
+            selected_indices: Vec::new(),
+        };
+
+        let first = selector.select_indices(&score);
+        let second = selector.select_indices(&score);
+
+        assert_eq!(first.len(), 3);
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn test_select_indices_weighted_random_never_repeats_an_index() {
+        let selector = VariableSelector::new(SelectionMethod::Variance)
+            .with_strategy(SelectionStrategy::WeightedRandom)
+            .with_rng_seed(7);
+        let score = SelectionScore {
+            scores: vec![0.2, 0.0, 0.8, 0.4],
+            method: SelectionMethod::Variance,
+            num_selected: 4,
+            confidence: Vec::new(),
+            selected_indices: Vec::new(),
+        };
+
+        let indices = selector.select_indices(&score);
+        let mut sorted = indices.clone();
+        sorted.sort_unstable();
+
+        assert_eq!(sorted, vec![0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn test_bootstrap_confidence_favors_consistently_predictive_column() {
+        let selector = VariableSelector::new(SelectionMethod::Correlation).with_rng_seed(11);
+
+        // Column 0 tracks the response exactly across resamples; column 1 is noise
+        let n = 30;
+        let intervention: Vec<Vec<f64>> = (0..n)
+            .map(|i| vec![i as f64, if i % 2 == 0 { 1.0 } else { -1.0 }])
+            .collect();
+        let response: Vec<f64> = intervention.iter().map(|row| row[0] * 2.0).collect();
+
+        let result = selector
+            .select_variables_matrix_with_confidence(&intervention, &response, None, 2, 1, 50, 0.0)
+            .unwrap();
+
+        assert_eq!(result.confidence.len(), 2);
+        assert!(result.confidence[0] > result.confidence[1]);
+    }
+
+    #[test]
+    fn test_bootstrap_confidence_min_confidence_cutoff_respects_min_variables() {
+        let selector = VariableSelector::new(SelectionMethod::Correlation).with_rng_seed(3);
+
+        let n = 20;
+        let intervention: Vec<Vec<f64>> = (0..n)
+            .map(|i| vec![i as f64, if i % 2 == 0 { 1.0 } else { -1.0 }])
+            .collect();
+        let response: Vec<f64> = intervention.iter().map(|row| row[0] * 2.0).collect();
+
+        // An impossibly high cutoff would drop everything; min_variables must still hold
+        let result = selector
+            .select_variables_matrix_with_confidence(&intervention, &response, None, 2, 1, 30, 2.0)
+            .unwrap();
+
+        assert!(result.selected_indices.len() >= 1);
+    }
+
+    #[test]
+    fn test_select_variables_matrix_without_confidence_leaves_confidence_empty() {
+        let selector = VariableSelector::new(SelectionMethod::Variance);
+        let intervention = vec![vec![1.0, 0.0], vec![1.0, 10.0]];
+        let response = vec![0.1, 0.2];
+
+        let result = selector
+            .select_variables_matrix(&intervention, &response, None, 2, 1)
+            .unwrap();
+
+        assert!(result.confidence.is_empty());
+        assert!(!result.selected_indices.is_empty());
+    }
+
+    #[test]
+    fn test_bootstrap_confidence_is_deterministic_given_same_seed() {
+        let selector = VariableSelector::new(SelectionMethod::Correlation).with_rng_seed(99);
+
+        let n = 16;
+        let intervention: Vec<Vec<f64>> = (0..n)
+            .map(|i| vec![i as f64, if i % 3 == 0 { 1.0 } else { -1.0 }])
+            .collect();
+        let response: Vec<f64> = intervention.iter().map(|row| row[0] * 2.0).collect();
+
+        let first = selector
+            .select_variables_matrix_with_confidence(&intervention, &response, None, 2, 1, 25, 0.0)
+            .unwrap();
+        let second = selector
+            .select_variables_matrix_with_confidence(&intervention, &response, None, 2, 1, 25, 0.0)
+            .unwrap();
+
+        assert_eq!(first.confidence, second.confidence);
+    }
 }
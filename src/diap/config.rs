@@ -19,6 +19,9 @@ pub struct DiapConfig {
     
     /// 存储配置
     pub storage: StorageConfig,
+
+    /// WebAuthn配置
+    pub webauthn: WebauthnConfig,
 }
 
 /// 身份配置
@@ -57,9 +60,12 @@ pub enum IdentityType {
     
     /// 服务身份
     Service,
-    
+
     /// 智能体身份
     Agent,
+
+    /// 由FIDO2/WebAuthn认证器（硬件密钥或平台passkey）托管的身份
+    WebAuthn,
 }
 
 /// 网络配置
@@ -162,14 +168,30 @@ pub struct StorageConfig {
     
     /// 是否启用加密存储
     pub enable_encryption: bool,
-    
-    /// 加密密钥（base64编码）
+
+    /// 加密口令：通过Argon2id派生出实际的AES-256-GCM密钥，而不是直接当作密钥使用
     pub encryption_key: Option<String>,
     
     /// 存储备份间隔（秒）
     pub backup_interval: u64,
 }
 
+/// WebAuthn（FIDO2/passkey）身份后端的配置
+///
+/// 对应 `webauthn-rs` 的 `WebauthnBuilder`：`rp_id` 必须是依赖方网站的域名
+/// （不带协议和端口），`rp_origin` 是认证器校验的完整来源URL。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebauthnConfig {
+    /// 依赖方ID，通常是部署该服务的域名
+    pub rp_id: String,
+
+    /// 依赖方来源URL，必须与 `rp_id` 一致
+    pub rp_origin: String,
+
+    /// 展示给用户的依赖方名称
+    pub rp_name: String,
+}
+
 /// 身份权限（从mod.rs导入）
 use super::IdentityPermissions;
 
@@ -218,6 +240,11 @@ impl Default for DiapConfig {
                 encryption_key: None,
                 backup_interval: 86400, // 24小时
             },
+            webauthn: WebauthnConfig {
+                rp_id: "localhost".to_string(),
+                rp_origin: "http://localhost:8080".to_string(),
+                rp_name: "Multi-Agent Oracle".to_string(),
+            },
         }
     }
 }
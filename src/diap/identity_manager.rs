@@ -0,0 +1,1437 @@
+//! DIAP身份管理器
+//!
+//! 管理智能体的DIAP身份，包括注册、验证、证明生成等功能。
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use chrono::{DateTime, Utc};
+use webauthn_rs::prelude::{
+    CreationChallengeResponse, Passkey, PasskeyAuthentication, PasskeyRegistration,
+    PublicKeyCredential, RegisterPublicKeyCredential, RequestChallengeResponse, Webauthn,
+    WebauthnBuilder,
+};
+
+use super::config::{DiapConfig, IdentityType};
+use super::{DiapError, AuthResult, IdentityStatus, IdentityPermissions};
+
+/// 身份存储加密文件格式版本号
+const ENCRYPTION_FORMAT_VERSION: u8 = 1;
+/// Argon2id盐长度
+const SALT_LEN: usize = 16;
+/// AES-256-GCM nonce长度
+const NONCE_LEN: usize = 12;
+
+/// 用Argon2id从配置的口令和盐派生一把AES-256密钥
+fn derive_encryption_key(passphrase: &str, salt: &[u8]) -> Result<[u8; 32], DiapError> {
+    use argon2::Argon2;
+
+    let mut key = [0u8; 32];
+    Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|e| DiapError::InternalError(format!("密钥派生失败: {}", e)))?;
+    Ok(key)
+}
+
+/// 用AES-256-GCM加密数据，返回自描述的文件格式：
+/// `[版本号(1字节)][盐(16字节)][nonce(12字节)][密文+GCM标签]`
+fn encrypt_at_rest(passphrase: &str, plaintext: &[u8]) -> Result<Vec<u8>, DiapError> {
+    use aes_gcm::aead::{Aead, KeyInit};
+    use aes_gcm::{Aes256Gcm, Key, Nonce};
+    use rand::rngs::OsRng;
+    use rand::RngCore;
+
+    let mut salt = [0u8; SALT_LEN];
+    OsRng.fill_bytes(&mut salt);
+    let mut nonce_bytes = [0u8; NONCE_LEN];
+    OsRng.fill_bytes(&mut nonce_bytes);
+
+    let key_bytes = derive_encryption_key(passphrase, &salt)?;
+    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key_bytes));
+    let ciphertext = cipher
+        .encrypt(Nonce::from_slice(&nonce_bytes), plaintext)
+        .map_err(|e| DiapError::InternalError(format!("加密失败: {}", e)))?;
+
+    let mut sealed = Vec::with_capacity(1 + SALT_LEN + NONCE_LEN + ciphertext.len());
+    sealed.push(ENCRYPTION_FORMAT_VERSION);
+    sealed.extend_from_slice(&salt);
+    sealed.extend_from_slice(&nonce_bytes);
+    sealed.extend_from_slice(&ciphertext);
+    Ok(sealed)
+}
+
+/// 解密 [`encrypt_at_rest`] 产生的数据；口令错误或数据被篡改时（GCM标签校验
+/// 失败）返回 `DiapError::InternalError`
+fn decrypt_at_rest(passphrase: &str, sealed: &[u8]) -> Result<Vec<u8>, DiapError> {
+    use aes_gcm::aead::{Aead, KeyInit};
+    use aes_gcm::{Aes256Gcm, Key, Nonce};
+
+    if sealed.len() < 1 + SALT_LEN + NONCE_LEN {
+        return Err(DiapError::InternalError("加密数据格式不正确".to_string()));
+    }
+    if sealed[0] != ENCRYPTION_FORMAT_VERSION {
+        return Err(DiapError::InternalError(format!(
+            "不支持的加密格式版本: {}",
+            sealed[0]
+        )));
+    }
+
+    let salt = &sealed[1..1 + SALT_LEN];
+    let nonce_bytes = &sealed[1 + SALT_LEN..1 + SALT_LEN + NONCE_LEN];
+    let ciphertext = &sealed[1 + SALT_LEN + NONCE_LEN..];
+
+    let key_bytes = derive_encryption_key(passphrase, salt)?;
+    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key_bytes));
+    cipher
+        .decrypt(Nonce::from_slice(nonce_bytes), ciphertext)
+        .map_err(|_| DiapError::InternalError("解密失败：口令错误或数据已被篡改".to_string()))
+}
+
+/// 挑战-应答认证中，一次性挑战nonce的有效期（秒）
+const CHALLENGE_EXPIRY_SECONDS: i64 = 120;
+
+/// 备份文件格式版本号
+const BACKUP_FORMAT_VERSION: u8 = 1;
+
+/// 备份清单中的一条身份记录：完整的 [`AgentIdentity`]，以及（如果该身份
+/// 持久化了Ed25519私钥）其私钥字节，使恢复后无需重新注册即可签名
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+struct BackupEntry {
+    identity: AgentIdentity,
+    secret_key: Option<[u8; 32]>,
+}
+
+/// [`DiapIdentityManager::export_backup`]/[`DiapIdentityManager::restore_from_backup`]
+/// 之间交换的备份清单；整个清单序列化后用 [`encrypt_at_rest`] 密封
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+struct BackupManifest {
+    format_version: u8,
+    entries: Vec<BackupEntry>,
+}
+
+/// 一次性挑战：[`DiapIdentityManager::create_challenge`]签发、
+/// [`DiapIdentityManager::verify_identity`]消费，防止nonce被重放
+#[derive(Debug, Clone)]
+struct PendingChallenge {
+    nonce: [u8; 32],
+    expires_at: DateTime<Utc>,
+}
+
+/// 供 [`DiapIdentityManager::verify_identity`] 使用的身份证明
+///
+/// Ed25519身份（默认的本地密钥身份）用对挑战nonce的签名证明私钥持有；
+/// [`IdentityType::WebAuthn`] 身份改用FIDO2/passkey认证器给出的断言，
+/// 其挑战-应答状态由 [`DiapIdentityManager::begin_webauthn_auth`] 维护。
+pub enum IdentityProof<'a> {
+    /// 对 [`DiapIdentityManager::create_challenge`] 签发nonce的Ed25519签名
+    Ed25519Signature(&'a [u8]),
+    /// 对 [`DiapIdentityManager::begin_webauthn_auth`] 发起挑战的WebAuthn断言
+    WebAuthnAssertion(&'a PublicKeyCredential),
+}
+
+/// 设备类型，区分同一身份下绑定的不同客户端
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum DeviceType {
+    /// 移动端
+    Mobile,
+    /// 桌面端
+    Desktop,
+    /// 浏览器
+    Browser,
+    /// 无人值守的服务端
+    Server,
+}
+
+/// 绑定在某个身份下的一台设备
+///
+/// 设备持有自己的Ed25519密钥对，身份管理器只保存其公钥；设备列表整体由
+/// 身份的主私钥签名（见 [`AgentIdentity::device_list_signature`]），篡改
+/// 任意一条记录都会导致签名校验失败。
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Device {
+    /// 设备ID
+    pub device_id: String,
+    /// 设备的Ed25519公钥（base58编码）
+    pub device_public_key: String,
+    /// 设备类型
+    pub device_type: DeviceType,
+    /// 设备加入身份的时间
+    pub added_at: DateTime<Utc>,
+}
+
+/// 对设备列表的规范字节表示求序列化，供身份对其签名、以及远端校验签名时使用
+///
+/// 使用JSON序列化即可：这里只需要同一份 `Vec<Device>` 始终产生同一段字节，
+/// 不要求跨语言的规范编码。
+fn canonical_device_list_bytes(devices: &[Device]) -> Result<Vec<u8>, DiapError> {
+    serde_json::to_vec(devices)
+        .map_err(|e| DiapError::InternalError(format!("序列化设备列表失败: {}", e)))
+}
+
+/// [`DiapIdentityManager::get_inbound_keys_for_device`] 的返回值
+///
+/// 远端收到后可调用 [`Self::verify`] 独立核实：设备确实出现在签名所覆盖的
+/// 设备列表中，且该签名确实是身份公钥对这份列表的有效Ed25519签名——无需
+/// 额外的可信第三方。
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct InboundDeviceKeys {
+    /// 目标设备ID
+    pub device_id: String,
+    /// 目标设备的Ed25519公钥（base58编码）
+    pub device_public_key: String,
+    /// 所属身份的Ed25519公钥（base58编码）
+    pub identity_public_key: String,
+    /// 签名所覆盖的完整设备列表
+    pub devices: Vec<Device>,
+    /// 身份对 `devices` 的Ed25519签名
+    pub device_list_signature: Vec<u8>,
+}
+
+impl InboundDeviceKeys {
+    /// 独立验证：`devices` 中确实包含该设备，且 `device_list_signature`
+    /// 确实是 `identity_public_key` 对 `devices` 规范字节的有效签名
+    pub fn verify(&self) -> bool {
+        use ed25519_dalek::{Signature, Verifier, VerifyingKey};
+
+        let device_belongs = self.devices.iter().any(|device| {
+            device.device_id == self.device_id && device.device_public_key == self.device_public_key
+        });
+        if !device_belongs {
+            return false;
+        }
+
+        let Ok(device_list_bytes) = canonical_device_list_bytes(&self.devices) else {
+            return false;
+        };
+        let Ok(public_key_bytes) = bs58::decode(&self.identity_public_key).into_vec() else {
+            return false;
+        };
+        let Ok(public_key_bytes): Result<[u8; 32], _> = public_key_bytes.try_into() else {
+            return false;
+        };
+        let Ok(verifying_key) = VerifyingKey::from_bytes(&public_key_bytes) else {
+            return false;
+        };
+        let Ok(signature) = Signature::from_slice(&self.device_list_signature) else {
+            return false;
+        };
+
+        verifying_key.verify_strict(&device_list_bytes, &signature).is_ok()
+    }
+}
+
+/// 智能体身份信息
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct AgentIdentity {
+    /// 身份ID
+    pub id: String,
+    
+    /// 身份名称
+    pub name: String,
+    
+    /// 身份描述
+    pub description: Option<String>,
+    
+    /// 身份类型
+    pub identity_type: IdentityType,
+    
+    /// 公钥
+    pub public_key: String,
+    
+    /// 身份证明哈希
+    pub proof_hash: Option<String>,
+    
+    /// 身份状态
+    pub status: IdentityStatus,
+    
+    /// 创建时间
+    pub created_at: DateTime<Utc>,
+    
+    /// 更新时间
+    pub updated_at: DateTime<Utc>,
+    
+    /// 过期时间
+    pub expires_at: Option<DateTime<Utc>>,
+    
+    /// 身份权限
+    pub permissions: IdentityPermissions,
+
+    /// 元数据
+    pub metadata: serde_json::Value,
+
+    /// 绑定在该身份下的设备列表
+    #[serde(default)]
+    pub devices: Vec<Device>,
+
+    /// 身份主私钥对 `devices` 规范字节的Ed25519签名；`None`表示尚未注册过
+    /// 任何设备
+    #[serde(default)]
+    pub device_list_signature: Option<Vec<u8>>,
+
+    /// `identity_type` 为 [`IdentityType::WebAuthn`] 时，该身份注册的
+    /// FIDO2/passkey凭据；其余身份类型下始终为 `None`
+    #[serde(default)]
+    pub webauthn_credential: Option<Passkey>,
+}
+
+/// DIAP身份管理器
+pub struct DiapIdentityManager {
+    /// 配置
+    config: DiapConfig,
+    
+    /// 当前身份
+    current_identity: Arc<RwLock<Option<AgentIdentity>>>,
+    
+    /// 已知身份缓存
+    known_identities: Arc<RwLock<HashMap<String, AgentIdentity>>>,
+
+    /// 待应答的挑战-应答认证nonce，按身份ID索引
+    pending_challenges: Arc<RwLock<HashMap<String, PendingChallenge>>>,
+
+    /// 进行中的WebAuthn注册状态，按（临时分配的）身份ID索引，供
+    /// [`Self::finish_webauthn_registration`]消费
+    pending_webauthn_registrations: Arc<RwLock<HashMap<String, PasskeyRegistration>>>,
+
+    /// 进行中的WebAuthn认证状态，按身份ID索引，供
+    /// [`Self::finish_webauthn_auth`]消费
+    pending_webauthn_auths: Arc<RwLock<HashMap<String, PasskeyAuthentication>>>,
+
+    /// DIAP SDK管理器
+    #[allow(dead_code)]
+    diap_manager: Option<diap_rs_sdk::UniversalNoirManager>,
+}
+
+impl DiapIdentityManager {
+    /// 创建新的身份管理器
+    pub async fn new(config: DiapConfig) -> Result<Self, DiapError> {
+        // 验证配置
+        config.validate()?;
+        
+        // 初始化DIAP SDK管理器
+        let diap_manager = if config.proof.enable_zkp {
+            match diap_rs_sdk::UniversalNoirManager::new().await {
+                Ok(manager) => {
+                    log::info!("DIAP Noir管理器初始化成功");
+                    Some(manager)
+                }
+                Err(e) => {
+                    log::warn!("DIAP Noir管理器初始化失败: {}, 将使用简化模式", e);
+                    None
+                }
+            }
+        } else {
+            None
+        };
+        
+        // 创建管理器实例
+        let manager = Self {
+            config,
+            current_identity: Arc::new(RwLock::new(None)),
+            known_identities: Arc::new(RwLock::new(HashMap::new())),
+            pending_challenges: Arc::new(RwLock::new(HashMap::new())),
+            pending_webauthn_registrations: Arc::new(RwLock::new(HashMap::new())),
+            pending_webauthn_auths: Arc::new(RwLock::new(HashMap::new())),
+            diap_manager,
+        };
+        
+        // 加载现有身份
+        manager.load_identities().await?;
+        
+        Ok(manager)
+    }
+    
+    /// 注册新身份
+    pub async fn register_identity(&self, name: &str, description: Option<&str>) -> Result<AgentIdentity, DiapError> {
+        log::info!("开始注册新身份: {}", name);
+        
+        // 检查是否已存在同名身份
+        {
+            let identities = self.known_identities.read().await;
+            for identity in identities.values() {
+                if identity.name == name && identity.status != IdentityStatus::Revoked {
+                    return Err(DiapError::RegistrationFailed(format!("身份名称 '{}' 已存在", name)));
+                }
+            }
+        }
+        
+        // 生成身份ID
+        let identity_id = self.config.get_identity_id();
+        
+        // 生成密钥对
+        use ed25519_dalek::{SigningKey, SecretKey};
+        use rand::rngs::OsRng;
+        use rand::RngCore;
+        
+        let mut csprng = OsRng;
+        let mut secret_bytes = [0u8; 32];
+        csprng.fill_bytes(&mut secret_bytes);
+        
+        let secret_key = SecretKey::from(secret_bytes);
+        let signing_key = SigningKey::from(&secret_key);
+        let public_key = bs58::encode(signing_key.verifying_key().as_bytes()).into_string();
+        
+        // 创建身份证明
+        let proof_hash = if self.config.proof.enable_zkp {
+            match self.generate_identity_proof(&identity_id, &public_key).await {
+                Ok(hash) => {
+                    log::info!("身份证明生成成功: {}", hash);
+                    Some(hash)
+                }
+                Err(e) => {
+                    log::warn!("身份证明生成失败: {}, 将继续使用无证明身份", e);
+                    None
+                }
+            }
+        } else {
+            None
+        };
+        
+        // 创建身份对象
+        let now = Utc::now();
+        let identity = AgentIdentity {
+            id: identity_id.clone(),
+            name: name.to_string(),
+            description: description.map(|s| s.to_string()),
+            identity_type: self.config.identity.identity_type,
+            public_key,
+            proof_hash: proof_hash.clone(),
+            status: IdentityStatus::Registered,
+            created_at: now,
+            updated_at: now,
+            expires_at: self.config.identity.expires_in.map(|seconds| now + chrono::Duration::seconds(seconds as i64)),
+            permissions: self.config.identity.permissions.clone(),
+            metadata: serde_json::json!({
+                "registered_with_proof": proof_hash.is_some(),
+                "config_version": "0.2.11",
+                "platform": std::env::consts::OS,
+            }),
+            devices: Vec::new(),
+            device_list_signature: None,
+            webauthn_credential: None,
+        };
+        
+        // 保存身份
+        self.save_identity(&identity).await?;
+
+        // 持久化私钥（加密落盘），供后续挑战-应答认证调用 sign_with_identity 使用
+        self.save_secret_key(&identity_id, &signing_key).await?;
+
+        // 更新缓存
+        {
+            let mut identities = self.known_identities.write().await;
+            identities.insert(identity_id.clone(), identity.clone());
+        }
+        
+        // 设置为当前身份
+        {
+            let mut current = self.current_identity.write().await;
+            *current = Some(identity.clone());
+        }
+        
+        log::info!("身份注册成功: {} ({})", name, identity_id);
+        
+        Ok(identity)
+    }
+    
+    /// 为指定身份发起一次挑战：生成随机32字节nonce并记录短期有效期，
+    /// 调用方应使用 [`Self::sign_with_identity`]（或持有对应私钥的客户端）
+    /// 对该nonce签名，再把签名传给 [`Self::verify_identity`] 完成认证
+    pub async fn create_challenge(&self, identity_id: &str) -> Result<[u8; 32], DiapError> {
+        {
+            let identities = self.known_identities.read().await;
+            if !identities.contains_key(identity_id) {
+                return Err(DiapError::AuthenticationFailed(format!("身份不存在: {}", identity_id)));
+            }
+        }
+
+        use rand::rngs::OsRng;
+        use rand::RngCore;
+
+        let mut nonce = [0u8; 32];
+        OsRng.fill_bytes(&mut nonce);
+
+        let challenge = PendingChallenge {
+            nonce,
+            expires_at: Utc::now() + chrono::Duration::seconds(CHALLENGE_EXPIRY_SECONDS),
+        };
+
+        let mut pending = self.pending_challenges.write().await;
+        pending.insert(identity_id.to_string(), challenge);
+
+        Ok(nonce)
+    }
+
+    /// 用身份持久化的私钥对给定消息签名，典型用法是对
+    /// [`Self::create_challenge`] 签发的挑战nonce签名以证明私钥持有
+    pub async fn sign_with_identity(&self, identity_id: &str, message: &[u8]) -> Result<Vec<u8>, DiapError> {
+        use ed25519_dalek::Signer;
+
+        let signing_key = self.load_secret_key(identity_id).await?;
+        let signature = signing_key.sign(message);
+        Ok(signature.to_bytes().to_vec())
+    }
+
+    /// 验证身份
+    ///
+    /// `proof` 为 [`IdentityProof::Ed25519Signature`] 时，应为调用方对
+    /// [`Self::create_challenge`] 签发的挑战nonce的Ed25519签名；该挑战一旦被
+    /// 用于验证（无论成败）就会被消费，无法重放。为
+    /// [`IdentityProof::WebAuthnAssertion`] 时，对应身份必须是
+    /// [`IdentityType::WebAuthn`]，断言会针对 [`Self::begin_webauthn_auth`]
+    /// 发起、尚未消费的挑战状态进行校验。不提供证明时跳过校验，保留原有的
+    /// "仅检查身份存在且未过期/撤销"行为。
+    pub async fn verify_identity(&self, identity_id: &str, proof: Option<IdentityProof<'_>>) -> Result<AuthResult, DiapError> {
+        log::debug!("验证身份: {}", identity_id);
+
+        // 获取身份信息
+        let identity = {
+            let identities = self.known_identities.read().await;
+            identities.get(identity_id).cloned()
+        };
+
+        let identity = match identity {
+            Some(id) => id,
+            None => return Err(DiapError::AuthenticationFailed(format!("身份不存在: {}", identity_id))),
+        };
+
+        // 检查身份状态
+        match identity.status {
+            IdentityStatus::Revoked => return Err(DiapError::AuthenticationFailed("身份已被撤销".to_string())),
+            IdentityStatus::Expired => return Err(DiapError::AuthenticationFailed("身份已过期".to_string())),
+            _ => {}
+        }
+
+        // 检查过期时间
+        if let Some(expires_at) = identity.expires_at {
+            if Utc::now() > expires_at {
+                return Err(DiapError::AuthenticationFailed("身份已过期".to_string()));
+            }
+        }
+
+        // 验证挑战-应答证明（如果提供了证明）：一次性取出（消费）对应的挑战
+        // 状态，确认未过期，再按证明类型分别校验
+        let proof_valid = match proof {
+            Some(IdentityProof::Ed25519Signature(sig_bytes)) => {
+                let challenge = {
+                    let mut pending = self.pending_challenges.write().await;
+                    pending.remove(identity_id)
+                };
+
+                match challenge {
+                    Some(challenge) if Utc::now() <= challenge.expires_at => {
+                        self.verify_signature(&identity, &challenge.nonce, sig_bytes)
+                    }
+                    Some(_) => {
+                        log::warn!("挑战已过期: {}", identity_id);
+                        false
+                    }
+                    None => {
+                        log::warn!("没有待验证的挑战（可能已被使用过或从未发起）: {}", identity_id);
+                        false
+                    }
+                }
+            }
+            Some(IdentityProof::WebAuthnAssertion(credential)) => {
+                self.verify_webauthn_assertion(identity_id, &identity, credential).await?
+            }
+            None => {
+                // 没有提供证明，跳过证明验证
+                true
+            }
+        };
+
+        // 创建验证结果
+        let auth_result = AuthResult {
+            authenticated: proof_valid,
+            identity_id: identity.id.clone(),
+            proof_hash: identity.proof_hash.clone(),
+            timestamp: Utc::now().timestamp(),
+            metadata: serde_json::json!({
+                "identity_name": identity.name,
+                "identity_type": format!("{:?}", identity.identity_type),
+                "proof_verified": proof_valid,
+                "permissions": identity.permissions,
+            }),
+        };
+        
+        if auth_result.authenticated {
+            log::info!("身份验证成功: {}", identity_id);
+        } else {
+            log::warn!("身份验证失败: {}", identity_id);
+        }
+        
+        Ok(auth_result)
+    }
+
+    /// 构建一个 `Webauthn` 校验器实例；配置（`rp_id`/`rp_origin`）有误时报错
+    fn build_webauthn(&self) -> Result<Webauthn, DiapError> {
+        let rp_origin = url::Url::parse(&self.config.webauthn.rp_origin)
+            .map_err(|e| DiapError::ConfigError(format!("WebAuthn来源URL无效: {}", e)))?;
+
+        WebauthnBuilder::new(&self.config.webauthn.rp_id, &rp_origin)
+            .map_err(|e| DiapError::ConfigError(format!("WebAuthn依赖方配置无效: {}", e)))?
+            .rp_name(&self.config.webauthn.rp_name)
+            .build()
+            .map_err(|e| DiapError::ConfigError(format!("WebAuthn校验器构建失败: {}", e)))
+    }
+
+    /// 发起WebAuthn身份注册：为新身份分配ID，向FIDO2认证器请求创建一个
+    /// 新的passkey凭据。返回身份ID（后续 [`Self::finish_webauthn_registration`]
+    /// 需要用到）和需要转发给客户端认证器的创建挑战。
+    pub async fn begin_webauthn_registration(&self, name: &str) -> Result<(String, CreationChallengeResponse), DiapError> {
+        log::info!("开始WebAuthn身份注册: {}", name);
+
+        let webauthn = self.build_webauthn()?;
+        let identity_id = self.config.get_identity_id();
+        let user_unique_id = uuid::Uuid::new_v4();
+
+        let (challenge, registration_state) = webauthn
+            .start_passkey_registration(user_unique_id, name, name, None)
+            .map_err(|e| DiapError::RegistrationFailed(format!("发起WebAuthn注册失败: {}", e)))?;
+
+        let mut pending = self.pending_webauthn_registrations.write().await;
+        pending.insert(identity_id.clone(), registration_state);
+
+        Ok((identity_id, challenge))
+    }
+
+    /// 完成WebAuthn身份注册：校验认证器返回的凭据，创建并持久化一个
+    /// [`IdentityType::WebAuthn`] 身份
+    pub async fn finish_webauthn_registration(
+        &self,
+        identity_id: &str,
+        name: &str,
+        description: Option<&str>,
+        credential: &RegisterPublicKeyCredential,
+    ) -> Result<AgentIdentity, DiapError> {
+        let registration_state = {
+            let mut pending = self.pending_webauthn_registrations.write().await;
+            pending.remove(identity_id)
+        }
+        .ok_or_else(|| DiapError::RegistrationFailed(format!("没有待完成的WebAuthn注册: {}", identity_id)))?;
+
+        let webauthn = self.build_webauthn()?;
+        let passkey = webauthn
+            .finish_passkey_registration(credential, &registration_state)
+            .map_err(|e| DiapError::RegistrationFailed(format!("完成WebAuthn注册失败: {}", e)))?;
+
+        let now = Utc::now();
+        let identity = AgentIdentity {
+            id: identity_id.to_string(),
+            name: name.to_string(),
+            description: description.map(|s| s.to_string()),
+            identity_type: IdentityType::WebAuthn,
+            public_key: String::new(),
+            proof_hash: None,
+            status: IdentityStatus::Registered,
+            created_at: now,
+            updated_at: now,
+            expires_at: self.config.identity.expires_in.map(|seconds| now + chrono::Duration::seconds(seconds as i64)),
+            permissions: self.config.identity.permissions.clone(),
+            metadata: serde_json::json!({
+                "config_version": "0.2.11",
+                "platform": std::env::consts::OS,
+            }),
+            devices: Vec::new(),
+            device_list_signature: None,
+            webauthn_credential: Some(passkey),
+        };
+
+        self.save_identity(&identity).await?;
+
+        {
+            let mut identities = self.known_identities.write().await;
+            identities.insert(identity_id.to_string(), identity.clone());
+        }
+
+        log::info!("WebAuthn身份注册成功: {} ({})", name, identity_id);
+        Ok(identity)
+    }
+
+    /// 发起WebAuthn身份认证：取出该身份注册的passkey凭据，向认证器请求
+    /// 一次新的签名挑战
+    pub async fn begin_webauthn_auth(&self, identity_id: &str) -> Result<RequestChallengeResponse, DiapError> {
+        let passkey = {
+            let identities = self.known_identities.read().await;
+            let identity = identities
+                .get(identity_id)
+                .ok_or_else(|| DiapError::AuthenticationFailed(format!("身份不存在: {}", identity_id)))?;
+
+            identity
+                .webauthn_credential
+                .clone()
+                .ok_or_else(|| DiapError::AuthenticationFailed(format!("身份未注册WebAuthn凭据: {}", identity_id)))?
+        };
+
+        let webauthn = self.build_webauthn()?;
+        let (challenge, auth_state) = webauthn
+            .start_passkey_authentication(&[passkey])
+            .map_err(|e| DiapError::AuthenticationFailed(format!("发起WebAuthn认证失败: {}", e)))?;
+
+        let mut pending = self.pending_webauthn_auths.write().await;
+        pending.insert(identity_id.to_string(), auth_state);
+
+        Ok(challenge)
+    }
+
+    /// 完成WebAuthn身份认证：校验认证器返回的断言是否匹配
+    /// [`Self::begin_webauthn_auth`] 发起的挑战状态，并在成功时把凭据的
+    /// 签名计数器更新落盘（防止克隆凭据重放）
+    async fn verify_webauthn_assertion(
+        &self,
+        identity_id: &str,
+        identity: &AgentIdentity,
+        credential: &PublicKeyCredential,
+    ) -> Result<bool, DiapError> {
+        if identity.identity_type != IdentityType::WebAuthn {
+            log::warn!("身份不是WebAuthn类型，拒绝WebAuthn断言: {}", identity_id);
+            return Ok(false);
+        }
+
+        let auth_state = {
+            let mut pending = self.pending_webauthn_auths.write().await;
+            pending.remove(identity_id)
+        };
+
+        let Some(auth_state) = auth_state else {
+            log::warn!("没有待验证的WebAuthn挑战（可能已被使用过或从未发起）: {}", identity_id);
+            return Ok(false);
+        };
+
+        let webauthn = self.build_webauthn()?;
+        let auth_result = match webauthn.finish_passkey_authentication(credential, &auth_state) {
+            Ok(result) => result,
+            Err(e) => {
+                log::warn!("WebAuthn断言验证失败: {}", e);
+                return Ok(false);
+            }
+        };
+
+        if let Some(mut passkey) = identity.webauthn_credential.clone() {
+            if passkey.update_credential(&auth_result).unwrap_or(false) {
+                let mut updated_identity = identity.clone();
+                updated_identity.webauthn_credential = Some(passkey);
+                updated_identity.updated_at = Utc::now();
+                self.save_identity(&updated_identity).await?;
+
+                let mut identities = self.known_identities.write().await;
+                identities.insert(identity_id.to_string(), updated_identity);
+            }
+        }
+
+        Ok(true)
+    }
+
+    /// 完成WebAuthn身份认证，返回认证结果；等价于把 `credential` 包装成
+    /// [`IdentityProof::WebAuthnAssertion`] 传给 [`Self::verify_identity`]
+    pub async fn finish_webauthn_auth(&self, identity_id: &str, credential: &PublicKeyCredential) -> Result<AuthResult, DiapError> {
+        self.verify_identity(identity_id, Some(IdentityProof::WebAuthnAssertion(credential))).await
+    }
+
+    /// 获取当前身份
+    pub async fn get_current_identity(&self) -> Option<AgentIdentity> {
+        let current = self.current_identity.read().await;
+        current.clone()
+    }
+    
+    /// 设置当前身份
+    pub async fn set_current_identity(&self, identity_id: &str) -> Result<(), DiapError> {
+        let identity = {
+            let identities = self.known_identities.read().await;
+            identities.get(identity_id).cloned()
+        };
+        
+        match identity {
+            Some(id) => {
+                let mut current = self.current_identity.write().await;
+                *current = Some(id);
+                log::info!("当前身份已设置为: {}", identity_id);
+                Ok(())
+            }
+            None => Err(DiapError::AuthenticationFailed(format!("身份不存在: {}", identity_id))),
+        }
+    }
+    
+    /// 获取所有已知身份
+    pub async fn get_all_identities(&self) -> Vec<AgentIdentity> {
+        let identities = self.known_identities.read().await;
+        identities.values().cloned().collect()
+    }
+
+    /// 按ID获取单个已知身份
+    pub async fn get_identity(&self, identity_id: &str) -> Option<AgentIdentity> {
+        let identities = self.known_identities.read().await;
+        identities.get(identity_id).cloned()
+    }
+    
+    /// 生成身份证明
+    async fn generate_identity_proof(&self, identity_id: &str, public_key: &str) -> Result<String, DiapError> {
+        // 这里应该调用DIAP SDK生成零知识证明
+        // 由于DIAP SDK的具体API需要进一步研究，这里先实现一个简化版本
+        
+        // 创建证明数据
+        let proof_data = serde_json::json!({
+            "identity_id": identity_id,
+            "public_key": public_key,
+            "timestamp": Utc::now().timestamp(),
+            "nonce": rand::random::<u64>(),
+        });
+        
+        // 计算证明哈希
+        let proof_string = serde_json::to_string(&proof_data)
+            .map_err(|e| DiapError::ProofGenerationFailed(format!("序列化证明数据失败: {}", e)))?;
+        
+        use sha2::Digest;
+        let mut hasher = sha2::Sha256::new();
+        hasher.update(proof_string.as_bytes());
+        let hash = hasher.finalize();
+        let proof_hash = hex::encode(hash);
+        
+        Ok(proof_hash)
+    }
+    
+    /// 获取加密静态数据所用的口令；启用加密但未配置口令时报错
+    fn encryption_passphrase(&self) -> Result<&str, DiapError> {
+        self.config
+            .storage
+            .encryption_key
+            .as_deref()
+            .ok_or_else(|| DiapError::ConfigError("启用加密存储但未提供加密密钥".to_string()))
+    }
+
+    /// 用身份的公钥验证其对给定消息的Ed25519签名
+    fn verify_signature(&self, identity: &AgentIdentity, message: &[u8], signature: &[u8]) -> bool {
+        use ed25519_dalek::{Signature, Verifier, VerifyingKey};
+
+        let public_key_bytes = match bs58::decode(&identity.public_key).into_vec() {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                log::warn!("公钥base58解码失败: {}", e);
+                return false;
+            }
+        };
+        let public_key_bytes: [u8; 32] = match public_key_bytes.try_into() {
+            Ok(bytes) => bytes,
+            Err(_) => {
+                log::warn!("公钥长度不正确");
+                return false;
+            }
+        };
+        let verifying_key = match VerifyingKey::from_bytes(&public_key_bytes) {
+            Ok(key) => key,
+            Err(e) => {
+                log::warn!("公钥格式不正确: {}", e);
+                return false;
+            }
+        };
+        let signature = match Signature::from_slice(signature) {
+            Ok(sig) => sig,
+            Err(e) => {
+                log::warn!("签名格式不正确: {}", e);
+                return false;
+            }
+        };
+
+        verifying_key.verify_strict(message, &signature).is_ok()
+    }
+
+    /// 用身份的公钥验证其对给定消息的Ed25519签名，供跨模块（如网络层的
+    /// 消息鉴权）复用，无需各自重新实现公钥解码与签名校验
+    pub fn verify_message_signature(&self, identity: &AgentIdentity, message: &[u8], signature: &[u8]) -> bool {
+        self.verify_signature(identity, message, signature)
+    }
+
+    /// 密钥库中某身份私钥文件的路径
+    fn keystore_path(&self, identity_id: &str) -> PathBuf {
+        self.config.storage.identity_store_path.join(format!("{}.key", identity_id))
+    }
+
+    /// 把Ed25519私钥用AES-256-GCM加密后写入密钥库（私钥始终加密存储，
+    /// 不受 `storage.enable_encryption` 开关影响）
+    async fn save_secret_key(&self, identity_id: &str, signing_key: &ed25519_dalek::SigningKey) -> Result<(), DiapError> {
+        std::fs::create_dir_all(&self.config.storage.identity_store_path)
+            .map_err(|e| DiapError::InternalError(format!("创建存储目录失败: {}", e)))?;
+
+        let passphrase = self.encryption_passphrase()?;
+        let sealed = encrypt_at_rest(passphrase, &signing_key.to_bytes())?;
+
+        std::fs::write(self.keystore_path(identity_id), sealed)
+            .map_err(|e| DiapError::InternalError(format!("写入密钥库失败: {}", e)))?;
+
+        Ok(())
+    }
+
+    /// 从密钥库读取并解密某身份的Ed25519私钥
+    async fn load_secret_key(&self, identity_id: &str) -> Result<ed25519_dalek::SigningKey, DiapError> {
+        let path = self.keystore_path(identity_id);
+        let sealed = std::fs::read(&path)
+            .map_err(|e| DiapError::InternalError(format!("读取密钥库失败: {}", e)))?;
+
+        let passphrase = self.encryption_passphrase()?;
+        let secret_bytes = decrypt_at_rest(passphrase, &sealed)?;
+        let seed: [u8; 32] = secret_bytes
+            .try_into()
+            .map_err(|_| DiapError::InternalError("密钥库中的私钥长度不正确".to_string()))?;
+
+        Ok(ed25519_dalek::SigningKey::from_bytes(&seed))
+    }
+
+    /// 保存身份到存储
+    async fn save_identity(&self, identity: &AgentIdentity) -> Result<(), DiapError> {
+        // 确保存储目录存在
+        std::fs::create_dir_all(&self.config.storage.identity_store_path)
+            .map_err(|e| DiapError::InternalError(format!("创建存储目录失败: {}", e)))?;
+
+        // 构建文件路径
+        let file_path = self.config.storage.identity_store_path.join(format!("{}.json", identity.id));
+
+        // 序列化身份数据
+        let identity_json = serde_json::to_string_pretty(identity)
+            .map_err(|e| DiapError::InternalError(format!("序列化身份数据失败: {}", e)))?;
+
+        // 加密数据（如果启用）：Argon2id派生密钥 + AES-256-GCM认证加密
+        let data_to_write = if self.config.storage.enable_encryption {
+            let passphrase = self.encryption_passphrase()?;
+            encrypt_at_rest(passphrase, identity_json.as_bytes())?
+        } else {
+            identity_json.into_bytes()
+        };
+
+        // 写入文件
+        std::fs::write(&file_path, data_to_write)
+            .map_err(|e| DiapError::InternalError(format!("写入身份文件失败: {}", e)))?;
+
+        log::debug!("身份已保存到: {:?}", file_path);
+
+        Ok(())
+    }
+
+    /// 从存储加载身份
+    async fn load_identities(&self) -> Result<(), DiapError> {
+        let identity_dir = &self.config.storage.identity_store_path;
+        
+        // 检查目录是否存在
+        if !identity_dir.exists() {
+            log::info!("身份存储目录不存在，将创建新目录: {:?}", identity_dir);
+            return Ok(());
+        }
+        
+        // 读取目录中的所有身份文件
+        let entries = std::fs::read_dir(identity_dir)
+            .map_err(|e| DiapError::InternalError(format!("读取身份目录失败: {}", e)))?;
+        
+        let mut loaded_count = 0;
+        for entry in entries {
+            let entry = entry.map_err(|e| DiapError::InternalError(format!("读取目录条目失败: {}", e)))?;
+            let path = entry.path();
+            
+            // 只处理.json文件
+            if path.extension().map(|ext| ext == "json").unwrap_or(false) {
+                match self.load_identity_from_file(&path).await {
+                    Ok(identity) => {
+                        let mut identities = self.known_identities.write().await;
+                        identities.insert(identity.id.clone(), identity);
+                        loaded_count += 1;
+                    }
+                    Err(e) => {
+                        log::warn!("加载身份文件失败 {:?}: {}", path, e);
+                    }
+                }
+            }
+        }
+        
+        log::info!("已加载 {} 个身份", loaded_count);
+        
+        Ok(())
+    }
+    
+    /// 从文件加载单个身份
+    async fn load_identity_from_file(&self, path: &PathBuf) -> Result<AgentIdentity, DiapError> {
+        // 读取文件内容
+        let raw = std::fs::read(path)
+            .map_err(|e| DiapError::InternalError(format!("读取身份文件失败: {}", e)))?;
+
+        // 解密数据（如果启用加密）
+        let decrypted_content = if self.config.storage.enable_encryption {
+            let passphrase = self.encryption_passphrase()?;
+            decrypt_at_rest(passphrase, &raw)?
+        } else {
+            raw
+        };
+
+        // 解析身份数据
+        let identity: AgentIdentity = serde_json::from_slice(&decrypted_content)
+            .map_err(|e| DiapError::InternalError(format!("解析身份数据失败: {}", e)))?;
+
+        Ok(identity)
+    }
+    
+    /// 更新身份状态；撤销身份（[`IdentityStatus::Revoked`]）会级联撤销该
+    /// 身份绑定的所有设备——清空设备列表并对空列表重新签名，使旧的设备
+    /// 列表签名无法再被拿去证明任何设备仍然合法
+    pub async fn update_identity_status(&self, identity_id: &str, status: IdentityStatus) -> Result<(), DiapError> {
+        let mut identities = self.known_identities.write().await;
+
+        if let Some(identity) = identities.get_mut(identity_id) {
+            identity.status = status;
+            identity.updated_at = Utc::now();
+
+            if status == IdentityStatus::Revoked && !identity.devices.is_empty() {
+                let revoked_count = identity.devices.len();
+                identity.devices.clear();
+
+                let device_list_bytes = canonical_device_list_bytes(&identity.devices)?;
+                let signature = self.sign_with_identity(identity_id, &device_list_bytes).await?;
+                identity.device_list_signature = Some(signature);
+
+                log::info!("身份撤销已级联撤销 {} 台设备: {}", revoked_count, identity_id);
+            }
+
+            // 保存更新
+            self.save_identity(identity).await?;
+
+            log::info!("身份状态已更新: {} -> {:?}", identity_id, status);
+            Ok(())
+        } else {
+            Err(DiapError::AuthenticationFailed(format!("身份不存在: {}", identity_id)))
+        }
+    }
+
+    /// 为指定身份注册一台新设备：设备在本地生成自己的Ed25519密钥对，这里
+    /// 只记录其公钥，并用身份的主私钥对更新后的设备列表重新签名，使设备
+    /// 列表的任何篡改都能被检测出来
+    pub async fn register_device(
+        &self,
+        identity_id: &str,
+        device_public_key: String,
+        device_type: DeviceType,
+    ) -> Result<Device, DiapError> {
+        let device = Device {
+            device_id: uuid::Uuid::new_v4().to_string(),
+            device_public_key,
+            device_type,
+            added_at: Utc::now(),
+        };
+
+        let mut identities = self.known_identities.write().await;
+        let identity = identities
+            .get_mut(identity_id)
+            .ok_or_else(|| DiapError::AuthenticationFailed(format!("身份不存在: {}", identity_id)))?;
+
+        if identity.status == IdentityStatus::Revoked {
+            return Err(DiapError::AuthenticationFailed("身份已被撤销，无法注册设备".to_string()));
+        }
+
+        identity.devices.push(device.clone());
+        let device_list_bytes = canonical_device_list_bytes(&identity.devices)?;
+        let signature = self.sign_with_identity(identity_id, &device_list_bytes).await?;
+        identity.device_list_signature = Some(signature);
+        identity.updated_at = Utc::now();
+
+        self.save_identity(identity).await?;
+
+        log::info!("设备已注册: {} -> {}", identity_id, device.device_id);
+        Ok(device)
+    }
+
+    /// 从身份的设备列表中移除一台设备，并对更新后的列表重新签名
+    pub async fn revoke_device(&self, identity_id: &str, device_id: &str) -> Result<(), DiapError> {
+        let mut identities = self.known_identities.write().await;
+        let identity = identities
+            .get_mut(identity_id)
+            .ok_or_else(|| DiapError::AuthenticationFailed(format!("身份不存在: {}", identity_id)))?;
+
+        let original_len = identity.devices.len();
+        identity.devices.retain(|device| device.device_id != device_id);
+        if identity.devices.len() == original_len {
+            return Err(DiapError::AuthenticationFailed(format!("设备不存在: {}", device_id)));
+        }
+
+        let device_list_bytes = canonical_device_list_bytes(&identity.devices)?;
+        let signature = self.sign_with_identity(identity_id, &device_list_bytes).await?;
+        identity.device_list_signature = Some(signature);
+        identity.updated_at = Utc::now();
+
+        self.save_identity(identity).await?;
+
+        log::info!("设备已撤销: {} -> {}", identity_id, device_id);
+        Ok(())
+    }
+
+    /// 获取某个身份当前绑定的设备列表
+    pub async fn get_device_list(&self, identity_id: &str) -> Result<Vec<Device>, DiapError> {
+        let identities = self.known_identities.read().await;
+        identities
+            .get(identity_id)
+            .map(|identity| identity.devices.clone())
+            .ok_or_else(|| DiapError::AuthenticationFailed(format!("身份不存在: {}", identity_id)))
+    }
+
+    /// 获取某台设备的inbound key资料：设备公钥，连同身份对整份设备列表的
+    /// 签名——远端用身份的公钥核实该签名确实覆盖了包含这台设备的列表，
+    /// 即可确认这台设备合法地属于该身份
+    pub async fn get_inbound_keys_for_device(
+        &self,
+        identity_id: &str,
+        device_id: &str,
+    ) -> Result<InboundDeviceKeys, DiapError> {
+        let identities = self.known_identities.read().await;
+        let identity = identities
+            .get(identity_id)
+            .ok_or_else(|| DiapError::AuthenticationFailed(format!("身份不存在: {}", identity_id)))?;
+
+        let device = identity
+            .devices
+            .iter()
+            .find(|device| device.device_id == device_id)
+            .ok_or_else(|| DiapError::AuthenticationFailed(format!("设备不存在: {}", device_id)))?;
+
+        let device_list_signature = identity
+            .device_list_signature
+            .clone()
+            .ok_or_else(|| DiapError::InternalError("设备列表尚未签名".to_string()))?;
+
+        Ok(InboundDeviceKeys {
+            device_id: device.device_id.clone(),
+            device_public_key: device.device_public_key.clone(),
+            identity_public_key: identity.public_key.clone(),
+            devices: identity.devices.clone(),
+            device_list_signature,
+        })
+    }
+    
+    /// 更新身份权限
+    pub async fn update_identity_permissions(&self, identity_id: &str, permissions: IdentityPermissions) -> Result<(), DiapError> {
+        let mut identities = self.known_identities.write().await;
+        
+        if let Some(identity) = identities.get_mut(identity_id) {
+            identity.permissions = permissions;
+            identity.updated_at = Utc::now();
+            
+            // 保存更新
+            self.save_identity(identity).await?;
+            
+            log::info!("身份权限已更新: {}", identity_id);
+            Ok(())
+        } else {
+            Err(DiapError::AuthenticationFailed(format!("身份不存在: {}", identity_id)))
+        }
+    }
+
+    /// 把给定身份（包括其加密持久化的私钥、设备列表、权限和身份证明哈希）
+    /// 导出为一份可移动的备份：清单用Argon2id从 `backup_secret` 派生出的
+    /// AES-256密钥密封，与 [`save_identity`](Self::save_identity) 使用的
+    /// 私钥存储加密机制相同
+    pub async fn export_backup(&self, identity_ids: &[String], backup_secret: &str) -> Result<Vec<u8>, DiapError> {
+        let identities = self.known_identities.read().await;
+
+        let mut entries = Vec::with_capacity(identity_ids.len());
+        for identity_id in identity_ids {
+            let identity = identities
+                .get(identity_id)
+                .ok_or_else(|| DiapError::AuthenticationFailed(format!("身份不存在: {}", identity_id)))?
+                .clone();
+
+            let secret_key = match self.load_secret_key(identity_id).await {
+                Ok(signing_key) => Some(signing_key.to_bytes()),
+                Err(_) => None,
+            };
+
+            entries.push(BackupEntry { identity, secret_key });
+        }
+        drop(identities);
+
+        let manifest = BackupManifest {
+            format_version: BACKUP_FORMAT_VERSION,
+            entries,
+        };
+
+        let plaintext = serde_json::to_vec(&manifest)
+            .map_err(|e| DiapError::InternalError(format!("序列化备份清单失败: {}", e)))?;
+
+        log::info!("已导出 {} 个身份的备份", manifest.entries.len());
+        encrypt_at_rest(backup_secret, &plaintext)
+    }
+
+    /// 从 [`Self::export_backup`] 产生的备份中恢复身份
+    ///
+    /// 每条记录在合并前都会校验：已撤销的身份会被跳过；带有设备列表签名的
+    /// 身份会重新验证该签名，签名不合法的记录同样被跳过。已存在于
+    /// `known_identities` 中的身份只有在备份里的版本 `updated_at` 更新时才
+    /// 会被覆盖，避免用一份陈旧的备份覆盖掉本机更新过的身份。返回实际合并
+    /// 的身份数量。
+    pub async fn restore_from_backup(&self, bytes: &[u8], backup_secret: &str) -> Result<usize, DiapError> {
+        let plaintext = decrypt_at_rest(backup_secret, bytes)?;
+
+        let manifest: BackupManifest = serde_json::from_slice(&plaintext)
+            .map_err(|e| DiapError::InternalError(format!("解析备份清单失败: {}", e)))?;
+
+        if manifest.format_version != BACKUP_FORMAT_VERSION {
+            return Err(DiapError::InternalError(format!(
+                "不支持的备份格式版本: {}",
+                manifest.format_version
+            )));
+        }
+
+        let mut merged_count = 0;
+        for entry in manifest.entries {
+            let identity = entry.identity;
+
+            if identity.status == IdentityStatus::Revoked {
+                log::warn!("跳过已撤销的身份备份: {}", identity.id);
+                continue;
+            }
+
+            if let Some(signature) = &identity.device_list_signature {
+                let device_list_bytes = match canonical_device_list_bytes(&identity.devices) {
+                    Ok(bytes) => bytes,
+                    Err(_) => continue,
+                };
+                if !self.verify_signature(&identity, &device_list_bytes, signature) {
+                    log::warn!("跳过设备列表签名校验失败的身份备份: {}", identity.id);
+                    continue;
+                }
+            }
+
+            {
+                let mut identities = self.known_identities.write().await;
+                let should_merge = match identities.get(&identity.id) {
+                    Some(existing) => identity.updated_at > existing.updated_at,
+                    None => true,
+                };
+                if !should_merge {
+                    log::info!("跳过本地已有更新版本的身份: {}", identity.id);
+                    continue;
+                }
+                identities.insert(identity.id.clone(), identity.clone());
+            }
+
+            self.save_identity(&identity).await?;
+            if let Some(secret_bytes) = entry.secret_key {
+                let signing_key = ed25519_dalek::SigningKey::from_bytes(&secret_bytes);
+                self.save_secret_key(&identity.id, &signing_key).await?;
+            }
+
+            merged_count += 1;
+        }
+
+        log::info!("已从备份合并 {} 个身份", merged_count);
+        Ok(merged_count)
+    }
+}
+
+impl Default for DiapIdentityManager {
+    fn default() -> Self {
+        // 注意：这个默认实现主要用于测试，实际使用时应通过new()方法创建
+        let config = DiapConfig::default();
+        
+        // 由于new()是async的，我们不能在这里调用它
+        // 所以创建一个简化版本
+        Self {
+            config,
+            current_identity: Arc::new(RwLock::new(None)),
+            known_identities: Arc::new(RwLock::new(HashMap::new())),
+            pending_challenges: Arc::new(RwLock::new(HashMap::new())),
+            pending_webauthn_registrations: Arc::new(RwLock::new(HashMap::new())),
+            pending_webauthn_auths: Arc::new(RwLock::new(HashMap::new())),
+            diap_manager: None,
+        }
+    }
+}
+
+impl std::fmt::Debug for DiapIdentityManager {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DiapIdentityManager")
+            .field("config", &self.config)
+            .field("current_identity", &self.current_identity)
+            .field("known_identities", &self.known_identities)
+            .field("pending_challenges", &self.pending_challenges)
+            .field("pending_webauthn_registrations", &"<PasskeyRegistration>")
+            .field("pending_webauthn_auths", &"<PasskeyAuthentication>")
+            .field("diap_manager", &"<UniversalNoirManager>")
+            .finish()
+    }
+}
+
+impl Clone for DiapIdentityManager {
+    fn clone(&self) -> Self {
+        Self {
+            config: self.config.clone(),
+            current_identity: Arc::clone(&self.current_identity),
+            known_identities: Arc::clone(&self.known_identities),
+            pending_challenges: Arc::clone(&self.pending_challenges),
+            pending_webauthn_registrations: Arc::clone(&self.pending_webauthn_registrations),
+            pending_webauthn_auths: Arc::clone(&self.pending_webauthn_auths),
+            diap_manager: None, // 不能克隆 UniversalNoirManager，所以设置为 None
+        }
+    }
+}
+
+/// [`PolicyGatedStore::seal_secret`] 时记录的策略：只有当身份在解封时仍然
+/// 满足这份策略——未被撤销、未过期、`config_version`不低于密封时的版本、
+/// 权限不低于密封时记录的权限——才允许解封
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct SealPolicy {
+    /// 密封时要求身份至少达到的 `config_version`（按`.`分隔的数字段逐段比较）
+    pub min_config_version: String,
+    /// 解封时身份必须仍然具备的权限：策略里为`true`的字段，身份当前也必须
+    /// 为`true`，否则视为权限被降级
+    pub required_permissions: IdentityPermissions,
+}
+
+/// [`PolicyGatedStore::seal_secret`] 的返回值：被策略绑定、用身份密钥派生出
+/// 的AES-256密钥加密过的数据
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct SealedBlob {
+    identity_id: String,
+    policy: SealPolicy,
+    sealed: Vec<u8>,
+}
+
+/// 把Ed25519私钥字节转换为封存密钥：对私钥字节和一个固定的领域分隔符求
+/// SHA-256，避免封存密钥材料与签名用途的私钥本身产生可被滥用的关联
+fn derive_seal_key(signing_key: &ed25519_dalek::SigningKey) -> [u8; 32] {
+    use sha2::{Digest, Sha256};
+
+    let mut hasher = Sha256::new();
+    hasher.update(signing_key.to_bytes());
+    hasher.update(b"diap-policy-gated-seal");
+    hasher.finalize().into()
+}
+
+/// 用给定的AES-256密钥加密数据：`[nonce(12字节)][密文+GCM标签]`；密钥本身
+/// 已经是派生好的材料，不需要再走Argon2id
+fn seal_with_key(key_bytes: &[u8; 32], plaintext: &[u8]) -> Vec<u8> {
+    use aes_gcm::aead::{Aead, KeyInit};
+    use aes_gcm::{Aes256Gcm, Key, Nonce};
+    use rand::rngs::OsRng;
+    use rand::RngCore;
+
+    let mut nonce_bytes = [0u8; NONCE_LEN];
+    OsRng.fill_bytes(&mut nonce_bytes);
+
+    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key_bytes));
+    let ciphertext = cipher
+        .encrypt(Nonce::from_slice(&nonce_bytes), plaintext)
+        .expect("AES-256-GCM加密不应失败");
+
+    let mut sealed = Vec::with_capacity(NONCE_LEN + ciphertext.len());
+    sealed.extend_from_slice(&nonce_bytes);
+    sealed.extend_from_slice(&ciphertext);
+    sealed
+}
+
+/// 解密 [`seal_with_key`] 产生的数据；密钥错误或数据被篡改（GCM标签校验
+/// 失败）时返回 `DiapError::AuthenticationFailed`
+fn unseal_with_key(key_bytes: &[u8; 32], sealed: &[u8]) -> Result<Vec<u8>, DiapError> {
+    use aes_gcm::aead::{Aead, KeyInit};
+    use aes_gcm::{Aes256Gcm, Key, Nonce};
+
+    if sealed.len() < NONCE_LEN {
+        return Err(DiapError::AuthenticationFailed("封存数据格式不正确".to_string()));
+    }
+
+    let (nonce_bytes, ciphertext) = sealed.split_at(NONCE_LEN);
+    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key_bytes));
+    cipher
+        .decrypt(Nonce::from_slice(nonce_bytes), ciphertext)
+        .map_err(|_| DiapError::AuthenticationFailed("解封失败：身份密钥不匹配或数据已被篡改".to_string()))
+}
+
+/// 把`.`分隔的版本号解析成数字段，便于逐段比较（`Vec<u64>`的字典序比较
+/// 恰好就是版本号比较语义）；无法解析为数字的段按`0`处理
+fn parse_version(version: &str) -> Vec<u64> {
+    version.split('.').map(|part| part.parse().unwrap_or(0)).collect()
+}
+
+/// 给定身份当前的权限是否仍然满足一份策略所要求的权限：策略里每个为
+/// `true`的权限位，身份当前也必须为`true`；身份自身的权限若已过期同样
+/// 视为不满足
+fn permissions_satisfy(current: &IdentityPermissions, required: &IdentityPermissions) -> bool {
+    if let Some(expires_at) = current.expires_at {
+        if Utc::now().timestamp() > expires_at {
+            return false;
+        }
+    }
+
+    (!required.can_create_oracle_data || current.can_create_oracle_data)
+        && (!required.can_vote_in_consensus || current.can_vote_in_consensus)
+        && (!required.can_manage_agents || current.can_manage_agents)
+        && (!required.can_access_sensitive_data || current.can_access_sensitive_data)
+}
+
+/// 层叠在身份存储之上的密封子系统
+///
+/// 灵感来自DICE/版本化策略下的密钥封存：一个身份可以把任意数据和一份
+/// [`SealPolicy`] 绑定后封存，之后只有当它在解封时仍然满足这份策略，才能
+/// 拿回数据。这样即使身份的密钥库本身没有丢失，一旦身份被撤销或权限被
+/// 降级，它也无法再解封自己权限更高时封存的数据。
+pub struct PolicyGatedStore {
+    identity_manager: Arc<DiapIdentityManager>,
+}
+
+impl PolicyGatedStore {
+    /// 创建一个层叠在给定身份管理器之上的策略封存存储
+    pub fn new(identity_manager: Arc<DiapIdentityManager>) -> Self {
+        Self { identity_manager }
+    }
+
+    /// 用指定身份的私钥派生出的密钥封存一段数据，并记录解封时必须满足的
+    /// 策略
+    pub async fn seal_secret(&self, identity_id: &str, data: &[u8], policy: SealPolicy) -> Result<SealedBlob, DiapError> {
+        let signing_key = self.identity_manager.load_secret_key(identity_id).await?;
+        let key_bytes = derive_seal_key(&signing_key);
+        let sealed = seal_with_key(&key_bytes, data);
+
+        Ok(SealedBlob {
+            identity_id: identity_id.to_string(),
+            policy,
+            sealed,
+        })
+    }
+
+    /// 解封一段数据；身份必须存在、未撤销、未过期，且当前的
+    /// `config_version`与权限不低于封存时记录的策略，否则拒绝解封
+    pub async fn unseal_secret(&self, identity_id: &str, blob: &SealedBlob) -> Result<Vec<u8>, DiapError> {
+        if blob.identity_id != identity_id {
+            return Err(DiapError::AuthenticationFailed("封存数据不属于该身份".to_string()));
+        }
+
+        let identity = self
+            .identity_manager
+            .get_identity(identity_id)
+            .await
+            .ok_or_else(|| DiapError::AuthenticationFailed(format!("身份不存在: {}", identity_id)))?;
+
+        if identity.status == IdentityStatus::Revoked {
+            return Err(DiapError::AuthenticationFailed("身份已被撤销，拒绝解封".to_string()));
+        }
+        if let Some(expires_at) = identity.expires_at {
+            if Utc::now() > expires_at {
+                return Err(DiapError::AuthenticationFailed("身份已过期，拒绝解封".to_string()));
+            }
+        }
+
+        let current_version = identity
+            .metadata
+            .get("config_version")
+            .and_then(|value| value.as_str())
+            .unwrap_or("0");
+        if parse_version(current_version) < parse_version(&blob.policy.min_config_version) {
+            return Err(DiapError::AuthenticationFailed(
+                "身份版本已被降级，低于封存时要求的最低版本".to_string(),
+            ));
+        }
+
+        if !permissions_satisfy(&identity.permissions, &blob.policy.required_permissions) {
+            return Err(DiapError::AuthenticationFailed(
+                "身份权限已被降级，不再满足封存时的策略".to_string(),
+            ));
+        }
+
+        let signing_key = self.identity_manager.load_secret_key(identity_id).await?;
+        let key_bytes = derive_seal_key(&signing_key);
+        unseal_with_key(&key_bytes, &blob.sealed)
+    }
+}
\ No newline at end of file
@@ -85,7 +85,7 @@ pub enum IdentityStatus {
 }
 
 /// 身份权限
-#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
 pub struct IdentityPermissions {
     /// 是否可以创建预言机数据
     pub can_create_oracle_data: bool,
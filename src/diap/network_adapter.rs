@@ -105,18 +105,46 @@ pub enum MessageType {
 pub struct DiapNetworkAdapter {
     /// 配置
     config: DiapConfig,
-    
+
     /// 身份管理器引用
     identity_manager: Arc<super::identity_manager::DiapIdentityManager>,
-    
+
     /// 网络节点缓存
     network_nodes: Arc<RwLock<HashMap<String, NetworkNode>>>,
-    
+
     /// 消息队列
     message_queue: Arc<RwLock<Vec<NetworkMessage>>>,
-    
+
     /// 是否正在运行
     is_running: Arc<RwLock<bool>>,
+
+    /// 因签名缺失或校验失败而被丢弃的入站消息计数
+    dropped_invalid_signatures: Arc<RwLock<usize>>,
+}
+
+/// 消息中需要参与签名的字段，按固定顺序序列化以保证签名方与验证方
+/// 计算出的字节完全一致
+#[derive(serde::Serialize)]
+struct SignableMessage<'a> {
+    message_id: &'a str,
+    sender_id: &'a str,
+    receiver_id: &'a Option<String>,
+    message_type: MessageType,
+    payload: &'a serde_json::Value,
+    timestamp: i64,
+}
+
+/// 计算一条消息参与签名的规范字节（不含`signature`字段本身）
+fn signable_bytes(message: &NetworkMessage) -> Vec<u8> {
+    let signable = SignableMessage {
+        message_id: &message.message_id,
+        sender_id: &message.sender_id,
+        receiver_id: &message.receiver_id,
+        message_type: message.message_type,
+        payload: &message.payload,
+        timestamp: message.timestamp,
+    };
+    serde_json::to_vec(&signable).unwrap_or_default()
 }
 
 impl DiapNetworkAdapter {
@@ -133,8 +161,9 @@ impl DiapNetworkAdapter {
             network_nodes: Arc::new(RwLock::new(HashMap::new())),
             message_queue: Arc::new(RwLock::new(Vec::new())),
             is_running: Arc::new(RwLock::new(false)),
+            dropped_invalid_signatures: Arc::new(RwLock::new(0)),
         };
-        
+
         Ok(adapter)
     }
     
@@ -245,15 +274,21 @@ impl DiapNetworkAdapter {
     }
     
     /// 发送网络消息
-    pub async fn send_message(&self, message: NetworkMessage) -> Result<(), DiapError> {
+    pub async fn send_message(&self, mut message: NetworkMessage) -> Result<(), DiapError> {
         log::debug!("发送网络消息: {} -> {:?}", message.sender_id, message.receiver_id);
-        
+
         // 验证发送者身份
         let current_identity = self.identity_manager.get_current_identity().await;
         if current_identity.is_none() || current_identity.as_ref().unwrap().id != message.sender_id {
             return Err(DiapError::AuthenticationFailed("发送者身份验证失败".to_string()));
         }
-        
+
+        // 用发送者身份的Ed25519私钥对消息的规范字节签名，使接收方能够
+        // 独立校验消息确实来自其声明的sender_id，而不仅仅是连接层已认证
+        let signable = signable_bytes(&message);
+        let signature = self.identity_manager.sign_with_identity(&message.sender_id, &signable).await?;
+        message.signature = Some(base64::encode(signature));
+
         // 根据消息类型处理
         match message.message_type {
             MessageType::Heartbeat => {
@@ -275,20 +310,67 @@ impl DiapNetworkAdapter {
     }
     
     /// 接收网络消息
+    ///
+    /// 在返回给调用方之前校验每条消息的Ed25519签名；签名缺失或校验失败
+    /// 的消息会被丢弃并计入 [`Self::dropped_invalid_signature_count`]，
+    /// 不会混入正常消息流
     pub async fn receive_messages(&self, limit: usize) -> Vec<NetworkMessage> {
         let mut queue = self.message_queue.write().await;
-        
+
         if queue.is_empty() {
             return Vec::new();
         }
-        
+
         let messages: Vec<NetworkMessage> = if limit == 0 || limit >= queue.len() {
             queue.drain(..).collect()
         } else {
             queue.drain(..limit).collect()
         };
-        
-        messages
+        drop(queue);
+
+        let mut verified = Vec::with_capacity(messages.len());
+        for message in messages {
+            match self.verify_message_signature(&message).await {
+                Ok(true) => verified.push(message),
+                Ok(false) => {
+                    log::warn!("丢弃签名缺失或无效的网络消息: {}", message.message_id);
+                    *self.dropped_invalid_signatures.write().await += 1;
+                }
+                Err(e) => {
+                    log::warn!("校验网络消息签名时出错，已丢弃: {} ({})", message.message_id, e);
+                    *self.dropped_invalid_signatures.write().await += 1;
+                }
+            }
+        }
+
+        verified
+    }
+
+    /// 校验一条消息的`signature`字段确实是发送者身份对其规范字节的
+    /// 有效Ed25519签名；签名缺失、发送者身份未知或签名格式错误均视为
+    /// 校验失败（返回`Ok(false)`），不会中断调用方的处理流程
+    pub async fn verify_message_signature(&self, message: &NetworkMessage) -> Result<bool, DiapError> {
+        let Some(signature_b64) = &message.signature else {
+            return Ok(false);
+        };
+
+        let identity = match self.identity_manager.get_identity(&message.sender_id).await {
+            Some(identity) => identity,
+            None => return Ok(false),
+        };
+
+        let signature_bytes = match base64::decode(signature_b64) {
+            Ok(bytes) => bytes,
+            Err(_) => return Ok(false),
+        };
+
+        let signable = signable_bytes(message);
+        Ok(self.identity_manager.verify_message_signature(&identity, &signable, &signature_bytes))
+    }
+
+    /// 因签名缺失或校验失败而被丢弃的入站消息累计数
+    pub async fn dropped_invalid_signature_count(&self) -> usize {
+        *self.dropped_invalid_signatures.read().await
     }
     
     /// 获取网络节点列表
@@ -437,6 +519,7 @@ impl DiapNetworkAdapter {
             connected_nodes,
             active_connections: connected_nodes,
             last_check: chrono::Utc::now().timestamp(),
+            dropped_invalid_signatures: self.dropped_invalid_signature_count().await,
         }
     }
 }
@@ -458,6 +541,9 @@ pub struct NetworkStatus {
     
     /// 最后检查时间
     pub last_check: i64,
+
+    /// 因签名缺失或校验失败而被丢弃的入站消息累计数
+    pub dropped_invalid_signatures: usize,
 }
 
 impl Default for DiapNetworkAdapter {
@@ -472,6 +558,7 @@ impl Default for DiapNetworkAdapter {
             network_nodes: Arc::new(RwLock::new(HashMap::new())),
             message_queue: Arc::new(RwLock::new(Vec::new())),
             is_running: Arc::new(RwLock::new(false)),
+            dropped_invalid_signatures: Arc::new(RwLock::new(0)),
         }
     }
 }
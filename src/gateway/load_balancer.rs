@@ -0,0 +1,351 @@
+use crate::reputation::algorithms::ReputationAlgorithms;
+use crate::reputation::ReputationConfig;
+use anyhow::{anyhow, Result};
+use rand::Rng;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+/// 网关负载均衡器的配置
+#[derive(Debug, Clone)]
+pub struct GatewayLoadBalancerConfig {
+    /// 指数加权移动平均的新样本权重（越大越偏向最近的观测）
+    pub ewma_alpha: f64,
+    /// 过载率越过该阈值即触发熔断（状态转为 [`CircuitState::Open`]）
+    pub overload_ejection_threshold: f64,
+    /// 熔断打开后，需要经过多少次 [`GatewayLoadBalancer::tick`] 才进入半开探测
+    pub open_cooldown_ticks: u64,
+    /// 半开状态下允许发出的探测请求数量
+    pub half_open_probe_count: u32,
+    /// 计算响应时间信誉增量时使用的期望响应时间（毫秒）
+    pub expected_response_time_ms: u64,
+    /// 计算可用性信誉增量时使用的期望可用性
+    pub expected_uptime: f64,
+    /// 每次 `tick` 对过载率施加的衰减系数（无新样本时逐渐恢复）
+    pub idle_decay_factor: f64,
+}
+
+impl Default for GatewayLoadBalancerConfig {
+    fn default() -> Self {
+        Self {
+            ewma_alpha: 0.2,
+            overload_ejection_threshold: 0.3,
+            open_cooldown_ticks: 10,
+            half_open_probe_count: 3,
+            expected_response_time_ms: 200,
+            expected_uptime: 0.99,
+            idle_decay_factor: 0.95,
+        }
+    }
+}
+
+/// 熔断器状态
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CircuitState {
+    /// 正常路由
+    Closed,
+    /// 已熔断，暂停路由，记录熔断发生的 tick 以便计算冷却时间
+    Open { opened_at_tick: u64 },
+    /// 半开探测：放行有限数量的请求以检验主机是否恢复
+    HalfOpen { probes_remaining: u32 },
+}
+
+/// 调用方对一次 `get_host` 结果的汇报
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GatewayOutcome {
+    /// 请求成功，附带实际响应时间（毫秒）
+    Success { response_time_ms: u64 },
+    /// 主机返回了过载信号（例如限流、队列已满）
+    Overload,
+    /// 请求失败（超时、连接错误等）
+    Failure,
+}
+
+/// `get_host` 返回的不透明句柄，持有者必须通过 `report` 汇报一次结果
+#[derive(Debug, Clone)]
+pub struct HostHandle {
+    /// 被选中的主机 ID
+    pub host_id: String,
+    /// 发出该句柄时的 tick，用于诊断
+    issued_at_tick: u64,
+}
+
+struct GatewayHost {
+    tier: String,
+    staked_amount: u64,
+    reputation_score: f64,
+    ewma_success_rate: f64,
+    ewma_overload_rate: f64,
+    ewma_failure_rate: f64,
+    active_requests: u32,
+    circuit_state: CircuitState,
+}
+
+/// 过载感知的自适应网关负载均衡器
+pub struct GatewayLoadBalancer {
+    hosts: Arc<RwLock<HashMap<String, GatewayHost>>>,
+    algorithms: ReputationAlgorithms,
+    config: GatewayLoadBalancerConfig,
+    tick_counter: Arc<RwLock<u64>>,
+}
+
+impl GatewayLoadBalancer {
+    /// 创建新的网关负载均衡器
+    pub fn new(config: GatewayLoadBalancerConfig, reputation_config: ReputationConfig) -> Self {
+        Self {
+            hosts: Arc::new(RwLock::new(HashMap::new())),
+            algorithms: ReputationAlgorithms::new(reputation_config),
+            config,
+            tick_counter: Arc::new(RwLock::new(0)),
+        }
+    }
+
+    /// 注册一个网关主机，初始信誉分使用 `reputation_config.initial_credit`
+    pub async fn register_host(
+        &self,
+        host_id: impl Into<String>,
+        tier: impl Into<String>,
+        staked_amount: u64,
+    ) {
+        let mut hosts = self.hosts.write().await;
+        hosts.insert(
+            host_id.into(),
+            GatewayHost {
+                tier: tier.into(),
+                staked_amount,
+                reputation_score: self.algorithms.config.initial_credit,
+                ewma_success_rate: 1.0,
+                ewma_overload_rate: 0.0,
+                ewma_failure_rate: 0.0,
+                active_requests: 0,
+                circuit_state: CircuitState::Closed,
+            },
+        );
+    }
+
+    /// 按加权随机选择出一个指定层级（tier）下可用的主机
+    ///
+    /// 权重 = `calculate_voting_weight(信誉分, 质押量)` × 负载倒数
+    /// （`1 / (1 + 当前活跃请求数)`）。处于 [`CircuitState::Open`] 的主机会被
+    /// 跳过；处于 [`CircuitState::HalfOpen`] 的主机只在仍有探测配额时参与。
+    pub async fn get_host(&self, tier: &str) -> Result<HostHandle> {
+        let tick = *self.tick_counter.read().await;
+        let mut hosts = self.hosts.write().await;
+
+        let mut candidates: Vec<(String, f64)> = Vec::new();
+        for (host_id, host) in hosts.iter() {
+            if host.tier != tier {
+                continue;
+            }
+
+            let eligible = match host.circuit_state {
+                CircuitState::Closed => true,
+                CircuitState::Open { .. } => false,
+                CircuitState::HalfOpen { probes_remaining } => probes_remaining > 0,
+            };
+            if !eligible {
+                continue;
+            }
+
+            let voting_weight = self
+                .algorithms
+                .calculate_voting_weight(host.reputation_score.max(0.0), host.staked_amount);
+            let load_factor = 1.0 / (1.0 + host.active_requests as f64);
+            let weight = (voting_weight * load_factor).max(f64::EPSILON);
+            candidates.push((host_id.clone(), weight));
+        }
+
+        if candidates.is_empty() {
+            return Err(anyhow!("层级 `{}` 下没有可用的网关主机", tier));
+        }
+
+        let total_weight: f64 = candidates.iter().map(|(_, w)| w).sum();
+        let mut roll = rand::thread_rng().gen_range(0.0..total_weight);
+        let mut chosen = candidates[0].0.clone();
+        for (host_id, weight) in &candidates {
+            if roll < *weight {
+                chosen = host_id.clone();
+                break;
+            }
+            roll -= weight;
+        }
+
+        let host = hosts
+            .get_mut(&chosen)
+            .ok_or_else(|| anyhow!("主机 `{}` 在加权选择后消失", chosen))?;
+        host.active_requests += 1;
+        if let CircuitState::HalfOpen { probes_remaining } = &mut host.circuit_state {
+            *probes_remaining = probes_remaining.saturating_sub(1);
+        }
+
+        Ok(HostHandle {
+            host_id: chosen,
+            issued_at_tick: tick,
+        })
+    }
+
+    /// 汇报一次 `get_host` 返回结果的处理情况，驱动 EWMA 更新、熔断状态
+    /// 转换与信誉反馈
+    pub async fn report(&self, handle: HostHandle, outcome: GatewayOutcome) -> Result<()> {
+        let tick = *self.tick_counter.read().await;
+        let mut hosts = self.hosts.write().await;
+        let host = hosts
+            .get_mut(&handle.host_id)
+            .ok_or_else(|| anyhow!("未知的网关主机: {}", handle.host_id))?;
+
+        host.active_requests = host.active_requests.saturating_sub(1);
+
+        let alpha = self.config.ewma_alpha;
+        let (success_sample, overload_sample, failure_sample) = match outcome {
+            GatewayOutcome::Success { .. } => (1.0, 0.0, 0.0),
+            GatewayOutcome::Overload => (0.0, 1.0, 0.0),
+            GatewayOutcome::Failure => (0.0, 0.0, 1.0),
+        };
+        host.ewma_success_rate = alpha * success_sample + (1.0 - alpha) * host.ewma_success_rate;
+        host.ewma_overload_rate = alpha * overload_sample + (1.0 - alpha) * host.ewma_overload_rate;
+        host.ewma_failure_rate = alpha * failure_sample + (1.0 - alpha) * host.ewma_failure_rate;
+
+        let reputation_delta = match outcome {
+            GatewayOutcome::Success { response_time_ms } => self.algorithms.calculate_response_time_delta(
+                self.config.expected_response_time_ms,
+                response_time_ms,
+                1.0,
+            ),
+            GatewayOutcome::Overload | GatewayOutcome::Failure => {
+                self.algorithms.calculate_availability_delta(self.config.expected_uptime, 0.0, 1.0)
+            }
+        };
+        host.reputation_score = (host.reputation_score + reputation_delta)
+            .clamp(self.algorithms.config.min_credit, self.algorithms.config.max_credit);
+
+        // 熔断状态机：过载率越过阈值即打开熔断；半开探测根据结果决定关闭还是重新打开
+        match host.circuit_state {
+            CircuitState::Closed => {
+                if host.ewma_overload_rate > self.config.overload_ejection_threshold {
+                    host.circuit_state = CircuitState::Open {
+                        opened_at_tick: tick,
+                    };
+                }
+            }
+            CircuitState::HalfOpen { probes_remaining } => {
+                if host.ewma_overload_rate <= self.config.overload_ejection_threshold {
+                    host.circuit_state = CircuitState::Closed;
+                } else if probes_remaining == 0 {
+                    host.circuit_state = CircuitState::Open {
+                        opened_at_tick: tick,
+                    };
+                }
+            }
+            CircuitState::Open { .. } => {}
+        }
+
+        Ok(())
+    }
+
+    /// 后台节拍：推进逻辑时钟、将冷却期满的熔断主机转入半开探测，并对空闲的
+    /// 过载率做轻微衰减，使暂时没有新请求的主机也能逐步恢复
+    pub async fn tick(&self) {
+        let mut tick_counter = self.tick_counter.write().await;
+        *tick_counter += 1;
+        let tick = *tick_counter;
+        drop(tick_counter);
+
+        let mut hosts = self.hosts.write().await;
+        for host in hosts.values_mut() {
+            host.ewma_overload_rate *= self.config.idle_decay_factor;
+
+            if let CircuitState::Open { opened_at_tick } = host.circuit_state {
+                if tick.saturating_sub(opened_at_tick) >= self.config.open_cooldown_ticks {
+                    host.circuit_state = CircuitState::HalfOpen {
+                        probes_remaining: self.config.half_open_probe_count,
+                    };
+                }
+            }
+        }
+    }
+
+    /// 查询某个主机当前的熔断状态（用于监控/调试）
+    pub async fn circuit_state(&self, host_id: &str) -> Option<CircuitState> {
+        self.hosts.read().await.get(host_id).map(|h| h.circuit_state)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn balancer() -> GatewayLoadBalancer {
+        GatewayLoadBalancer::new(GatewayLoadBalancerConfig::default(), ReputationConfig::default())
+    }
+
+    #[tokio::test]
+    async fn test_get_host_returns_registered_host_in_tier() {
+        let lb = balancer();
+        lb.register_host("gw-1", "light", 100).await;
+
+        let handle = lb.get_host("light").await.expect("应能选中主机");
+        assert_eq!(handle.host_id, "gw-1");
+    }
+
+    #[tokio::test]
+    async fn test_repeated_overload_trips_circuit_breaker() {
+        let lb = balancer();
+        lb.register_host("gw-1", "light", 100).await;
+
+        for _ in 0..20 {
+            let handle = lb.get_host("light").await.expect("熔断前应能选中主机");
+            lb.report(handle, GatewayOutcome::Overload).await.unwrap();
+        }
+
+        assert!(matches!(
+            lb.circuit_state("gw-1").await,
+            Some(CircuitState::Open { .. })
+        ));
+        assert!(lb.get_host("light").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_half_open_recovery_after_cooldown() {
+        let lb = balancer();
+        lb.register_host("gw-1", "light", 100).await;
+
+        for _ in 0..20 {
+            let handle = lb.get_host("light").await.expect("熔断前应能选中主机");
+            lb.report(handle, GatewayOutcome::Overload).await.unwrap();
+        }
+        assert!(matches!(
+            lb.circuit_state("gw-1").await,
+            Some(CircuitState::Open { .. })
+        ));
+
+        for _ in 0..lb.config.open_cooldown_ticks {
+            lb.tick().await;
+        }
+        assert!(matches!(
+            lb.circuit_state("gw-1").await,
+            Some(CircuitState::HalfOpen { .. })
+        ));
+
+        let handle = lb.get_host("light").await.expect("半开状态下应能放行探测");
+        lb.report(handle, GatewayOutcome::Success { response_time_ms: 50 })
+            .await
+            .unwrap();
+        assert_eq!(lb.circuit_state("gw-1").await, Some(CircuitState::Closed));
+    }
+
+    #[tokio::test]
+    async fn test_chronic_overload_reduces_reputation_score() {
+        let lb = balancer();
+        lb.register_host("gw-1", "light", 100).await;
+        let initial_score = lb.hosts.read().await.get("gw-1").unwrap().reputation_score;
+
+        for _ in 0..5 {
+            if let Ok(handle) = lb.get_host("light").await {
+                let _ = lb.report(handle, GatewayOutcome::Overload).await;
+            }
+        }
+
+        let final_score = lb.hosts.read().await.get("gw-1").unwrap().reputation_score;
+        assert!(final_score < initial_score);
+    }
+}
@@ -0,0 +1,17 @@
+//! 网关负载均衡模块
+//!
+//! 取代 [`crate::test::visualization::print_gateway_load`] 中打印的静态负载
+//! 描述，提供一个真实的、与信誉系统耦合的自适应负载均衡子系统：每次调用
+//! `get_host` 按加权随机选择出一个网关主机（权重由
+//! [`crate::reputation::algorithms::ReputationAlgorithms::calculate_voting_weight`]
+//! 与主机当前负载的倒数共同决定），调用方随后通过 `report` 汇报
+//! 成功/过载/失败结果。模块维护每个主机的指数加权成功率/过载率/失败率，
+//! 一旦过载率越过阈值即触发熔断（ejection），并通过半开探测逐步恢复；
+//! 观测到的行为会经由 `calculate_response_time_delta`/`calculate_availability_delta`
+//! 反馈回信誉分数，使长期过载的网关自动损失信誉与路由权重。
+
+mod load_balancer;
+
+pub use load_balancer::{
+    CircuitState, GatewayLoadBalancer, GatewayLoadBalancerConfig, GatewayOutcome, HostHandle,
+};
@@ -14,9 +14,10 @@ pub mod oracle_agent;
 pub mod reputation;
 pub mod consensus;
 pub mod blockchain;
+pub mod gateway;
 pub mod network;
 pub mod types;
-// // pub mod diap;
+pub mod diap;
 pub mod solana;
 pub mod test;
 pub mod zkp;
@@ -27,8 +28,9 @@ pub use oracle_agent::{OracleAgent, OracleAgentConfig, OracleDataType, OracleDat
 pub use reputation::{ReputationManager, ReputationScore, ReputationConfig, ReputationMetrics, ReputationUpdate};
 pub use consensus::{ConsensusEngine, ConsensusResult, SpectralFeatures, extract_spectral_features};
 pub use blockchain::{BlockchainAdapter, ChainType};
+pub use gateway::{GatewayLoadBalancer, GatewayLoadBalancerConfig, GatewayOutcome, HostHandle};
 pub use network::{NetworkManager, NetworkConfig, PeerInfo};
-// // pub use diap::{DiapConfig, DiapIdentityManager, AgentIdentity, DiapNetworkAdapter, DiapError, AuthResult, IdentityStatus};
+pub use diap::{DiapConfig, DiapIdentityManager, AgentIdentity, DiapNetworkAdapter, DiapError, AuthResult, IdentityStatus};
 pub use types::{NodeId, NodeInfo, Timestamp, current_timestamp, SystemError, NetworkMessage};
 pub use zkp::{ZkpGenerator, ZkpConfig, ZkProof, PublicInputs, PrivateInputs, CircuitInputs};
 pub use causal_graph::{CausalGraph, CausalGraphBuilder, GraphBuilderConfig, CausalEffect, Intervention, DoOperatorResult};
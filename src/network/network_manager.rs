@@ -1,9 +1,12 @@
 use crate::network::{PeerDiscovery, MessageHandler, Protocol};
 use anyhow::{Result, anyhow};
+use async_trait::async_trait;
+use rand::Rng;
 use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
-use tokio::sync::RwLock;
-use log::{info, warn, error};
+use tokio::sync::{mpsc, RwLock};
+use tokio::time::Duration;
+use log::{info, warn, error, debug};
 use serde::{Deserialize, Serialize};
 
 /// 网络管理器
@@ -22,6 +25,83 @@ pub struct NetworkManager {
     connections: Arc<RwLock<HashMap<String, ConnectionStatus>>>,
     /// 网络状态
     status: Arc<RwLock<NetworkStatus>>,
+    /// 入站消息队列（由底层传输投递）
+    inbound_tx: mpsc::Sender<(String, Vec<u8>)>,
+    inbound_rx: Arc<tokio::sync::Mutex<mpsc::Receiver<(String, Vec<u8>)>>>,
+    /// 出站消息队列（由调用方排队，事件循环统一发送）
+    outbound_tx: mpsc::Sender<(String, Vec<u8>)>,
+    outbound_rx: Arc<tokio::sync::Mutex<mpsc::Receiver<(String, Vec<u8>)>>>,
+    /// 节点发现定时器
+    discovery_timer: Arc<tokio::sync::Mutex<tokio::time::Interval>>,
+    /// 心跳定时器
+    heartbeat_timer: Arc<tokio::sync::Mutex<tokio::time::Interval>>,
+    /// 关闭信号
+    shutdown_tx: tokio::sync::watch::Sender<bool>,
+    shutdown_rx: tokio::sync::watch::Receiver<bool>,
+    /// 保留节点集合：始终被接纳，且不会被心跳/清理逻辑驱逐
+    reserved_nodes: Arc<RwLock<HashSet<String>>>,
+    /// 非保留节点的准入策略
+    non_reserved_peer_mode: Arc<RwLock<NonReservedPeerMode>>,
+    /// IP层面的连接准入过滤器
+    ip_filter: Arc<RwLock<IpFilter>>,
+    /// 连接强度状态机，取代单一的`is_running`布尔值
+    attachment_state: Arc<RwLock<AttachmentState>>,
+    /// 按连接类型注册的出站拨号处理器
+    transport_connect_handlers: Arc<RwLock<HashMap<ConnectionType, Arc<dyn ProtocolConnectHandler>>>>,
+    /// 按连接类型注册的入站接受处理器
+    transport_accept_handlers: Arc<RwLock<HashMap<ConnectionType, Arc<dyn ProtocolAcceptHandler>>>>,
+    /// 已通过注册传输建立的活跃连接对象，供`send_message`/入站转发使用
+    peer_connections: Arc<RwLock<HashMap<String, Arc<dyn PeerConnection>>>>,
+    /// UPnP/STUN发现得到的外部地址，`None`表示尚未发现或NAT穿透未启用
+    external_address: Arc<RwLock<Option<String>>>,
+    /// 发现外部地址时一并探测到的本机局域网IP
+    local_ip: Arc<RwLock<Option<String>>>,
+    /// 通过UPnP/IGD建立的端口映射，`stop()`时需要逐一撤销
+    upnp_mappings: Arc<RwLock<Vec<UpnpMapping>>>,
+    /// 每个对等连接最近一次带宽采样的字节计数/时间戳，用于心跳时计算速率增量
+    bandwidth_samples: Arc<RwLock<HashMap<String, BandwidthSample>>>,
+    /// DIAP身份管理器：配置后，`send_message`/`handle_received_message`会
+    /// 对消息负载做Ed25519签名与校验，而不仅仅依赖连接建立时的身份认证
+    identity_manager: Arc<RwLock<Option<Arc<crate::diap::DiapIdentityManager>>>>,
+}
+
+/// `identity_manager`启用时，`send_message`/`handle_received_message`用它
+/// 给原始负载加上一层Ed25519签名，而不是直接收发裸字节
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct SignedEnvelope {
+    /// 原始消息负载
+    payload: Vec<u8>,
+    /// 发送方身份对`payload`的Ed25519签名
+    signature: Vec<u8>,
+}
+
+/// 事件循环的下一个待处理事件
+///
+/// `next_action`-style 驱动会在入站队列、出站队列、发现定时器、
+/// 心跳定时器与关闭信号之间 `select`，保证所有连接状态变更都发生在
+/// 同一个地方，而不是分散在多个独立的 `RwLock` 写者里。
+#[derive(Debug, Clone)]
+pub enum NetworkEvent {
+    /// 收到一条入站消息
+    Inbound {
+        /// 发送方地址
+        peer_address: String,
+        /// 消息内容
+        message: Vec<u8>,
+    },
+    /// 有一条出站消息待发送
+    Outbound {
+        /// 目标地址
+        peer_address: String,
+        /// 消息内容
+        message: Vec<u8>,
+    },
+    /// 到达发现周期
+    Discovery,
+    /// 到达心跳周期
+    Heartbeat,
+    /// 收到关闭信号
+    Shutdown,
 }
 
 /// 网络配置
@@ -45,6 +125,28 @@ pub struct NetworkConfig {
     pub enable_relay: bool,
     /// 中继节点列表
     pub relay_nodes: Vec<String>,
+    /// 节点发现周期（秒）
+    pub discovery_interval_secs: u64,
+    /// 单次事件循环迭代允许处理的最大事件数（超出则让出调度器并立即重新排程）
+    pub event_budget_per_tick: usize,
+    /// 保留节点：即使连接预算已满也始终被接纳，且不会被心跳/清理逻辑驱逐
+    pub reserved_nodes: HashSet<String>,
+    /// 非保留节点的准入策略
+    pub non_reserved_peer_mode: NonReservedPeerMode,
+    /// 达到`AttachedWeak`所需的最少活跃连接数
+    pub attachment_weak_threshold: usize,
+    /// 达到`AttachedGood`所需的最少活跃连接数
+    pub attachment_good_threshold: usize,
+    /// 达到`AttachedStrong`所需的最少活跃连接数
+    pub attachment_strong_threshold: usize,
+    /// UPnP端口映射的租期（秒），到期前由心跳定时器驱动续租
+    pub upnp_lease_secs: u32,
+    /// `enable_nat_traversal`开启且UPnP/IGD不可用时使用的STUN式反射
+    /// 地址查询服务器列表（`host:port`）
+    pub stun_servers: Vec<String>,
+    /// 带宽速率指数加权移动平均（EWMA）的时间窗口（秒）；越大越平滑、
+    /// 对瞬时突发越不敏感
+    pub bandwidth_window_secs: u64,
 }
 
 impl Default for NetworkConfig {
@@ -62,10 +164,116 @@ impl Default for NetworkConfig {
             enable_nat_traversal: true,
             enable_relay: true,
             relay_nodes: vec![],
+            discovery_interval_secs: 30,
+            event_budget_per_tick: 64,
+            reserved_nodes: HashSet::new(),
+            non_reserved_peer_mode: NonReservedPeerMode::Accept,
+            attachment_weak_threshold: 1,
+            attachment_good_threshold: 4,
+            attachment_strong_threshold: 8,
+            upnp_lease_secs: 3600,
+            stun_servers: vec![
+                "stun.l.google.com:19302".to_string(),
+                "stun1.l.google.com:19302".to_string(),
+            ],
+            bandwidth_window_secs: 30,
         }
     }
 }
 
+/// 网络连接质量状态机，取代单一的`is_running`布尔值，用于表达预言机
+/// 法定人数参与所需的连接强度
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AttachmentState {
+    /// 没有任何活跃连接
+    Detached,
+    /// 正在拨号，但活跃连接数尚未达到`AttachedWeak`门槛
+    Attaching,
+    /// 活跃连接数达到`attachment_weak_threshold`
+    AttachedWeak,
+    /// 活跃连接数达到`attachment_good_threshold`
+    AttachedGood,
+    /// 活跃连接数达到`attachment_strong_threshold`
+    AttachedStrong,
+    /// 活跃连接数达到`max_connections`
+    FullyAttached,
+    /// 活跃连接数超过`max_connections`，应当优先淘汰非保留节点
+    OverAttached,
+    /// 正在`stop()`过程中主动断开所有连接
+    Detaching,
+}
+
+impl AttachmentState {
+    /// 用于"至少达到某强度"判断的顺序值；`Detaching`不代表连接强度，
+    /// 不参与该顺序
+    fn rank(self) -> u8 {
+        match self {
+            AttachmentState::Detached | AttachmentState::Detaching => 0,
+            AttachmentState::Attaching => 1,
+            AttachmentState::AttachedWeak => 2,
+            AttachmentState::AttachedGood => 3,
+            AttachmentState::AttachedStrong => 4,
+            AttachmentState::FullyAttached => 5,
+            AttachmentState::OverAttached => 6,
+        }
+    }
+
+    /// 当前状态是否达到或超过`floor`，供调用方判断"至少AttachedGood才
+    /// 参与预言机法定人数"这类门槛
+    pub fn is_at_least(self, floor: AttachmentState) -> bool {
+        self.rank() >= floor.rank()
+    }
+}
+
+/// 根据活跃连接数与运行状态计算应处的连接强度状态
+fn classify_attachment_state(active_connections: usize, config: &NetworkConfig, running: bool) -> AttachmentState {
+    if active_connections == 0 {
+        return if running { AttachmentState::Attaching } else { AttachmentState::Detached };
+    }
+
+    if active_connections > config.max_connections {
+        AttachmentState::OverAttached
+    } else if active_connections >= config.max_connections {
+        AttachmentState::FullyAttached
+    } else if active_connections >= config.attachment_strong_threshold {
+        AttachmentState::AttachedStrong
+    } else if active_connections >= config.attachment_good_threshold {
+        AttachmentState::AttachedGood
+    } else if active_connections >= config.attachment_weak_threshold {
+        AttachmentState::AttachedWeak
+    } else {
+        AttachmentState::Attaching
+    }
+}
+
+/// 某个对等连接最近一次带宽采样时的字节计数与时间戳，用于在下一次心跳
+/// 时通过增量计算瞬时速率
+#[derive(Debug, Clone, Copy)]
+struct BandwidthSample {
+    sampled_at: u64,
+    bytes_sent: u64,
+    bytes_received: u64,
+}
+
+/// 根据一段时间内的字节增量计算瞬时速率（KB/s）；`secs_elapsed`为0时
+/// （例如同一秒内重复采样）返回0，避免除零
+fn kbps_from_delta(bytes_delta: u64, secs_elapsed: u64) -> f64 {
+    if secs_elapsed == 0 {
+        return 0.0;
+    }
+    (bytes_delta as f64 / 1024.0) / secs_elapsed as f64
+}
+
+/// 用单极点IIR滤波器把瞬时速率并入指数加权移动平均，`window_secs`越大
+/// 平滑越强；`secs_elapsed >= window_secs`时直接采用瞬时值
+fn ewma_kbps(previous: f64, instantaneous: f64, secs_elapsed: u64, window_secs: u64) -> f64 {
+    if window_secs == 0 {
+        return instantaneous;
+    }
+    let alpha = (secs_elapsed as f64 / window_secs as f64).min(1.0);
+    previous + alpha * (instantaneous - previous)
+}
+
 /// 网络状态
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct NetworkStatus {
@@ -83,10 +291,19 @@ pub struct NetworkStatus {
     pub messages_received: u64,
     /// 发现的节点数
     pub discovered_peers: usize,
+    /// 入站连接数
+    pub inbound_connections: usize,
+    /// 出站连接数
+    pub outbound_connections: usize,
     /// 网络带宽（KB/s）
     pub network_bandwidth_kbps: f64,
     /// 最后错误
     pub last_error: Option<String>,
+    /// NAT穿透发现的外部地址（UPnP/IGD或STUN反射得到）
+    pub external_address: Option<String>,
+    /// 因签名缺失或Ed25519校验失败而被丢弃的入站消息累计数
+    /// （仅在配置了`identity_manager`时才会被统计）
+    pub dropped_unsigned_messages: u64,
 }
 
 /// 连接状态
@@ -106,14 +323,20 @@ pub struct ConnectionStatus {
     pub bytes_sent: u64,
     /// 接收字节数
     pub bytes_received: u64,
+    /// 发送速率的指数加权移动平均（KB/s），由心跳采样驱动
+    pub send_rate_kbps: f64,
+    /// 接收速率的指数加权移动平均（KB/s），由心跳采样驱动
+    pub recv_rate_kbps: f64,
     /// 最后错误
     pub last_error: Option<String>,
     /// 连接类型
     pub connection_type: ConnectionType,
+    /// 连接方向
+    pub direction: ConnectionDirection,
 }
 
 /// 连接类型
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum ConnectionType {
     /// 直接连接
     Direct,
@@ -125,12 +348,521 @@ pub enum ConnectionType {
     Other(String),
 }
 
+/// 传输层连接抽象：一条已建立的、具体协议无关的收发通道
+///
+/// `register_transport`注册的处理器返回该trait的实例后，`NetworkManager`
+/// 不再关心底层究竟是TCP、QUIC还是WebRTC，只通过这组方法收发字节
+#[async_trait]
+pub trait PeerConnection: Send + Sync {
+    /// 向对端发送一帧消息
+    async fn send(&self, message: &[u8]) -> Result<()>;
+    /// 等待并接收对端的下一帧消息
+    async fn recv(&self) -> Result<Vec<u8>>;
+    /// 主动关闭该连接
+    async fn close(&self) -> Result<()>;
+}
+
+/// 出站连接建立器：负责按`peer_address`拨号并返回建立好的连接
+///
+/// 部署方可以为`ConnectionType::Direct`/`Relay`/`WebRTC`各注册一个实现，
+/// 从而在不改动`NetworkManager`内部逻辑的前提下接入真实的QUIC、WebRTC
+/// 等传输
+#[async_trait]
+pub trait ProtocolConnectHandler: Send + Sync {
+    /// 拨号连接到`addr`
+    async fn connect(&self, addr: &str) -> Result<Box<dyn PeerConnection>>;
+}
+
+/// 入站连接接受器：处理落在该协议上的、由对端主动发起的连接
+#[async_trait]
+pub trait ProtocolAcceptHandler: Send + Sync {
+    /// 接受来自`addr`的入站连接
+    async fn accept(&self, addr: &str) -> Result<Box<dyn PeerConnection>>;
+}
+
+/// 连接方向
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ConnectionDirection {
+    /// 本地主动发起
+    Outbound,
+    /// 对方主动发起，本地接受
+    Inbound,
+}
+
+/// 非保留节点的准入策略
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum NonReservedPeerMode {
+    /// 在连接预算允许的情况下接受非保留节点
+    Accept,
+    /// 拒绝所有非保留节点，只允许`reserved_nodes`中的节点连接
+    Deny,
+}
+
+/// 一条CIDR规则，例如`10.0.0.0/8`或`2001:db8::/32`
+#[derive(Debug, Clone)]
+pub struct IpCidr {
+    network: std::net::IpAddr,
+    prefix_len: u8,
+}
+
+impl IpCidr {
+    /// 解析形如`ip/prefix`的CIDR字符串；没有`/prefix`时按主机地址处理
+    /// （等价于/32或/128）
+    pub fn parse(cidr: &str) -> Option<Self> {
+        let mut parts = cidr.splitn(2, '/');
+        let network: std::net::IpAddr = parts.next()?.parse().ok()?;
+        let max_prefix = match network {
+            std::net::IpAddr::V4(_) => 32,
+            std::net::IpAddr::V6(_) => 128,
+        };
+        let prefix_len = match parts.next() {
+            Some(p) => p.parse::<u8>().ok()?.min(max_prefix),
+            None => max_prefix,
+        };
+        Some(Self { network, prefix_len })
+    }
+
+    /// 判断给定地址是否落在这条CIDR规则覆盖的网段内
+    pub fn contains(&self, addr: &std::net::IpAddr) -> bool {
+        match (self.network, addr) {
+            (std::net::IpAddr::V4(net), std::net::IpAddr::V4(ip)) => {
+                let net_bits = u32::from(net);
+                let ip_bits = u32::from(*ip);
+                let mask = if self.prefix_len == 0 { 0 } else { u32::MAX << (32 - self.prefix_len) };
+                (net_bits & mask) == (ip_bits & mask)
+            }
+            (std::net::IpAddr::V6(net), std::net::IpAddr::V6(ip)) => {
+                let net_bits = u128::from(net);
+                let ip_bits = u128::from(*ip);
+                let mask = if self.prefix_len == 0 { 0 } else { u128::MAX << (128 - self.prefix_len) };
+                (net_bits & mask) == (ip_bits & mask)
+            }
+            _ => false,
+        }
+    }
+}
+
+/// IP层面的连接准入过滤器：拒绝列表优先于允许列表；允许列表为空时
+/// 视为"未配置白名单"，即不因此拒绝任何地址
+#[derive(Debug, Clone, Default)]
+pub struct IpFilter {
+    /// 允许名单；为空表示不限制
+    pub allow: Vec<IpCidr>,
+    /// 拒绝名单；命中即拒绝，优先级高于允许名单
+    pub deny: Vec<IpCidr>,
+}
+
+impl IpFilter {
+    /// 判断给定IP是否被允许连接
+    pub fn is_allowed(&self, addr: &std::net::IpAddr) -> bool {
+        if self.deny.iter().any(|rule| rule.contains(addr)) {
+            return false;
+        }
+        if self.allow.is_empty() {
+            return true;
+        }
+        self.allow.iter().any(|rule| rule.contains(addr))
+    }
+}
+
+/// 从对等节点地址里提取IP地址，用于`IpFilter`检查
+///
+/// 支持multiaddr风格地址（如`/ip4/1.2.3.4/tcp/4001/...`）以及
+/// `host:port`或裸IP字符串
+fn extract_peer_ip(peer_address: &str) -> Option<std::net::IpAddr> {
+    if peer_address.starts_with('/') {
+        let segments: Vec<&str> = peer_address.split('/').collect();
+        for window in segments.windows(2) {
+            if (window[0] == "ip4" || window[0] == "ip6") && !window[1].is_empty() {
+                if let Ok(ip) = window[1].parse() {
+                    return Some(ip);
+                }
+            }
+        }
+        return None;
+    }
+
+    if let Ok(ip) = peer_address.parse() {
+        return Some(ip);
+    }
+
+    peer_address
+        .rsplit_once(':')
+        .and_then(|(host, _port)| host.parse().ok())
+}
+
+/// 一次成功的UPnP端口映射，记录了续租/撤销所需的全部信息
+#[derive(Debug, Clone)]
+pub struct UpnpMapping {
+    /// 网关的SOAP控制URL
+    control_url: String,
+    /// 实际生效的WANIPConnection/WANPPPConnection服务类型
+    service_type: String,
+    /// 映射的外部/内部端口（两者相同）
+    external_port: u16,
+    /// 协议："TCP"或"UDP"
+    protocol: &'static str,
+}
+
+/// 一次NAT穿透地址发现的结果
+#[derive(Debug, Clone, Default)]
+struct DiscoveredAddress {
+    /// 发现的外部IP地址（仅地址，不含端口）
+    external_ip: Option<String>,
+    /// 本地出口网卡地址，续租映射时需要作为`NewInternalClient`
+    local_ip: Option<String>,
+    /// 通过IGD成功建立的端口映射（STUN路径下恒为空）
+    mappings: Vec<UpnpMapping>,
+}
+
+const SSDP_MULTICAST_ADDR: &str = "239.255.255.250:1900";
+const SSDP_SEARCH_TARGET: &str = "urn:schemas-upnp-org:device:InternetGatewayDevice:1";
+const WAN_SERVICE_TYPES: [&str; 2] = [
+    "urn:schemas-upnp-org:service:WANIPConnection:1",
+    "urn:schemas-upnp-org:service:WANPPPConnection:1",
+];
+
+/// 依次尝试UPnP打洞与STUN反射，返回发现的外部地址；`enable_nat_traversal`
+/// 关闭时不应调用本函数
+async fn discover_external_address(listen_port: u16, stun_servers: &[String]) -> DiscoveredAddress {
+    if let Some(discovered) = discover_via_upnp(listen_port).await {
+        debug!("🌐 通过UPnP/IGD发现外部地址: {:?}", discovered.external_ip);
+        return discovered;
+    }
+    debug!("UPnP/IGD不可用，退回STUN反射地址查询");
+
+    let local_ip = local_candidate_address().map(|ip| ip.to_string());
+
+    for server in stun_servers {
+        let server = server.clone();
+        match tokio::task::spawn_blocking(move || stun_reflexive_address(&server)).await {
+            Ok(Some(ip)) => {
+                return DiscoveredAddress {
+                    external_ip: Some(ip),
+                    local_ip,
+                    mappings: vec![],
+                };
+            }
+            Ok(None) => continue,
+            Err(e) => {
+                warn!("STUN查询任务失败: {}", e);
+                continue;
+            }
+        }
+    }
+
+    DiscoveredAddress { local_ip, ..Default::default() }
+}
+
+/// 撤销一条UPnP端口映射
+async fn remove_upnp_mapping(mapping: &UpnpMapping) -> Result<()> {
+    let client = reqwest::Client::new();
+    delete_port_mapping(&client, &mapping.control_url, &mapping.service_type, mapping.external_port, mapping.protocol).await
+}
+
+/// 续租一条UPnP端口映射（重新发送`AddPortMapping`即可刷新租期）
+async fn renew_upnp_mapping(mapping: &UpnpMapping, lease_secs: u32, internal_client: &str) -> Result<()> {
+    let client = reqwest::Client::new();
+    add_port_mapping(
+        &client,
+        &mapping.control_url,
+        &mapping.service_type,
+        mapping.external_port,
+        mapping.external_port,
+        internal_client,
+        mapping.protocol,
+        lease_secs,
+    )
+    .await
+}
+
+/// 通过SSDP发现网关、为TCP/UDP各建立一条端口映射，并读取网关上报的外部
+/// IP；任一必要步骤失败都返回`None`，由调用方退回STUN
+async fn discover_via_upnp(listen_port: u16) -> Option<DiscoveredAddress> {
+    let location = tokio::task::spawn_blocking(ssdp_locate_gateway).await.ok()??;
+    let local_ip = local_candidate_address()?;
+    let (control_url, service_type) = locate_control_url(&location).await?;
+
+    let client = reqwest::Client::new();
+    let mut mappings = Vec::new();
+
+    for protocol in ["TCP", "UDP"] {
+        let result = add_port_mapping(
+            &client,
+            &control_url,
+            &service_type,
+            listen_port,
+            listen_port,
+            &local_ip.to_string(),
+            protocol,
+            DEFAULT_UPNP_LEASE_SECS,
+        )
+        .await;
+
+        match result {
+            Ok(()) => mappings.push(UpnpMapping {
+                control_url: control_url.clone(),
+                service_type: service_type.clone(),
+                external_port: listen_port,
+                protocol,
+            }),
+            Err(e) => warn!("IGD为{}端口{}建立映射失败: {}", protocol, listen_port, e),
+        }
+    }
+
+    if mappings.is_empty() {
+        return None;
+    }
+
+    let external_ip = get_external_ip_address(&client, &control_url, &service_type).await.ok();
+
+    Some(DiscoveredAddress {
+        external_ip,
+        local_ip: Some(local_ip.to_string()),
+        mappings,
+    })
+}
+
+const DEFAULT_UPNP_LEASE_SECS: u32 = 3600;
+
+/// 通过向公共DNS服务器"连接"一个UDP套接字（不会真正发包）来获知本机
+/// 出口网卡地址，用于IGD的`NewInternalClient`字段
+fn local_candidate_address() -> Option<std::net::IpAddr> {
+    let socket = std::net::UdpSocket::bind("0.0.0.0:0").ok()?;
+    socket.connect("8.8.8.8:80").ok()?;
+    socket.local_addr().ok().map(|addr| addr.ip())
+}
+
+/// 发送一次SSDP M-SEARCH，阻塞等待（最多2秒）第一个IGD的应答，返回其
+/// 设备描述文档的URL（`LOCATION`响应头）
+fn ssdp_locate_gateway() -> Option<String> {
+    let socket = std::net::UdpSocket::bind("0.0.0.0:0").ok()?;
+    socket.set_read_timeout(Some(Duration::from_secs(2))).ok()?;
+
+    let request = format!(
+        "M-SEARCH * HTTP/1.1\r\nHOST: {addr}\r\nMAN: \"ssdp:discover\"\r\nMX: 2\r\nST: {st}\r\n\r\n",
+        addr = SSDP_MULTICAST_ADDR,
+        st = SSDP_SEARCH_TARGET,
+    );
+    socket.send_to(request.as_bytes(), SSDP_MULTICAST_ADDR).ok()?;
+
+    let mut buf = [0u8; 2048];
+    let (n, _) = socket.recv_from(&mut buf).ok()?;
+    let response = String::from_utf8_lossy(&buf[..n]);
+
+    response
+        .lines()
+        .find(|line| line.to_ascii_lowercase().starts_with("location:"))
+        .and_then(|line| line.split_once(':').map(|(_, value)| value.trim().to_string()))
+}
+
+/// 拉取网关的设备描述XML，按优先级在其中找到第一个WAN连接服务，返回其
+/// 控制URL（已补全为绝对地址）与服务类型
+async fn locate_control_url(location: &str) -> Option<(String, String)> {
+    let body = reqwest::get(location).await.ok()?.text().await.ok()?;
+
+    for service_type in WAN_SERVICE_TYPES {
+        let Some(service_pos) = body.find(service_type) else { continue };
+        let Some(control_url) = extract_between(&body[service_pos..], "<controlURL>", "</controlURL>") else { continue };
+
+        let absolute = if control_url.starts_with("http") {
+            control_url
+        } else {
+            format!("{}{}", root_of(location), control_url)
+        };
+
+        return Some((absolute, service_type.to_string()));
+    }
+
+    None
+}
+
+/// 取`location`的`scheme://host:port`部分，用于把设备描述里的相对
+/// controlURL补全为绝对地址
+fn root_of(location: &str) -> String {
+    if let Some(scheme_end) = location.find("://") {
+        let after_scheme = &location[scheme_end + 3..];
+        if let Some(slash) = after_scheme.find('/') {
+            return location[..scheme_end + 3 + slash].to_string();
+        }
+    }
+    location.to_string()
+}
+
+/// 在`haystack`中截取第一段被`start`/`end`标签包裹的文本
+fn extract_between(haystack: &str, start: &str, end: &str) -> Option<String> {
+    let start_idx = haystack.find(start)? + start.len();
+    let end_idx = start_idx + haystack[start_idx..].find(end)?;
+    Some(haystack[start_idx..end_idx].to_string())
+}
+
+/// 发送`AddPortMapping` SOAP请求
+async fn add_port_mapping(
+    client: &reqwest::Client,
+    control_url: &str,
+    service_type: &str,
+    external_port: u16,
+    internal_port: u16,
+    internal_client: &str,
+    protocol: &str,
+    lease_secs: u32,
+) -> Result<()> {
+    let body = format!(
+        r#"<?xml version="1.0"?>
+<s:Envelope xmlns:s="http://schemas.xmlsoap.org/soap/envelope/" s:encodingStyle="http://schemas.xmlsoap.org/soap/encoding/">
+<s:Body>
+<u:AddPortMapping xmlns:u="{service_type}">
+<NewRemoteHost></NewRemoteHost>
+<NewExternalPort>{external_port}</NewExternalPort>
+<NewProtocol>{protocol}</NewProtocol>
+<NewInternalPort>{internal_port}</NewInternalPort>
+<NewInternalClient>{internal_client}</NewInternalClient>
+<NewEnabled>1</NewEnabled>
+<NewPortMappingDescription>MultiAgentOracle</NewPortMappingDescription>
+<NewLeaseDuration>{lease_secs}</NewLeaseDuration>
+</u:AddPortMapping>
+</s:Body>
+</s:Envelope>"#
+    );
+
+    soap_request(client, control_url, service_type, "AddPortMapping", body).await.map(|_| ())
+}
+
+/// 发送`DeletePortMapping` SOAP请求
+async fn delete_port_mapping(
+    client: &reqwest::Client,
+    control_url: &str,
+    service_type: &str,
+    external_port: u16,
+    protocol: &str,
+) -> Result<()> {
+    let body = format!(
+        r#"<?xml version="1.0"?>
+<s:Envelope xmlns:s="http://schemas.xmlsoap.org/soap/envelope/" s:encodingStyle="http://schemas.xmlsoap.org/soap/encoding/">
+<s:Body>
+<u:DeletePortMapping xmlns:u="{service_type}">
+<NewRemoteHost></NewRemoteHost>
+<NewExternalPort>{external_port}</NewExternalPort>
+<NewProtocol>{protocol}</NewProtocol>
+</u:DeletePortMapping>
+</s:Body>
+</s:Envelope>"#
+    );
+
+    soap_request(client, control_url, service_type, "DeletePortMapping", body).await.map(|_| ())
+}
+
+/// 发送`GetExternalIPAddress` SOAP请求并解析出`NewExternalIPAddress`
+async fn get_external_ip_address(client: &reqwest::Client, control_url: &str, service_type: &str) -> Result<String> {
+    let body = format!(
+        r#"<?xml version="1.0"?>
+<s:Envelope xmlns:s="http://schemas.xmlsoap.org/soap/envelope/" s:encodingStyle="http://schemas.xmlsoap.org/soap/encoding/">
+<s:Body>
+<u:GetExternalIPAddress xmlns:u="{service_type}"></u:GetExternalIPAddress>
+</s:Body>
+</s:Envelope>"#
+    );
+
+    let response_body = soap_request(client, control_url, service_type, "GetExternalIPAddress", body).await?;
+    extract_between(&response_body, "<NewExternalIPAddress>", "</NewExternalIPAddress>")
+        .ok_or_else(|| anyhow!("网关响应中未找到NewExternalIPAddress"))
+}
+
+/// 向IGD控制URL发送一次SOAP动作，返回响应体文本
+async fn soap_request(client: &reqwest::Client, control_url: &str, service_type: &str, action: &str, body: String) -> Result<String> {
+    let soap_action = format!("\"{}#{}\"", service_type, action);
+
+    let response = client
+        .post(control_url)
+        .header("Content-Type", "text/xml; charset=\"utf-8\"")
+        .header("SOAPACTION", soap_action)
+        .body(body)
+        .send()
+        .await?;
+
+    if !response.status().is_success() {
+        return Err(anyhow!("IGD {}失败: HTTP {}", action, response.status()));
+    }
+
+    Ok(response.text().await?)
+}
+
+/// 向单个STUN服务器发送一次RFC 5389 Binding请求，解析`XOR-MAPPED-ADDRESS`
+/// 属性得到本机的公网反射地址；这是阻塞调用，需经`spawn_blocking`执行
+fn stun_reflexive_address(server: &str) -> Option<String> {
+    const MAGIC_COOKIE: u32 = 0x2112_A442;
+
+    let socket = std::net::UdpSocket::bind("0.0.0.0:0").ok()?;
+    socket.set_read_timeout(Some(Duration::from_secs(2))).ok()?;
+    socket.connect(server).ok()?;
+
+    let mut transaction_id = [0u8; 12];
+    rand::thread_rng().fill(&mut transaction_id);
+
+    let mut request = Vec::with_capacity(20);
+    request.extend_from_slice(&0x0001u16.to_be_bytes()); // Binding Request
+    request.extend_from_slice(&0u16.to_be_bytes()); // 无附加属性
+    request.extend_from_slice(&MAGIC_COOKIE.to_be_bytes());
+    request.extend_from_slice(&transaction_id);
+
+    socket.send(&request).ok()?;
+
+    let mut buf = [0u8; 512];
+    let n = socket.recv(&mut buf).ok()?;
+    parse_xor_mapped_address(&buf[..n], MAGIC_COOKIE)
+}
+
+/// 解析STUN响应中的`XOR-MAPPED-ADDRESS`（0x0020）属性，目前只支持IPv4
+fn parse_xor_mapped_address(packet: &[u8], magic_cookie: u32) -> Option<String> {
+    if packet.len() < 20 {
+        return None;
+    }
+
+    let message_type = u16::from_be_bytes([packet[0], packet[1]]);
+    if message_type != 0x0101 {
+        return None; // 不是Binding Success Response
+    }
+
+    let mut offset = 20;
+    while offset + 4 <= packet.len() {
+        let attr_type = u16::from_be_bytes([packet[offset], packet[offset + 1]]);
+        let attr_len = u16::from_be_bytes([packet[offset + 2], packet[offset + 3]]) as usize;
+        let value_start = offset + 4;
+
+        if value_start + attr_len > packet.len() {
+            break;
+        }
+        let value = &packet[value_start..value_start + attr_len];
+
+        if attr_type == 0x0020 && value.len() >= 8 && value[1] == 0x01 {
+            let xaddr = u32::from_be_bytes([value[4], value[5], value[6], value[7]]) ^ magic_cookie;
+            return Some(std::net::IpAddr::from(xaddr.to_be_bytes()).to_string());
+        }
+
+        let padded_len = attr_len + ((4 - (attr_len % 4)) % 4);
+        offset = value_start + padded_len;
+    }
+
+    None
+}
+
 impl NetworkManager {
     /// 创建新的网络管理器
     pub fn new(node_id: String, config: NetworkConfig) -> Result<Self> {
         let peer_discovery = PeerDiscovery::new(config.clone());
         let message_handler = MessageHandler::new();
-        
+
+        // 入站/出站消息队列：容量与连接预算挂钩，避免无界增长
+        let queue_capacity = config.max_connections.max(16) * 4;
+        let (inbound_tx, inbound_rx) = mpsc::channel(queue_capacity);
+        let (outbound_tx, outbound_rx) = mpsc::channel(queue_capacity);
+        let (shutdown_tx, shutdown_rx) = tokio::sync::watch::channel(false);
+
+        let discovery_interval = config.discovery_interval_secs.max(1);
+        let heartbeat_interval = config.heartbeat_interval_secs.max(1);
+        let reserved_nodes = config.reserved_nodes.clone();
+        let non_reserved_peer_mode = config.non_reserved_peer_mode;
+
         Ok(Self {
             node_id,
             config,
@@ -146,14 +878,282 @@ impl NetworkManager {
                 messages_sent: 0,
                 messages_received: 0,
                 discovered_peers: 0,
+                inbound_connections: 0,
+                outbound_connections: 0,
                 network_bandwidth_kbps: 0.0,
                 last_error: None,
+                external_address: None,
+                dropped_unsigned_messages: 0,
             })),
+            inbound_tx,
+            inbound_rx: Arc::new(tokio::sync::Mutex::new(inbound_rx)),
+            outbound_tx,
+            outbound_rx: Arc::new(tokio::sync::Mutex::new(outbound_rx)),
+            discovery_timer: Arc::new(tokio::sync::Mutex::new(tokio::time::interval_at(
+                tokio::time::Instant::now() + Duration::from_secs(discovery_interval),
+                Duration::from_secs(discovery_interval),
+            ))),
+            heartbeat_timer: Arc::new(tokio::sync::Mutex::new(tokio::time::interval_at(
+                tokio::time::Instant::now() + Duration::from_secs(heartbeat_interval),
+                Duration::from_secs(heartbeat_interval),
+            ))),
+            shutdown_tx,
+            shutdown_rx,
+            reserved_nodes: Arc::new(RwLock::new(reserved_nodes)),
+            non_reserved_peer_mode: Arc::new(RwLock::new(non_reserved_peer_mode)),
+            ip_filter: Arc::new(RwLock::new(IpFilter::default())),
+            attachment_state: Arc::new(RwLock::new(AttachmentState::Detached)),
+            transport_connect_handlers: Arc::new(RwLock::new(HashMap::new())),
+            transport_accept_handlers: Arc::new(RwLock::new(HashMap::new())),
+            peer_connections: Arc::new(RwLock::new(HashMap::new())),
+            external_address: Arc::new(RwLock::new(None)),
+            local_ip: Arc::new(RwLock::new(None)),
+            upnp_mappings: Arc::new(RwLock::new(Vec::new())),
+            bandwidth_samples: Arc::new(RwLock::new(HashMap::new())),
+            identity_manager: Arc::new(RwLock::new(None)),
+        })
+    }
+
+    /// 配置DIAP身份管理器：配置后，`send_message`会用当前身份的Ed25519
+    /// 私钥对负载签名，`handle_received_message`会用发送方（`peer_address`
+    /// 即其身份ID）公开的身份公钥校验签名，丢弃签名缺失或无效的消息
+    pub async fn set_identity_manager(&self, identity_manager: Arc<crate::diap::DiapIdentityManager>) {
+        *self.identity_manager.write().await = Some(identity_manager);
+    }
+
+    /// 注册一个协议的出站拨号处理器：`connect_to_peer`会依据`peer_address`
+    /// 解析出的连接类型挑选对应的处理器来实际建立连接
+    pub async fn register_transport(&self, protocol_type: ConnectionType, handler: Arc<dyn ProtocolConnectHandler>) {
+        self.transport_connect_handlers.write().await.insert(protocol_type, handler);
+    }
+
+    /// 注册一个协议的入站接受处理器：`accept_inbound_peer`会据此接受落在
+    /// 该协议上的对端连接
+    pub async fn register_accept_handler(&self, protocol_type: ConnectionType, handler: Arc<dyn ProtocolAcceptHandler>) {
+        self.transport_accept_handlers.write().await.insert(protocol_type, handler);
+    }
+
+    /// 根据multiaddr风格的`peer_address`选择连接类型：relay前缀或命中
+    /// `config.relay_nodes`的地址归入`Relay`，含`/webrtc`段的归入`WebRTC`，
+    /// 其余（含`/tcp`、`/quic`或裸地址）归入`Direct`
+    fn classify_connection_type(&self, peer_address: &str) -> ConnectionType {
+        if peer_address.contains("/p2p-circuit")
+            || self.config.relay_nodes.iter().any(|relay| relay == peer_address)
+        {
+            return ConnectionType::Relay;
+        }
+
+        if peer_address.contains("/webrtc") {
+            return ConnectionType::WebRTC;
+        }
+
+        ConnectionType::Direct
+    }
+
+    /// 若该连接类型注册了拨号处理器，则实际建立连接并返回；否则返回
+    /// `None`，调用方退回到仅记录日志的模拟行为
+    async fn dial_via_transport(&self, peer_address: &str, connection_type: &ConnectionType) -> Result<Option<Box<dyn PeerConnection>>> {
+        let handler = self.transport_connect_handlers.read().await.get(connection_type).cloned();
+        match handler {
+            Some(handler) => Ok(Some(handler.connect(peer_address).await?)),
+            None => Ok(None),
+        }
+    }
+
+    /// 若该连接类型注册了接受处理器，则实际接受连接并返回；否则返回
+    /// `None`
+    async fn accept_via_transport(&self, peer_address: &str, connection_type: &ConnectionType) -> Result<Option<Box<dyn PeerConnection>>> {
+        let handler = self.transport_accept_handlers.read().await.get(connection_type).cloned();
+        match handler {
+            Some(handler) => Ok(Some(handler.accept(peer_address).await?)),
+            None => Ok(None),
+        }
+    }
+
+    /// 把一条已建立的传输连接接入管理器：登记到`peer_connections`，并
+    /// 派生一个后台任务把对端发来的数据转发进入站队列，复用事件循环的
+    /// 统一处理路径
+    async fn attach_transport_connection(&self, peer_address: &str, connection: Box<dyn PeerConnection>) {
+        let connection: Arc<dyn PeerConnection> = Arc::from(connection);
+        self.peer_connections.write().await.insert(peer_address.to_string(), connection.clone());
+
+        let inbound_tx = self.inbound_tx.clone();
+        let peer_address = peer_address.to_string();
+        tokio::spawn(async move {
+            loop {
+                match connection.recv().await {
+                    Ok(message) => {
+                        if inbound_tx.send((peer_address.clone(), message)).await.is_err() {
+                            break;
+                        }
+                    }
+                    Err(e) => {
+                        debug!("传输连接 {} 接收循环结束: {}", peer_address, e);
+                        break;
+                    }
+                }
+            }
+        });
+    }
+
+    /// 获取入站消息队列的发送端，供底层传输在收到字节流后投递
+    pub fn inbound_sender(&self) -> mpsc::Sender<(String, Vec<u8>)> {
+        self.inbound_tx.clone()
+    }
+
+    /// 将一条消息排入出站队列，由事件循环统一发送
+    pub async fn queue_outbound_message(&self, peer_address: &str, message: Vec<u8>) -> Result<()> {
+        self.outbound_tx
+            .send((peer_address.to_string(), message))
+            .await
+            .map_err(|_| anyhow!("出站队列已关闭"))
+    }
+
+    /// 计算事件循环下一个要处理的事件
+    ///
+    /// 在入站队列、出站队列、发现定时器、心跳定时器与关闭信号之间
+    /// `select`，所有对 `connections`/`status` 的修改都经由调用方在
+    /// 拿到事件后统一处理，而不是分散在多个独立的 `RwLock` 写者里。
+    async fn next_action(&self) -> NetworkEvent {
+        let mut inbound_rx = self.inbound_rx.lock().await;
+        let mut outbound_rx = self.outbound_rx.lock().await;
+        let mut discovery_timer = self.discovery_timer.lock().await;
+        let mut heartbeat_timer = self.heartbeat_timer.lock().await;
+        let mut shutdown_rx = self.shutdown_rx.clone();
+
+        tokio::select! {
+            _ = shutdown_rx.changed() => NetworkEvent::Shutdown,
+            Some((peer_address, message)) = inbound_rx.recv() => {
+                NetworkEvent::Inbound { peer_address, message }
+            }
+            Some((peer_address, message)) = outbound_rx.recv() => {
+                NetworkEvent::Outbound { peer_address, message }
+            }
+            _ = discovery_timer.tick() => NetworkEvent::Discovery,
+            _ = heartbeat_timer.tick() => NetworkEvent::Heartbeat,
+        }
+    }
+
+    /// 执行一次有界的事件循环迭代：最多处理 `budget` 个事件后返回，
+    /// 这样洪泛般的入站消息也无法饿死心跳和清理工作。
+    ///
+    /// 返回本次实际处理的事件数；遇到关闭信号会提前返回。
+    pub async fn run_tick(&self, budget: usize) -> Result<usize> {
+        let mut processed = 0;
+
+        while processed < budget {
+            match self.next_action().await {
+                NetworkEvent::Shutdown => {
+                    debug!("🛑 事件循环收到关闭信号");
+                    break;
+                }
+                NetworkEvent::Inbound { peer_address, message } => {
+                    if let Err(e) = self.handle_received_message(&peer_address, message).await {
+                        warn!("事件循环处理入站消息失败 {}: {}", peer_address, e);
+                    }
+                }
+                NetworkEvent::Outbound { peer_address, message } => {
+                    if let Err(e) = self.send_message(&peer_address, message).await {
+                        warn!("事件循环发送消息失败 {}: {}", peer_address, e);
+                    }
+                }
+                NetworkEvent::Discovery => {
+                    if let Err(e) = self.connect_to_bootstrap_nodes().await {
+                        warn!("事件循环节点发现失败: {}", e);
+                    }
+                }
+                NetworkEvent::Heartbeat => {
+                    self.heartbeat_check().await?;
+                    self.cleanup_inactive_connections(self.config.heartbeat_interval_secs * 3).await?;
+                }
+            }
+
+            processed += 1;
+        }
+
+        Ok(processed)
+    }
+
+    /// 启动事件循环：在后台任务中不断调用 [`NetworkManager::run_tick`]，
+    /// 每次达到预算后主动让出调度器并立即重新排程，直到收到关闭信号。
+    pub fn run(self: Arc<Self>) -> tokio::task::JoinHandle<()> {
+        let budget = self.config.event_budget_per_tick.max(1);
+
+        tokio::spawn(async move {
+            info!("🔁 启动网络事件循环，单次预算: {} 个事件", budget);
+
+            loop {
+                if let Err(e) = self.run_tick(budget).await {
+                    error!("事件循环迭代出错: {}", e);
+                }
+
+                if *self.shutdown_rx.borrow() {
+                    info!("✅ 事件循环已退出");
+                    break;
+                }
+
+                // 让出调度器，避免在预算用尽时独占运行时线程
+                tokio::task::yield_now().await;
+            }
         })
     }
+
+    /// 关闭事件循环：先广播关闭信号，排空已经进入队列的在途事件，
+    /// 再停止网络管理器本身。
+    pub async fn shutdown(&self) -> Result<()> {
+        info!("🛑 请求关闭网络事件循环");
+        let _ = self.shutdown_tx.send(true);
+
+        loop {
+            let drained = self.drain_pending_events().await?;
+            if drained == 0 {
+                break;
+            }
+        }
+
+        self.stop().await
+    }
+
+    /// 排空入站/出站队列中尚未处理的在途事件（不等待新事件到达）
+    async fn drain_pending_events(&self) -> Result<usize> {
+        let mut pending = Vec::new();
+
+        {
+            let mut inbound_rx = self.inbound_rx.lock().await;
+            while let Ok((peer_address, message)) = inbound_rx.try_recv() {
+                pending.push(NetworkEvent::Inbound { peer_address, message });
+            }
+        }
+        {
+            let mut outbound_rx = self.outbound_rx.lock().await;
+            while let Ok((peer_address, message)) = outbound_rx.try_recv() {
+                pending.push(NetworkEvent::Outbound { peer_address, message });
+            }
+        }
+
+        let count = pending.len();
+
+        for event in pending {
+            match event {
+                NetworkEvent::Inbound { peer_address, message } => {
+                    if let Err(e) = self.handle_received_message(&peer_address, message).await {
+                        warn!("排空在途入站事件失败 {}: {}", peer_address, e);
+                    }
+                }
+                NetworkEvent::Outbound { peer_address, message } => {
+                    if let Err(e) = self.send_message(&peer_address, message).await {
+                        warn!("排空在途出站事件失败 {}: {}", peer_address, e);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        Ok(count)
+    }
     
     /// 启动网络
-    pub async fn start(&mut self) -> Result<()> {
+    pub async fn start(&self) -> Result<()> {
         let mut status = self.status.write().await;
         
         if status.is_running {
@@ -169,20 +1169,41 @@ impl NetworkManager {
             .duration_since(std::time::UNIX_EPOCH)
             .unwrap()
             .as_secs();
-        
+
+        self.set_attachment_state(AttachmentState::Attaching).await;
+
         // 启动对等节点发现
         self.peer_discovery.start().await?;
         
         // 连接到引导节点
         self.connect_to_bootstrap_nodes().await?;
-        
+
+        if self.config.enable_nat_traversal {
+            self.discover_nat_address().await;
+        }
+
         info!("✅ 网络启动成功");
-        
+
         Ok(())
     }
+
+    /// 通过UPnP/IGD或STUN发现外部地址，并记录UPnP端口映射以便`stop()`时撤销
+    async fn discover_nat_address(&self) {
+        let discovered = discover_external_address(self.config.listen_port, &self.config.stun_servers).await;
+
+        if let Some(external_ip) = &discovered.external_ip {
+            info!("🌐 发现外部地址: {}", external_ip);
+        } else {
+            warn!("⚠️ 未能发现外部地址，NAT穿透可能不可用");
+        }
+
+        *self.external_address.write().await = discovered.external_ip;
+        *self.local_ip.write().await = discovered.local_ip;
+        *self.upnp_mappings.write().await = discovered.mappings;
+    }
     
     /// 停止网络
-    pub async fn stop(&mut self) -> Result<()> {
+    pub async fn stop(&self) -> Result<()> {
         let mut status = self.status.write().await;
         
         if !status.is_running {
@@ -190,16 +1211,22 @@ impl NetworkManager {
         }
         
         info!("🛑 停止网络管理器");
-        
+        self.set_attachment_state(AttachmentState::Detaching).await;
+
         // 停止对等节点发现
         self.peer_discovery.stop().await?;
-        
+
         // 关闭所有连接
         self.close_all_connections().await?;
-        
+
+        if self.config.enable_nat_traversal {
+            self.teardown_nat_mappings().await;
+        }
+
         // 更新状态
         status.is_running = false;
-        
+        self.set_attachment_state(AttachmentState::Detached).await;
+
         info!("✅ 网络停止成功");
         
         Ok(())
@@ -223,117 +1250,288 @@ impl NetworkManager {
             }
         }
         
-        info!("📊 引导节点连接结果: {}/{} 成功", 
+        info!("📊 引导节点连接结果: {}/{} 成功",
             connected_count, self.config.bootstrap_nodes.len());
-        
+
+        // 把引导节点登记进Kademlia路由表，并对本地节点做一次自查找，
+        // 让最近的k-bucket尽快被真实邻居填满
+        self.peer_discovery.bootstrap_kademlia().await;
+
         Ok(())
     }
-    
-    /// 连接到对等节点
+
+    /// 基于Kademlia路由表查找离目标节点最近的`k`个已知节点
+    ///
+    /// 返回结果只反映当前节点本地已知的邻居，查找不会跨网络发起真实的
+    /// FIND_NODE查询——这与`PeerDiscovery::iterative_find_node`里用于
+    /// 自查找/引导的迭代算法共享同一张路由表。
+    pub async fn find_closest_peers(&self, target_id: &str, k: usize) -> Vec<(String, String)> {
+        self.peer_discovery.find_closest_peers(&target_id.to_string(), k).await
+    }
+
+    /// 连接到对等节点（本地主动发起，即出站连接）
     pub async fn connect_to_peer(&self, peer_address: &str) -> Result<()> {
-        let mut connections = self.connections.write().await;
-        
+        self.admit_peer(peer_address, ConnectionDirection::Outbound).await
+    }
+
+    /// 接受一个对方主动发起的入站连接
+    pub async fn accept_inbound_peer(&self, peer_address: &str) -> Result<()> {
+        self.admit_peer(peer_address, ConnectionDirection::Inbound).await
+    }
+
+    /// 准入层：保留节点无条件接纳；非保留节点在`Deny`模式下直接拒绝，
+    /// 在`Accept`模式下还要经过IP过滤与连接预算检查；通过准入后再按
+    /// 解析出的连接类型实际拨号/接受，最后才把连接记录写入连接表
+    async fn admit_peer(&self, peer_address: &str, direction: ConnectionDirection) -> Result<()> {
         // 检查是否已连接
+        if self.connections.read().await.contains_key(peer_address) {
+            return Err(anyhow!("已经连接到该节点"));
+        }
+
+        let is_reserved = self.reserved_nodes.read().await.contains(peer_address);
+
+        if !is_reserved {
+            let mode = *self.non_reserved_peer_mode.read().await;
+            if mode == NonReservedPeerMode::Deny {
+                return Err(anyhow!("节点 {} 被拒绝: 当前仅接受保留节点(NonReservedPeerMode::Deny)", peer_address));
+            }
+
+            if let Some(ip) = extract_peer_ip(peer_address) {
+                let ip_filter = self.ip_filter.read().await;
+                if !ip_filter.is_allowed(&ip) {
+                    return Err(anyhow!("节点 {} 的IP地址 {} 被IpFilter拒绝", peer_address, ip));
+                }
+            }
+
+            let active_connections = self.connections.read().await.values().filter(|c| c.is_active).count();
+            if active_connections >= self.config.max_connections {
+                return Err(anyhow!(
+                    "已达到最大连接数({})，拒绝非保留节点 {}",
+                    self.config.max_connections, peer_address
+                ));
+            }
+        }
+
+        let connection_type = self.classify_connection_type(peer_address);
+        let established = match direction {
+            ConnectionDirection::Outbound => self.dial_via_transport(peer_address, &connection_type).await?,
+            ConnectionDirection::Inbound => self.accept_via_transport(peer_address, &connection_type).await?,
+        };
+
+        let mut connections = self.connections.write().await;
         if connections.contains_key(peer_address) {
             return Err(anyhow!("已经连接到该节点"));
         }
-        
+
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+
         // 创建连接状态
         let connection = ConnectionStatus {
             peer_id: peer_address.to_string(),
             address: peer_address.to_string(),
-            connected_at: std::time::SystemTime::now()
-                .duration_since(std::time::UNIX_EPOCH)
-                .unwrap()
-                .as_secs(),
-            last_activity: std::time::SystemTime::now()
-                .duration_since(std::time::UNIX_EPOCH)
-                .unwrap()
-                .as_secs(),
+            connected_at: now,
+            last_activity: now,
             is_active: true,
             bytes_sent: 0,
             bytes_received: 0,
+            send_rate_kbps: 0.0,
+            recv_rate_kbps: 0.0,
             last_error: None,
-            connection_type: ConnectionType::Direct,
+            connection_type,
+            direction,
         };
-        
+
         // 添加到连接列表
         connections.insert(peer_address.to_string(), connection);
-        
+
         // 更新网络状态
         let mut status = self.status.write().await;
         status.total_connections += 1;
-        status.active_connections += 1;
-        
-        info!("🔗 连接到对等节点: {}", peer_address);
-        
+        status.active_connections = connections.values().filter(|c| c.is_active).count();
+        match direction {
+            ConnectionDirection::Inbound => status.inbound_connections += 1,
+            ConnectionDirection::Outbound => status.outbound_connections += 1,
+        }
+
+        self.recompute_attachment_state(status.active_connections, status.is_running).await;
+
+        drop(status);
+        drop(connections);
+
+        if let Some(connection) = established {
+            self.attach_transport_connection(peer_address, connection).await;
+        }
+
+        info!("🔗 {}对等节点: {} ({}保留节点)",
+            if direction == ConnectionDirection::Inbound { "接受来自" } else { "连接到" },
+            peer_address,
+            if is_reserved { "" } else { "非" });
+
         Ok(())
     }
-    
+
+    /// 添加保留节点：该节点此后即使连接预算已满也会被接纳，且不会被心跳/
+    /// 清理逻辑驱逐
+    pub async fn add_reserved_peer(&self, peer_id: String) {
+        self.reserved_nodes.write().await.insert(peer_id);
+    }
+
+    /// 移除保留节点
+    pub async fn remove_reserved_peer(&self, peer_id: &str) {
+        self.reserved_nodes.write().await.remove(peer_id);
+    }
+
+    /// 设置非保留节点的准入策略
+    pub async fn set_non_reserved_mode(&self, mode: NonReservedPeerMode) {
+        *self.non_reserved_peer_mode.write().await = mode;
+    }
+
+    /// 替换当前的IP准入过滤器
+    pub async fn set_ip_filter(&self, filter: IpFilter) {
+        *self.ip_filter.write().await = filter;
+    }
+
     /// 断开与对等节点的连接
     pub async fn disconnect_from_peer(&self, peer_address: &str) -> Result<()> {
         let mut connections = self.connections.write().await;
-        
+
         if let Some(connection) = connections.remove(peer_address) {
             // 更新网络状态
             let mut status = self.status.write().await;
             status.active_connections -= 1;
-            
+            match connection.direction {
+                ConnectionDirection::Inbound => status.inbound_connections = status.inbound_connections.saturating_sub(1),
+                ConnectionDirection::Outbound => status.outbound_connections = status.outbound_connections.saturating_sub(1),
+            }
+
+            self.recompute_attachment_state(status.active_connections, status.is_running).await;
+
+            drop(status);
+
+            if let Some(connection) = self.peer_connections.write().await.remove(peer_address) {
+                let _ = connection.close().await;
+            }
+
             info!("🔌 断开与对等节点的连接: {}", peer_address);
-            
+
             Ok(())
         } else {
             Err(anyhow!("未找到该节点的连接"))
         }
     }
-    
+
     /// 关闭所有连接
     async fn close_all_connections(&self) -> Result<()> {
         let mut connections = self.connections.write().await;
         let count = connections.len();
-        
+
         connections.clear();
-        
+
         // 更新网络状态
         let mut status = self.status.write().await;
         status.active_connections = 0;
-        
+        drop(status);
+
+        let mut peer_connections = self.peer_connections.write().await;
+        for (_, connection) in peer_connections.drain() {
+            let _ = connection.close().await;
+        }
+
         info!("🔌 关闭所有连接: {} 个", count);
-        
+
         Ok(())
     }
-    
+
     /// 发送消息到对等节点
+    ///
+    /// 配置了[`Self::set_identity_manager`]时，会用本节点身份的Ed25519
+    /// 私钥对`message`签名，实际发送的是签名信封；未配置时行为不变，
+    /// 直接收发裸字节。
     pub async fn send_message(&self, peer_address: &str, message: Vec<u8>) -> Result<()> {
+        let message = self.sign_outbound_message(message).await?;
         let mut connections = self.connections.write().await;
-        
+
         if let Some(connection) = connections.get_mut(peer_address) {
             if !connection.is_active {
                 return Err(anyhow!("连接不活跃"));
             }
-            
+
             // 更新连接状态
             connection.last_activity = std::time::SystemTime::now()
                 .duration_since(std::time::UNIX_EPOCH)
                 .unwrap()
                 .as_secs();
             connection.bytes_sent += message.len() as u64;
-            
+
             // 更新网络状态
             let mut status = self.status.write().await;
             status.messages_sent += 1;
-            
-            info!("📤 发送消息到 {}: {} 字节", peer_address, message.len());
-            
-            // 这里应该实现实际的消息发送逻辑
-            // 简化实现：记录日志
-            
+            drop(status);
+            drop(connections);
+
+            // 如果该连接注册了实际传输，通过它收发；否则退回到仅记录日志
+            // 的模拟行为
+            let transport = self.peer_connections.read().await.get(peer_address).cloned();
+            match transport {
+                Some(connection) => {
+                    connection.send(&message).await?;
+                    info!("📤 通过已注册传输发送消息到 {}: {} 字节", peer_address, message.len());
+                }
+                None => {
+                    info!("📤 发送消息到 {} (未注册传输，记录日志): {} 字节", peer_address, message.len());
+                }
+            }
+
             Ok(())
         } else {
             Err(anyhow!("未找到该节点的连接"))
         }
     }
     
+    /// 若配置了DIAP身份管理器，用本节点身份的私钥对`payload`签名并包装成
+    /// [`SignedEnvelope`]；未配置时原样返回`payload`
+    async fn sign_outbound_message(&self, payload: Vec<u8>) -> Result<Vec<u8>> {
+        let identity_manager = self.identity_manager.read().await.clone();
+        let Some(identity_manager) = identity_manager else {
+            return Ok(payload);
+        };
+
+        let signature = identity_manager.sign_with_identity(&self.node_id, &payload).await?;
+        let envelope = SignedEnvelope { payload, signature };
+        serde_json::to_vec(&envelope).map_err(|e| anyhow!("序列化签名信封失败: {}", e))
+    }
+
+    /// 若配置了DIAP身份管理器，解出[`SignedEnvelope`]并用`peer_address`
+    /// （即发送方身份ID）的公开身份校验签名，签名缺失或无效时返回
+    /// `Ok(None)`；未配置身份管理器时原样放行`message`
+    async fn verify_inbound_message(&self, peer_address: &str, message: Vec<u8>) -> Option<Vec<u8>> {
+        let identity_manager = self.identity_manager.read().await.clone()?;
+
+        let envelope: SignedEnvelope = match serde_json::from_slice(&message) {
+            Ok(envelope) => envelope,
+            Err(_) => {
+                warn!("丢弃未签名的入站消息: {}", peer_address);
+                return None;
+            }
+        };
+
+        let identity = identity_manager.get_identity(peer_address).await;
+        let valid = match identity {
+            Some(identity) => identity_manager.verify_message_signature(&identity, &envelope.payload, &envelope.signature),
+            None => false,
+        };
+
+        if valid {
+            Some(envelope.payload)
+        } else {
+            warn!("丢弃签名无效的入站消息: {}", peer_address);
+            None
+        }
+    }
+
     /// 广播消息到所有对等节点
     pub async fn broadcast_message(&self, message: Vec<u8>) -> Result<usize> {
         let connections = self.connections.read().await;
@@ -372,6 +1570,11 @@ impl NetworkManager {
     }
     
     /// 处理接收到的消息
+    ///
+    /// 配置了[`Self::set_identity_manager`]时，会先校验`message`的签名
+    /// 信封；签名缺失或对`peer_address`（发送方身份ID）公开的身份校验
+    /// 失败的消息会被丢弃并计入`NetworkStatus::dropped_unsigned_messages`，
+    /// 不会进入后续处理。
     pub async fn handle_received_message(&self, peer_address: &str, message: Vec<u8>) -> Result<()> {
         // 更新连接状态
         let mut connections = self.connections.write().await;
@@ -382,22 +1585,62 @@ impl NetworkManager {
                 .as_secs();
             connection.bytes_received += message.len() as u64;
         }
-        
+        drop(connections);
+
+        let Some(payload) = self.verify_inbound_message(peer_address, message).await else {
+            let mut status = self.status.write().await;
+            status.dropped_unsigned_messages += 1;
+            return Ok(());
+        };
+
         // 更新网络状态
         let mut status = self.status.write().await;
         status.messages_received += 1;
-        
-        info!("📥 从 {} 接收消息: {} 字节", peer_address, message.len());
-        
+
+        info!("📥 从 {} 接收消息: {} 字节", peer_address, payload.len());
+
         // 这里应该实现实际的消息处理逻辑
         // 简化实现：记录日志
-        
+
         Ok(())
     }
     
+    /// 获取当前连接强度状态
+    pub async fn attachment_state(&self) -> AttachmentState {
+        *self.attachment_state.read().await
+    }
+
+    /// 是否已具备至少`AttachedWeak`的连接强度
+    pub async fn is_attached(&self) -> bool {
+        self.attachment_state().await.is_at_least(AttachmentState::AttachedWeak)
+    }
+
+    /// 是否处于`Detached`（没有任何活跃连接）
+    pub async fn is_detached(&self) -> bool {
+        self.attachment_state().await == AttachmentState::Detached
+    }
+
+    /// 将连接强度状态切换到`new_state`；只在状态实际变化时记录日志，
+    /// 避免每次心跳都刷屏
+    async fn set_attachment_state(&self, new_state: AttachmentState) {
+        let mut state = self.attachment_state.write().await;
+        if *state != new_state {
+            info!("🔗 连接强度状态变化: {:?} -> {:?}", *state, new_state);
+            *state = new_state;
+        }
+    }
+
+    /// 根据当前活跃连接数与运行状态重新计算并应用连接强度状态
+    async fn recompute_attachment_state(&self, active_connections: usize, running: bool) {
+        let new_state = classify_attachment_state(active_connections, &self.config, running);
+        self.set_attachment_state(new_state).await;
+    }
+
     /// 获取网络状态
     pub async fn get_status(&self) -> NetworkStatus {
-        self.status.read().await.clone()
+        let mut status = self.status.read().await.clone();
+        status.external_address = self.external_address.read().await.clone();
+        status
     }
     
     /// 获取连接列表
@@ -415,63 +1658,468 @@ impl NetworkManager {
     /// 心跳检查
     pub async fn heartbeat_check(&self) -> Result<usize> {
         let mut connections = self.connections.write().await;
+        let reserved_nodes = self.reserved_nodes.read().await;
         let now = std::time::SystemTime::now()
             .duration_since(std::time::UNIX_EPOCH)
             .unwrap()
             .as_secs();
-        
+
         let mut inactive_count = 0;
-        
+        let mut bandwidth_samples = self.bandwidth_samples.write().await;
+        let mut total_send_kbps = 0.0;
+        let mut total_recv_kbps = 0.0;
+
         for connection in connections.values_mut() {
+            // 带宽采样：与上一次采样的字节计数相减得到增量，再并入该连接
+            // 自己的EWMA；首次见到某个对端时只登记基准值，本轮不产生速率
+            let sample = bandwidth_samples.entry(connection.peer_id.clone()).or_insert(BandwidthSample {
+                sampled_at: now,
+                bytes_sent: connection.bytes_sent,
+                bytes_received: connection.bytes_received,
+            });
+            let sample_elapsed = now.saturating_sub(sample.sampled_at);
+            if sample_elapsed > 0 {
+                let sent_delta = connection.bytes_sent.saturating_sub(sample.bytes_sent);
+                let recv_delta = connection.bytes_received.saturating_sub(sample.bytes_received);
+                let instantaneous_send_kbps = kbps_from_delta(sent_delta, sample_elapsed);
+                let instantaneous_recv_kbps = kbps_from_delta(recv_delta, sample_elapsed);
+
+                connection.send_rate_kbps = ewma_kbps(
+                    connection.send_rate_kbps,
+                    instantaneous_send_kbps,
+                    sample_elapsed,
+                    self.config.bandwidth_window_secs,
+                );
+                connection.recv_rate_kbps = ewma_kbps(
+                    connection.recv_rate_kbps,
+                    instantaneous_recv_kbps,
+                    sample_elapsed,
+                    self.config.bandwidth_window_secs,
+                );
+
+                total_send_kbps += instantaneous_send_kbps;
+                total_recv_kbps += instantaneous_recv_kbps;
+
+                sample.sampled_at = now;
+                sample.bytes_sent = connection.bytes_sent;
+                sample.bytes_received = connection.bytes_received;
+            }
+
+            if reserved_nodes.contains(&connection.peer_id) {
+                continue;
+            }
+
             let inactive_time = now - connection.last_activity;
-            
+
             if inactive_time > self.config.heartbeat_interval_secs * 3 {
                 connection.is_active = false;
                 inactive_count += 1;
-                
-                warn!("💔 连接超时: {} ({}秒未活动)", 
+
+                warn!("💔 连接超时: {} ({}秒未活动)",
                     connection.peer_id, inactive_time);
             }
         }
-        
+
+        let connected_peers: HashSet<&String> = connections.keys().collect();
+        bandwidth_samples.retain(|peer_id, _| connected_peers.contains(peer_id));
+        drop(bandwidth_samples);
+
         // 更新网络状态
         let mut status = self.status.write().await;
         status.active_connections = connections.values().filter(|c| c.is_active).count();
-        
+        status.network_bandwidth_kbps = ewma_kbps(
+            status.network_bandwidth_kbps,
+            total_send_kbps + total_recv_kbps,
+            self.config.heartbeat_interval_secs,
+            self.config.bandwidth_window_secs,
+        );
+
+        self.recompute_attachment_state(status.active_connections, status.is_running).await;
+
         if inactive_count > 0 {
             info!("💓 心跳检查: {} 个连接不活跃", inactive_count);
         }
-        
+
+        drop(status);
+        drop(connections);
+        drop(reserved_nodes);
+
+        if self.config.enable_nat_traversal {
+            self.renew_nat_mappings().await;
+        }
+
         Ok(inactive_count)
     }
+
+    /// 续租所有已建立的UPnP端口映射，避免网关租约到期后外部地址失效
+    async fn renew_nat_mappings(&self) {
+        let mappings = self.upnp_mappings.read().await.clone();
+        if mappings.is_empty() {
+            return;
+        }
+
+        let internal_client = self.local_ip.read().await.clone().unwrap_or_else(|| self.config.listen_address.clone());
+
+        for mapping in &mappings {
+            if let Err(e) = renew_upnp_mapping(mapping, self.config.upnp_lease_secs, &internal_client).await {
+                warn!("⚠️ 续租UPnP端口映射失败 ({}:{}): {}", mapping.protocol, mapping.external_port, e);
+            }
+        }
+    }
+
+    /// 撤销所有UPnP端口映射，并清空已发现的外部地址
+    async fn teardown_nat_mappings(&self) {
+        let mappings = self.upnp_mappings.write().await.drain(..).collect::<Vec<_>>();
+        for mapping in &mappings {
+            if let Err(e) = remove_upnp_mapping(mapping).await {
+                warn!("⚠️ 撤销UPnP端口映射失败 ({}:{}): {}", mapping.protocol, mapping.external_port, e);
+            }
+        }
+        *self.external_address.write().await = None;
+        *self.local_ip.write().await = None;
+    }
+
+    /// 获取已发现的外部地址（需开启`enable_nat_traversal`且已`start()`）
+    pub async fn get_external_address(&self) -> Option<String> {
+        self.external_address.read().await.clone()
+    }
     
     /// 清理不活跃连接
     pub async fn cleanup_inactive_connections(&self, max_inactive_secs: u64) -> Result<usize> {
         let mut connections = self.connections.write().await;
+        let reserved_nodes = self.reserved_nodes.read().await;
         let now = std::time::SystemTime::now()
             .duration_since(std::time::UNIX_EPOCH)
             .unwrap()
             .as_secs();
-        
+
         let before_count = connections.len();
-        
+
         connections.retain(|_, connection| {
+            if reserved_nodes.contains(&connection.peer_id) {
+                return true;
+            }
             let inactive_time = now - connection.last_activity;
             inactive_time <= max_inactive_secs
         });
-        
+
+        // 仍超出连接预算时，优先淘汰零吞吐量的非保留连接（即使尚未达到
+        // `max_inactive_secs`），按最后活动时间从旧到新依次驱逐
+        let mut over_budget_evicted = 0;
+        if connections.len() > self.config.max_connections {
+            let mut idle_zero_throughput: Vec<(String, u64)> = connections
+                .values()
+                .filter(|c| {
+                    !reserved_nodes.contains(&c.peer_id)
+                        && c.send_rate_kbps == 0.0
+                        && c.recv_rate_kbps == 0.0
+                })
+                .map(|c| (c.peer_id.clone(), c.last_activity))
+                .collect();
+            idle_zero_throughput.sort_by_key(|(_, last_activity)| *last_activity);
+
+            for (peer_id, _) in idle_zero_throughput {
+                if connections.len() <= self.config.max_connections {
+                    break;
+                }
+                if connections.remove(&peer_id).is_some() {
+                    over_budget_evicted += 1;
+                    debug!("🧹 连接预算已满，驱逐零吞吐量的空闲连接: {}", peer_id);
+                }
+            }
+        }
+
         let after_count = connections.len();
         let removed_count = before_count - after_count;
-        
+
         // 更新网络状态
         let mut status = self.status.write().await;
         status.active_connections = connections.values().filter(|c| c.is_active).count();
         status.total_connections = connections.len();
-        
+
+        self.recompute_attachment_state(status.active_connections, status.is_running).await;
+
         if removed_count > 0 {
-            info!("🧹 清理不活跃连接: {} 个", removed_count);
+            info!("🧹 清理不活跃连接: {} 个 (其中因连接预算驱逐 {} 个)", removed_count, over_budget_evicted);
         }
-        
+
         Ok(removed_count)
     }
+
+    /// 带宽快照：聚合吞吐量EWMA，加上按送收速率之和排序的前`top_n`个连接
+    /// （"谈话最多的对端"），供运营者观测真实吞吐而非长期为零的占位字段
+    pub async fn bandwidth_snapshot(&self, top_n: usize) -> BandwidthSnapshot {
+        let network_bandwidth_kbps = self.status.read().await.network_bandwidth_kbps;
+
+        let mut top_talkers: Vec<ConnectionStatus> = self.connections.read().await.values().cloned().collect();
+        top_talkers.sort_by(|a, b| {
+            let a_total = a.send_rate_kbps + a.recv_rate_kbps;
+            let b_total = b.send_rate_kbps + b.recv_rate_kbps;
+            b_total.partial_cmp(&a_total).unwrap_or(std::cmp::Ordering::Equal)
+        });
+        top_talkers.truncate(top_n);
+
+        BandwidthSnapshot { network_bandwidth_kbps, top_talkers }
+    }
+}
+
+/// [`NetworkManager::bandwidth_snapshot`]的返回值：聚合吞吐量加上占比
+/// 最高的若干个连接
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BandwidthSnapshot {
+    /// 全网聚合带宽的EWMA（KB/s）
+    pub network_bandwidth_kbps: f64,
+    /// 按发送+接收速率之和排序的前N个连接
+    pub top_talkers: Vec<ConnectionStatus>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_config() -> NetworkConfig {
+        NetworkConfig {
+            bootstrap_nodes: vec![],
+            event_budget_per_tick: 4,
+            ..NetworkConfig::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_run_tick_processes_queued_outbound_within_budget() {
+        let manager = NetworkManager::new("node_a".to_string(), test_config()).unwrap();
+        manager.connect_to_peer("peer_b").await.unwrap();
+
+        for i in 0..10 {
+            manager
+                .queue_outbound_message("peer_b", format!("msg-{i}").into_bytes())
+                .await
+                .unwrap();
+        }
+
+        let processed = manager.run_tick(4).await.unwrap();
+        assert_eq!(processed, 4);
+
+        let status = manager.get_status().await;
+        assert_eq!(status.messages_sent, 4);
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_drains_inflight_events_then_stops() {
+        let manager = NetworkManager::new("node_a".to_string(), test_config()).unwrap();
+        manager.connect_to_peer("peer_b").await.unwrap();
+        manager
+            .queue_outbound_message("peer_b", b"in-flight".to_vec())
+            .await
+            .unwrap();
+
+        manager.shutdown().await.unwrap();
+
+        let status = manager.get_status().await;
+        assert_eq!(status.messages_sent, 1);
+        assert!(!status.is_running);
+    }
+
+    #[tokio::test]
+    async fn test_reserved_peer_admitted_despite_deny_mode_and_full_budget() {
+        let config = NetworkConfig {
+            max_connections: 1,
+            ..test_config()
+        };
+        let manager = NetworkManager::new("node_a".to_string(), config).unwrap();
+
+        manager.connect_to_peer("peer_b").await.unwrap();
+        manager.set_non_reserved_mode(NonReservedPeerMode::Deny).await;
+
+        // 非保留节点在Deny模式下被拒绝
+        assert!(manager.connect_to_peer("peer_c").await.is_err());
+
+        // 保留节点即使预算已满、即使处于Deny模式也必须被接纳
+        manager.add_reserved_peer("peer_d".to_string()).await;
+        manager.connect_to_peer("peer_d").await.unwrap();
+
+        let status = manager.get_status().await;
+        assert_eq!(status.active_connections, 2);
+        assert_eq!(status.outbound_connections, 2);
+    }
+
+    #[tokio::test]
+    async fn test_ip_filter_rejects_denied_address() {
+        let manager = NetworkManager::new("node_a".to_string(), test_config()).unwrap();
+        manager
+            .set_ip_filter(IpFilter {
+                allow: vec![],
+                deny: vec![IpCidr::parse("10.0.0.0/8").unwrap()],
+            })
+            .await;
+
+        assert!(manager.connect_to_peer("10.1.2.3:9000").await.is_err());
+        assert!(manager.connect_to_peer("192.168.1.1:9000").await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_attachment_state_progresses_with_active_connections() {
+        let config = NetworkConfig {
+            attachment_weak_threshold: 1,
+            attachment_good_threshold: 2,
+            attachment_strong_threshold: 3,
+            max_connections: 3,
+            ..test_config()
+        };
+        let manager = NetworkManager::new("node_a".to_string(), config).unwrap();
+
+        assert_eq!(manager.attachment_state().await, AttachmentState::Detached);
+        assert!(manager.is_detached().await);
+
+        manager.connect_to_peer("peer_b").await.unwrap();
+        assert_eq!(manager.attachment_state().await, AttachmentState::AttachedWeak);
+        assert!(manager.is_attached().await);
+
+        manager.connect_to_peer("peer_c").await.unwrap();
+        assert_eq!(manager.attachment_state().await, AttachmentState::AttachedGood);
+
+        manager.connect_to_peer("peer_d").await.unwrap();
+        assert_eq!(manager.attachment_state().await, AttachmentState::FullyAttached);
+
+        manager.disconnect_from_peer("peer_d").await.unwrap();
+        assert_eq!(manager.attachment_state().await, AttachmentState::AttachedGood);
+    }
+
+    #[tokio::test]
+    async fn test_attachment_state_returns_to_detached_when_last_peer_leaves() {
+        let manager = NetworkManager::new("node_a".to_string(), test_config()).unwrap();
+        manager.connect_to_peer("peer_b").await.unwrap();
+        assert!(manager.is_attached().await);
+
+        manager.disconnect_from_peer("peer_b").await.unwrap();
+
+        assert_eq!(manager.attachment_state().await, AttachmentState::Detached);
+        assert!(manager.is_detached().await);
+    }
+
+    /// 一个把发送的消息原样回显的内存连接，用于在没有真实网络的单测里
+    /// 验证`send_message`会经由注册的传输对象收发，而不是仅仅记录日志
+    struct LoopbackConnection {
+        sent: Arc<tokio::sync::Mutex<Vec<Vec<u8>>>>,
+    }
+
+    #[async_trait]
+    impl PeerConnection for LoopbackConnection {
+        async fn send(&self, message: &[u8]) -> Result<()> {
+            self.sent.lock().await.push(message.to_vec());
+            Ok(())
+        }
+
+        async fn recv(&self) -> Result<Vec<u8>> {
+            std::future::pending().await
+        }
+
+        async fn close(&self) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    struct LoopbackConnectHandler {
+        sent: Arc<tokio::sync::Mutex<Vec<Vec<u8>>>>,
+    }
+
+    #[async_trait]
+    impl ProtocolConnectHandler for LoopbackConnectHandler {
+        async fn connect(&self, _addr: &str) -> Result<Box<dyn PeerConnection>> {
+            Ok(Box::new(LoopbackConnection { sent: self.sent.clone() }))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_send_message_routes_through_registered_transport() {
+        let manager = NetworkManager::new("node_a".to_string(), test_config()).unwrap();
+        let sent = Arc::new(tokio::sync::Mutex::new(Vec::new()));
+
+        manager
+            .register_transport(ConnectionType::Direct, Arc::new(LoopbackConnectHandler { sent: sent.clone() }))
+            .await;
+
+        manager.connect_to_peer("peer_b").await.unwrap();
+        manager.send_message("peer_b", b"hello".to_vec()).await.unwrap();
+
+        assert_eq!(sent.lock().await.as_slice(), &[b"hello".to_vec()]);
+    }
+
+    #[tokio::test]
+    async fn test_external_address_absent_before_start() {
+        let manager = NetworkManager::new("node_a".to_string(), test_config()).unwrap();
+
+        assert_eq!(manager.get_external_address().await, None);
+        assert_eq!(manager.get_status().await.external_address, None);
+    }
+
+    #[tokio::test]
+    async fn test_teardown_nat_mappings_clears_discovered_state() {
+        let manager = NetworkManager::new("node_a".to_string(), test_config()).unwrap();
+
+        *manager.external_address.write().await = Some("203.0.113.1".to_string());
+        *manager.local_ip.write().await = Some("192.168.1.2".to_string());
+
+        manager.teardown_nat_mappings().await;
+
+        assert_eq!(manager.get_external_address().await, None);
+        assert_eq!(manager.local_ip.read().await.clone(), None);
+    }
+
+    #[tokio::test]
+    async fn test_heartbeat_check_computes_per_peer_and_aggregate_bandwidth() {
+        let manager = NetworkManager::new("node_a".to_string(), test_config()).unwrap();
+        manager.connect_to_peer("peer_b").await.unwrap();
+
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+
+        {
+            let mut connections = manager.connections.write().await;
+            let connection = connections.get_mut("peer_b").unwrap();
+            connection.bytes_sent = 10 * 1024;
+            connection.last_activity = now;
+        }
+        manager.bandwidth_samples.write().await.insert(
+            "peer_b".to_string(),
+            BandwidthSample { sampled_at: now - 10, bytes_sent: 0, bytes_received: 0 },
+        );
+
+        manager.heartbeat_check().await.unwrap();
+
+        let connections = manager.get_connections().await;
+        let peer_b = connections.iter().find(|c| c.peer_id == "peer_b").unwrap();
+        assert!(peer_b.send_rate_kbps > 0.0);
+
+        let snapshot = manager.bandwidth_snapshot(5).await;
+        assert!(snapshot.network_bandwidth_kbps > 0.0);
+        assert_eq!(snapshot.top_talkers[0].peer_id, "peer_b");
+    }
+
+    #[tokio::test]
+    async fn test_cleanup_evicts_zero_throughput_connection_when_over_budget() {
+        let mut config = test_config();
+        config.max_connections = 1;
+        let manager = NetworkManager::new("node_a".to_string(), config).unwrap();
+
+        manager.connect_to_peer("peer_old").await.unwrap();
+        manager.connect_to_peer("peer_new").await.unwrap();
+
+        {
+            let mut connections = manager.connections.write().await;
+            connections.get_mut("peer_old").unwrap().last_activity -= 5;
+        }
+
+        // both connections are zero-throughput and well within
+        // `max_inactive_secs`, so only the over-budget eviction signal
+        // (not the ordinary inactivity sweep) should remove one of them
+        let removed = manager.cleanup_inactive_connections(3600).await.unwrap();
+
+        assert_eq!(removed, 1);
+        let connections = manager.get_connections().await;
+        assert_eq!(connections.len(), 1);
+        assert_eq!(connections[0].peer_id, "peer_new");
+    }
 }
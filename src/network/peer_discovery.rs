@@ -3,10 +3,186 @@
 //! 负责发现和管理网络中的其他节点
 
 use crate::types::{NodeId, NodeInfo, Timestamp, current_timestamp};
-use std::collections::{HashMap, HashSet};
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::sync::Arc;
 use tokio::sync::RwLock;
 
+/// Kademlia路由表里每个k-bucket最多保存的节点数
+const KADEMLIA_K: usize = 20;
+/// 迭代查找时单轮并发查询的节点数
+const KADEMLIA_ALPHA: usize = 3;
+/// 节点key的位数（SHA-256输出，对应256个k-bucket）
+const KADEMLIA_KEY_BITS: usize = 256;
+
+/// 对节点ID求SHA-256，得到它在Kademlia地址空间里的256位key
+fn node_key(node_id: &NodeId) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(node_id.as_bytes());
+    hasher.finalize().into()
+}
+
+/// 两个256位key的XOR距离
+fn xor_distance(a: &[u8; 32], b: &[u8; 32]) -> [u8; 32] {
+    let mut out = [0u8; 32];
+    for i in 0..32 {
+        out[i] = a[i] ^ b[i];
+    }
+    out
+}
+
+/// 距离的最高有效位位置（0 = 最低位，255 = 最高位），即它所属的k-bucket
+/// 下标；距离为全零（同一节点）时返回0
+fn bucket_index(distance: &[u8; 32]) -> usize {
+    for (byte_index, byte) in distance.iter().enumerate() {
+        if *byte != 0 {
+            let bit_in_byte = 7 - byte.leading_zeros() as usize;
+            return (31 - byte_index) * 8 + bit_in_byte;
+        }
+    }
+    0
+}
+
+/// Kademlia k-bucket中的一条记录
+#[derive(Debug, Clone)]
+struct KBucketEntry {
+    node_id: NodeId,
+    address: String,
+    last_seen: Timestamp,
+}
+
+/// 单个k-bucket：最多保存`k=20`条记录，按最近见过时间从旧到新排列
+/// （队首是最久未见过的，LRU淘汰时优先考虑它）
+#[derive(Debug, Clone, Default)]
+struct KBucket {
+    entries: VecDeque<KBucketEntry>,
+}
+
+impl KBucket {
+    /// 若节点已在桶中，把它移到队尾（标记为最近见过）并返回`true`
+    fn touch(&mut self, node_id: &NodeId) -> bool {
+        if let Some(pos) = self.entries.iter().position(|entry| &entry.node_id == node_id) {
+            if let Some(mut entry) = self.entries.remove(pos) {
+                entry.last_seen = current_timestamp();
+                self.entries.push_back(entry);
+            }
+            true
+        } else {
+            false
+        }
+    }
+
+    fn is_full(&self) -> bool {
+        self.entries.len() >= KADEMLIA_K
+    }
+
+    fn least_recently_seen(&self) -> Option<&KBucketEntry> {
+        self.entries.front()
+    }
+
+    fn evict_lru(&mut self) -> Option<KBucketEntry> {
+        self.entries.pop_front()
+    }
+
+    fn push(&mut self, entry: KBucketEntry) {
+        self.entries.push_back(entry);
+    }
+}
+
+/// Kademlia式路由表：按与本地节点的XOR距离，把已知节点组织进256个
+/// k-bucket（下标为距离最高有效位的位置），每个桶最多`k=20`条记录
+pub struct KademliaTable {
+    local_node_id: NodeId,
+    local_key: [u8; 32],
+    buckets: RwLock<Vec<KBucket>>,
+}
+
+impl KademliaTable {
+    /// 创建一张空路由表
+    pub fn new(local_node_id: NodeId) -> Self {
+        let local_key = node_key(&local_node_id);
+        Self {
+            local_node_id,
+            local_key,
+            buckets: RwLock::new((0..KADEMLIA_KEY_BITS).map(|_| KBucket::default()).collect()),
+        }
+    }
+
+    fn bucket_index_for(&self, node_id: &NodeId) -> usize {
+        bucket_index(&xor_distance(&self.local_key, &node_key(node_id)))
+    }
+
+    /// 记录一次见到某节点（收到它的消息，或在查找中被其他节点返回）。
+    ///
+    /// 若该节点已在对应的k-bucket中，只更新其最近见过时间。若桶未满，
+    /// 直接插入。若桶已满且这是一个未知节点，按Kademlia的做法不会立刻丢弃
+    /// 它——返回桶里最久未见过的节点，调用方应先ping它，再调用
+    /// [`Self::resolve_pending_eviction`]决定是保留旧节点还是用新节点替换它。
+    pub async fn record_seen(&self, node_id: &NodeId, address: &str) -> Option<(NodeId, String)> {
+        if *node_id == self.local_node_id {
+            return None;
+        }
+
+        let idx = self.bucket_index_for(node_id);
+        let mut buckets = self.buckets.write().await;
+        let bucket = &mut buckets[idx];
+
+        if bucket.touch(node_id) {
+            return None;
+        }
+
+        if !bucket.is_full() {
+            bucket.push(KBucketEntry {
+                node_id: node_id.clone(),
+                address: address.to_string(),
+                last_seen: current_timestamp(),
+            });
+            return None;
+        }
+
+        bucket
+            .least_recently_seen()
+            .map(|entry| (entry.node_id.clone(), entry.address.clone()))
+    }
+
+    /// 完成对满桶里最久未见节点的ping之后调用：`survived=true`表示旧节点
+    /// 仍然在线（保留它、丢弃新节点），`false`表示旧节点已淘汰（用新节点
+    /// 取而代之）
+    pub async fn resolve_pending_eviction(&self, stale_node_id: &NodeId, new_node_id: &NodeId, new_address: &str, survived: bool) {
+        let idx = self.bucket_index_for(stale_node_id);
+        let mut buckets = self.buckets.write().await;
+        let bucket = &mut buckets[idx];
+
+        if survived {
+            bucket.touch(stale_node_id);
+        } else {
+            bucket.evict_lru();
+            bucket.push(KBucketEntry {
+                node_id: new_node_id.clone(),
+                address: new_address.to_string(),
+                last_seen: current_timestamp(),
+            });
+        }
+    }
+
+    /// 从所有k-bucket中取出与目标节点XOR距离最近的`k`个已知节点
+    pub async fn closest(&self, target: &NodeId, k: usize) -> Vec<(NodeId, String)> {
+        let target_key = node_key(target);
+        let buckets = self.buckets.read().await;
+
+        let mut all: Vec<([u8; 32], NodeId, String)> = Vec::new();
+        for bucket in buckets.iter() {
+            for entry in &bucket.entries {
+                let distance = xor_distance(&target_key, &node_key(&entry.node_id));
+                all.push((distance, entry.node_id.clone(), entry.address.clone()));
+            }
+        }
+
+        all.sort_by(|a, b| a.0.cmp(&b.0));
+        all.into_iter().take(k).map(|(_, id, addr)| (id, addr)).collect()
+    }
+}
+
 /// 节点发现配置
 #[derive(Debug, Clone)]
 pub struct DiscoveryConfig {
@@ -53,6 +229,8 @@ pub struct PeerDiscovery {
     bootstrap_nodes: Vec<String>,
     /// 发现历史
     discovery_history: Vec<DiscoveryEvent>,
+    /// Kademlia式路由表，支持按XOR距离做最近邻查找
+    kademlia: KademliaTable,
 }
 
 /// 发现事件
@@ -84,12 +262,14 @@ pub enum DiscoveryEventType {
 impl PeerDiscovery {
     /// 创建新的节点发现器
     pub fn new(local_node_id: NodeId, config: DiscoveryConfig) -> Self {
+        let kademlia = KademliaTable::new(local_node_id.clone());
         Self {
             local_node_id,
             config,
             discovered_peers: Arc::new(RwLock::new(HashMap::new())),
             bootstrap_nodes: Vec::new(),
             discovery_history: Vec::new(),
+            kademlia,
         }
     }
     
@@ -124,35 +304,136 @@ impl PeerDiscovery {
             ("node3".to_string(), "127.0.0.1:8083".to_string(), "validator".to_string(), 650.0),
         ];
         
-        let mut peers = self.discovered_peers.write().await;
-        
-        for (node_id, address, tier, reputation) in mock_peers {
-            if node_id == self.local_node_id {
-                continue; // 跳过本地节点
+        let mut newly_known = Vec::new();
+
+        {
+            let mut peers = self.discovered_peers.write().await;
+
+            for (node_id, address, tier, reputation) in mock_peers {
+                if node_id == self.local_node_id {
+                    continue; // 跳过本地节点
+                }
+
+                let peer_info = PeerInfo {
+                    node_id: node_id.clone(),
+                    address: address.clone(),
+                    tier,
+                    reputation,
+                    last_discovered: current_timestamp(),
+                    online: true,
+                    connection_count: 0,
+                };
+
+                peers.insert(node_id.clone(), peer_info);
+                newly_known.push((node_id, address));
             }
-            
-            let peer_info = PeerInfo {
-                node_id: node_id.clone(),
-                address,
-                tier,
-                reputation,
-                last_discovered: current_timestamp(),
-                online: true,
-                connection_count: 0,
-            };
-            
-            peers.insert(node_id.clone(), peer_info);
-            
+
+            println!("  发现 {} 个节点", peers.len());
+        }
+
+        for (node_id, address) in newly_known {
+            self.record_known_peer(&node_id, &address).await;
             self.record_discovery_event(
                 DiscoveryEventType::NodeDiscovered,
                 node_id,
                 "通过主动发现找到节点".to_string(),
             );
         }
-        
-        println!("  发现 {} 个节点", peers.len());
+
         Ok(())
     }
+
+    /// 把一次"见到某节点"的事实喂给Kademlia路由表。若对应的k-bucket已满
+    /// 且该节点是新面孔，按规范先ping桶里最久未见的节点，根据它是否还活
+    /// 着决定保留旧节点还是换成新节点。
+    async fn record_known_peer(&self, node_id: &NodeId, address: &str) {
+        if let Some((stale_id, stale_address)) = self.kademlia.record_seen(node_id, address).await {
+            let survived = self.ping_node(&stale_address).await;
+            self.kademlia
+                .resolve_pending_eviction(&stale_id, node_id, address, survived)
+                .await;
+        }
+    }
+
+    /// 对候选淘汰节点做一次存活探测；当前实现没有真实的网络层可用，
+    /// 因此只要该节点仍被标记为在线就视为存活
+    async fn ping_node(&self, address: &str) -> bool {
+        let peers = self.discovered_peers.read().await;
+        peers.values().any(|peer| peer.address == address && peer.online)
+    }
+
+    /// 把一批引导节点地址登记进Kademlia路由表，然后以本地节点为目标做一次
+    /// 自查找（self-lookup），借此填满离自己最近的k-bucket
+    pub async fn bootstrap_kademlia(&self) {
+        for address in &self.bootstrap_nodes {
+            self.record_known_peer(address, address).await;
+        }
+
+        let local_node_id = self.local_node_id.clone();
+        self.iterative_find_node(&local_node_id, KADEMLIA_K, |_, _| async { Vec::new() })
+            .await;
+    }
+
+    /// 从本地已知的路由表中取出与目标节点XOR距离最近的`k`个节点，不发起
+    /// 任何查询
+    pub async fn find_closest_peers(&self, target: &NodeId, k: usize) -> Vec<(NodeId, String)> {
+        self.kademlia.closest(target, k).await
+    }
+
+    /// 迭代式Kademlia查找：从本地已知的α个最近节点出发，通过`query_fn`
+    /// 向它们发起FIND_NODE式查询（`query_fn(queried_node, target)`返回该
+    /// 节点视角里离目标最近的若干节点），把返回结果合并进候选短名单并按
+    /// XOR距离重新排序，直到某一轮查询都找不到比已知最近节点更近的新节点
+    /// 为止。
+    pub async fn iterative_find_node<F, Fut>(&self, target: &NodeId, k: usize, query_fn: F) -> Vec<(NodeId, String)>
+    where
+        F: Fn(NodeId, NodeId) -> Fut,
+        Fut: std::future::Future<Output = Vec<(NodeId, String)>>,
+    {
+        let target_key = node_key(target);
+        let mut shortlist = self.kademlia.closest(target, k).await;
+        let mut queried: HashSet<NodeId> = HashSet::new();
+
+        loop {
+            let to_query: Vec<(NodeId, String)> = shortlist
+                .iter()
+                .filter(|(node_id, _)| !queried.contains(node_id))
+                .take(KADEMLIA_ALPHA)
+                .cloned()
+                .collect();
+
+            if to_query.is_empty() {
+                break;
+            }
+
+            let best_before = shortlist.first().map(|(node_id, _)| xor_distance(&target_key, &node_key(node_id)));
+
+            for (node_id, _address) in &to_query {
+                queried.insert(node_id.clone());
+                let responses = query_fn(node_id.clone(), target.clone()).await;
+
+                for (candidate_id, candidate_address) in responses {
+                    if candidate_id == self.local_node_id {
+                        continue;
+                    }
+                    self.record_known_peer(&candidate_id, &candidate_address).await;
+                    if !shortlist.iter().any(|(id, _)| *id == candidate_id) {
+                        shortlist.push((candidate_id, candidate_address));
+                    }
+                }
+            }
+
+            shortlist.sort_by_key(|(node_id, _)| xor_distance(&target_key, &node_key(node_id)));
+            shortlist.truncate(k);
+
+            let best_after = shortlist.first().map(|(node_id, _)| xor_distance(&target_key, &node_key(node_id)));
+            if best_after.is_none() || best_after >= best_before {
+                break;
+            }
+        }
+
+        shortlist
+    }
     
     /// 被动发现节点
     async fn passive_discovery(&self) -> Result<(), String> {
@@ -313,6 +594,11 @@ mod tests {
         let peers = discovery.get_discovered_peers().await;
         assert!(!peers.is_empty());
         
+        // 测试Kademlia路由表已经随主动发现填充，且能按XOR距离排序返回最近节点
+        let closest = discovery.find_closest_peers(&"node1".to_string(), 2).await;
+        assert!(!closest.is_empty());
+        assert!(closest.len() <= 2);
+
         // 测试获取统计
         let stats = discovery.get_discovery_stats().await;
         assert!(stats.total_peers > 0);
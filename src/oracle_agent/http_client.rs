@@ -4,6 +4,130 @@ use serde_json::Value;
 use std::time::Duration;
 use log::{debug, warn};
 
+/// 一个数据源到标量数值的解析函数
+pub type SourceParser = Box<dyn Fn(&Value) -> Result<f64> + Send + Sync>;
+
+/// 多源共识查询中配置的一个数据源
+pub struct ConsensusSource {
+    /// 数据源名称，便于在报告中识别
+    pub name: String,
+    /// 请求的URL
+    pub url: String,
+    /// 单独覆盖的超时时间（秒），为None时使用客户端默认值
+    pub timeout_secs: Option<u64>,
+    /// 将JSON响应解析为标量数值的函数
+    pub parser: SourceParser,
+}
+
+/// 单个数据源在一次多源聚合中的结果
+#[derive(Debug, Clone)]
+pub enum SourceOutcome {
+    /// 成功获取并通过了离群值校验
+    Agreed {
+        /// 数据源名称
+        name: String,
+        /// 解析出的数值
+        value: f64,
+    },
+    /// 成功获取但偏离中位数过多，被判定为离群值
+    Outlier {
+        /// 数据源名称
+        name: String,
+        /// 解析出的数值
+        value: f64,
+    },
+    /// 请求超时
+    TimedOut {
+        /// 数据源名称
+        name: String,
+    },
+    /// 请求失败或解析失败
+    Failed {
+        /// 数据源名称
+        name: String,
+        /// 失败原因
+        error: String,
+    },
+}
+
+/// 多源共识聚合的配置
+#[derive(Debug, Clone)]
+pub struct AggregationConfig {
+    /// 相对中位数的偏离比例超过该阈值即视为离群值（例如0.05代表5%）
+    pub outlier_relative_threshold: f64,
+    /// 基于MAD（中位数绝对偏差）的离群值判定系数k；设为None则只用相对阈值
+    pub mad_k: Option<f64>,
+    /// 至少需要多少个成功且非离群的数据源，才认为聚合结果可信
+    pub quorum: usize,
+}
+
+impl Default for AggregationConfig {
+    fn default() -> Self {
+        Self {
+            outlier_relative_threshold: 0.05,
+            mad_k: Some(3.0),
+            quorum: 2,
+        }
+    }
+}
+
+/// 一次多源聚合的完整报告
+#[derive(Debug, Clone)]
+pub struct AggregationReport {
+    /// 在通过校验的数据源上重新计算的聚合值（均值）
+    pub aggregated_value: f64,
+    /// 在通过校验的数据源上重新计算的中位数
+    pub median: f64,
+    /// 每个数据源的详细结果
+    pub sources: Vec<SourceOutcome>,
+}
+
+impl AggregationReport {
+    /// 通过校验（非离群、成功获取）的数据源数量
+    pub fn agreed_count(&self) -> usize {
+        self.sources
+            .iter()
+            .filter(|s| matches!(s, SourceOutcome::Agreed { .. }))
+            .count()
+    }
+}
+
+/// 计算一组数值的中位数（要求输入非空）
+fn median_of(values: &[f64]) -> f64 {
+    let mut sorted = values.to_vec();
+    sorted.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    let n = sorted.len();
+    if n % 2 == 1 {
+        sorted[n / 2]
+    } else {
+        (sorted[n / 2 - 1] + sorted[n / 2]) / 2.0
+    }
+}
+
+/// 计算一组数值相对其中位数的MAD（中位数绝对偏差）
+fn median_absolute_deviation(values: &[f64], median: f64) -> f64 {
+    let deviations: Vec<f64> = values.iter().map(|v| (v - median).abs()).collect();
+    median_of(&deviations)
+}
+
+/// 判断某个数值相对于整体分布是否为离群值
+fn is_outlier(value: f64, all_values: &[f64], median: f64, config: &AggregationConfig) -> bool {
+    if let Some(k) = config.mad_k {
+        let mad = median_absolute_deviation(all_values, median);
+        if mad > f64::EPSILON {
+            return (value - median).abs() > k * mad;
+        }
+    }
+
+    let relative_deviation = if median.abs() > f64::EPSILON {
+        (value - median).abs() / median.abs()
+    } else {
+        (value - median).abs()
+    };
+
+    relative_deviation > config.outlier_relative_threshold
+}
+
 /// HTTP客户端
 pub struct HttpClient {
     client: Client,
@@ -101,6 +225,124 @@ impl HttpClient {
         Ok(text)
     }
     
+    /// 从多个数据源并发获取同一个标量数值，剔除离群值后返回一个健壮的聚合结果
+    ///
+    /// 对每个数据源并发请求并用其自带的解析器抽取数值，计算所有成功结果的
+    /// 中位数，再剔除偏离中位数过多的离群值（相对阈值或MAD-based截断），
+    /// 最后在幸存值上重新计算均值/中位数。若通过校验的数据源数量低于
+    /// `config.quorum`，返回错误而不是给出一个不可信的结果。
+    pub async fn fetch_consensus(
+        &self,
+        sources: Vec<ConsensusSource>,
+        config: &AggregationConfig,
+    ) -> Result<AggregationReport> {
+        if sources.is_empty() {
+            return Err(anyhow!("未配置任何数据源"));
+        }
+
+        let default_timeout = self.default_timeout_secs;
+        let client = self.client.clone();
+
+        let fetches = sources.into_iter().map(|source| {
+            let client = client.clone();
+            async move {
+                let timeout = source.timeout_secs.unwrap_or(default_timeout);
+                let name = source.name.clone();
+
+                let response = client
+                    .get(&source.url)
+                    .timeout(Duration::from_secs(timeout))
+                    .send()
+                    .await;
+
+                match response {
+                    Err(e) if e.is_timeout() => SourceOutcome::TimedOut { name },
+                    Err(e) => SourceOutcome::Failed {
+                        name,
+                        error: format!("HTTP请求失败: {}", e),
+                    },
+                    Ok(resp) if !resp.status().is_success() => SourceOutcome::Failed {
+                        name,
+                        error: format!("HTTP错误: {}", resp.status()),
+                    },
+                    Ok(resp) => match resp.json::<Value>().await {
+                        Err(e) => SourceOutcome::Failed {
+                            name,
+                            error: format!("JSON解析失败: {}", e),
+                        },
+                        Ok(json) => match (source.parser)(&json) {
+                            Ok(value) => SourceOutcome::Agreed { name, value },
+                            Err(e) => SourceOutcome::Failed {
+                                name,
+                                error: format!("数值解析失败: {}", e),
+                            },
+                        },
+                    },
+                }
+            }
+        });
+
+        let mut results = futures::future::join_all(fetches).await;
+
+        let raw_values: Vec<f64> = results
+            .iter()
+            .filter_map(|s| match s {
+                SourceOutcome::Agreed { value, .. } => Some(*value),
+                _ => None,
+            })
+            .collect();
+
+        if raw_values.is_empty() {
+            return Err(anyhow!("所有数据源均获取失败，无法聚合"));
+        }
+
+        let raw_median = median_of(&raw_values);
+
+        for outcome in results.iter_mut() {
+            if let SourceOutcome::Agreed { name, value } = outcome {
+                if is_outlier(*value, &raw_values, raw_median, config) {
+                    warn!("数据源 {} 的值 {} 偏离中位数 {} 过多，判定为离群值", name, value, raw_median);
+                    *outcome = SourceOutcome::Outlier {
+                        name: name.clone(),
+                        value: *value,
+                    };
+                }
+            }
+        }
+
+        let survivors: Vec<f64> = results
+            .iter()
+            .filter_map(|s| match s {
+                SourceOutcome::Agreed { value, .. } => Some(*value),
+                _ => None,
+            })
+            .collect();
+
+        if survivors.len() < config.quorum {
+            return Err(anyhow!(
+                "未达到法定人数: 仅 {} 个数据源通过校验，需要至少 {} 个",
+                survivors.len(),
+                config.quorum
+            ));
+        }
+
+        let aggregated_value = survivors.iter().sum::<f64>() / survivors.len() as f64;
+        let survivor_median = median_of(&survivors);
+
+        debug!(
+            "多源聚合完成: {} 个来源通过校验，聚合值={}，中位数={}",
+            survivors.len(),
+            aggregated_value,
+            survivor_median
+        );
+
+        Ok(AggregationReport {
+            aggregated_value,
+            median: survivor_median,
+            sources: results,
+        })
+    }
+
     /// 健康检查
     pub async fn health_check(&self, url: &str) -> bool {
         match self.client
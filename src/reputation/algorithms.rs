@@ -1,9 +1,27 @@
 use crate::reputation::ReputationConfig;
 use std::f64::consts::E;
 
+/// 对一组样本求中位数（用于 MAD 离群点剔除）
+fn median_of(values: &[f64]) -> f64 {
+    let mut sorted = values.to_vec();
+    sorted.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    let mid = sorted.len() / 2;
+    if sorted.len() % 2 == 0 {
+        (sorted[mid - 1] + sorted[mid]) / 2.0
+    } else {
+        sorted[mid]
+    }
+}
+
+/// 中位数绝对偏差：`median(|xi - median|)`
+fn median_absolute_deviation(values: &[f64], median: f64) -> f64 {
+    let deviations: Vec<f64> = values.iter().map(|&x| (x - median).abs()).collect();
+    median_of(&deviations)
+}
+
 /// 信誉算法
 pub struct ReputationAlgorithms {
-    config: ReputationConfig,
+    pub(crate) config: ReputationConfig,
 }
 
 impl ReputationAlgorithms {
@@ -211,22 +229,91 @@ impl ReputationAlgorithms {
     }
     
     /// 计算信誉稳定性
+    ///
+    /// 使用鲁棒的、对近期样本更敏感的估计器，而不是对全量历史取原始总体方差
+    /// （后者会被单次大额惩罚永久拖垮，且不区分新旧样本）：
+    ///
+    /// 1. 先用 MAD（中位数绝对偏差）规则剔除离群点：`|x - median| > k · MAD`
+    ///    （`k ≈ 3`），避免一次性 slashing 冲击永久拉低稳定性；
+    /// 2. 在剔除离群点后的样本上计算指数加权移动平均（EWMA）与
+    ///    指数加权方差（EW-variance），`ewma_alpha` 越大越偏向最近样本；
+    /// 3. 把 EW 标准差（越小越稳定）与 EWMA 的趋势项（斜率符号/幅度）结合：
+    ///    持续回升的节点即使波动幅度相近，也应比在同一均值附近反复震荡的
+    ///    节点得分更高。
     pub fn calculate_stability_score(
         &self,
         score_history: &[f64],
     ) -> f64 {
+        const EWMA_ALPHA: f64 = 0.3;
+        const MAD_K: f64 = 3.0;
+
         if score_history.len() < 2 {
             return 1.0;
         }
-        
-        let mean = score_history.iter().sum::<f64>() / score_history.len() as f64;
-        let variance = score_history.iter()
-            .map(|&x| (x - mean).powi(2))
-            .sum::<f64>() / score_history.len() as f64;
-        
-        let std_dev = variance.sqrt();
-        
-        // 标准差越小，稳定性越高
-        1.0 / (1.0 + std_dev)
+
+        let median = median_of(score_history);
+        let mad = median_absolute_deviation(score_history, median);
+
+        let filtered: Vec<f64> = if mad > f64::EPSILON {
+            score_history
+                .iter()
+                .copied()
+                .filter(|&x| (x - median).abs() <= MAD_K * mad)
+                .collect()
+        } else {
+            // MAD为0说明样本高度集中（或全部相同），此时不剔除任何点，避免除零
+            score_history.to_vec()
+        };
+
+        let filtered = if filtered.len() >= 2 { filtered } else { score_history.to_vec() };
+
+        // 指数加权移动平均与指数加权方差，新样本权重更大
+        let mut ewma = filtered[0];
+        let mut ew_variance = 0.0;
+        for &x in &filtered[1..] {
+            let deviation = x - ewma;
+            ewma += EWMA_ALPHA * deviation;
+            ew_variance = (1.0 - EWMA_ALPHA) * (ew_variance + EWMA_ALPHA * deviation.powi(2));
+        }
+        let ew_std_dev = ew_variance.sqrt();
+
+        // 趋势项：EWMA相对首个样本的斜率，归一化到大致 [-1, 1]
+        let span = (filtered.len() - 1) as f64;
+        let raw_slope = (ewma - filtered[0]) / span;
+        let trend = (raw_slope / (raw_slope.abs() + 50.0)).clamp(-1.0, 1.0);
+
+        // 波动越小越稳定，趋势向好（持续回升）额外加分，趋势向坏则扣分
+        let volatility_component = 1.0 / (1.0 + ew_std_dev);
+        (volatility_component + 0.2 * trend).clamp(0.0, 1.0)
+    }
+
+    /// 使用指标 DSL 求值一条自定义的综合评分定义，替代固定权重的
+    /// [`calculate_comprehensive_score`]。
+    ///
+    /// `component_scores` 以组件名为键（例如 `accuracy`、`response_time`、
+    /// `availability`），`metric_source` 是形如
+    /// `score = avg(accuracy) * 0.5 + avg(response_time) * 0.3 + avg(availability) * 0.2`
+    /// 的单条指标定义。这样运营者可以在不重新编译 crate 的前提下调整
+    /// 信誉评分口径，或定义新的派生信誉信号。
+    ///
+    /// [`calculate_comprehensive_score`]: Self::calculate_comprehensive_score
+    pub fn calculate_score_from_metric_def(
+        &self,
+        metric_source: &str,
+        component_scores: &[(&str, f64)],
+    ) -> Result<f64, crate::test::metrics_dsl::DslError> {
+        let definition = crate::test::metrics_dsl::parse_metric_def(metric_source)?;
+
+        let mut row = crate::test::metrics_dsl::Row::new();
+        for (name, value) in component_scores {
+            row.insert(
+                (*name).to_string(),
+                crate::test::metrics_dsl::MetricValue::Number(*value),
+            );
+        }
+
+        let rows = vec![row];
+        let evaluator = crate::test::metrics_dsl::Evaluator::new(&rows);
+        evaluator.evaluate(&definition)
     }
 }
@@ -14,3 +14,8 @@ pub use reputation_metrics::{ReputationMetrics, PerformanceMetrics};
 // 内部模块
 pub(crate) mod algorithms;
 pub(crate) mod storage;
+pub(crate) mod tier_proof;
+pub(crate) mod trust_level;
+
+pub use tier_proof::{ScoreCommitment, TierProof};
+pub use trust_level::{TrustLevel, TrustLevelConfig, TrustProgress, LevelRequirement};
@@ -0,0 +1,399 @@
+//! 信誉等级的零知识证明
+//!
+//! 为节点的信誉分数提供隐私保护：节点可以向共识对等方证明"分数达到某个
+//! 等级 / 超过某个投票门槛"，而不泄露具体分数。建模参照 Groth16/PGHR13
+//! 这类基于配对的 SNARK 在 BN-254 曲线上所做的区间证明：节点先对分数做
+//! Pedersen 承诺 `C = g^score · h^r`，再对 `score - threshold ∈ [0, 2^n)`
+//! 做比特分解区间证明，使验证者只能得到"分数 ≥ 门槛"这一比特信息，而
+//! `score` 与盲因子 `r` 始终保持隐藏。
+//!
+//! 每个比特的区间证明用 Chaum-Pedersen 析取（OR）证明：证明者对 0/1 两个
+//! 分支分别给出一对 `(第一轮消息, 挑战份额, 响应)`，只有真实分支诚实计算，
+//! 另一分支的消息由模拟器从随机选取的挑战份额和响应反推得到，两者在验证者
+//! 看来不可区分；两个挑战份额之和必须等于 Fiat-Shamir 派生的总挑战，从而
+//! 保证证明者确实知道某一分支的合法开启，但不暴露是哪一个。
+//!
+//! 注意：本模块用大素数域上的模幂运算模拟 Pedersen 承诺与 Sigma 协议式
+//! 的比特证明，尚未接入真正的 BN-254 配对库；数据结构与接口已对齐真实
+//! SNARK 方案（承诺 + 区间证明 + 非交互 Fiat-Shamir 挑战），未来替换底层
+//! 原语（例如改为 `ark-bn254`/`ark-groth16`）即可升级为生产级实现。
+
+use crate::reputation::algorithms::ReputationAlgorithms;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+/// 用于模拟 Pedersen 承诺的素数域阶（生产实现应替换为 BN-254 标量域）
+const FIELD_MODULUS: u128 = 2_305_843_009_213_693_951; // 2^61 - 1，梅森素数
+/// 生成元 g
+const GENERATOR_G: u128 = 5;
+/// 生成元 h（与 g 的相对离散对数未知，模拟 Pedersen 承诺的第二个生成元）
+const GENERATOR_H: u128 = 7;
+/// 区间证明支持的最大比特数：`score - threshold` 必须落在 `[0, 2^n)` 内
+const RANGE_BITS: u32 = 20;
+
+/// 模幂运算：`base^exp mod modulus`
+fn pow_mod(base: u128, exp: u128, modulus: u128) -> u128 {
+    let mut result = 1u128;
+    let mut base = base % modulus;
+    let mut exp = exp;
+
+    while exp > 0 {
+        if exp & 1 == 1 {
+            result = result.wrapping_mul(base) % modulus;
+        }
+        exp >>= 1;
+        base = base.wrapping_mul(base) % modulus;
+    }
+
+    result
+}
+
+/// 求 `value` 在素数模 `modulus` 下的乘法逆元（费马小定理：`value^(p-2) mod p`）
+fn mod_inverse(value: u128, modulus: u128) -> u128 {
+    pow_mod(value, modulus - 2, modulus)
+}
+
+/// 在 `[0, modulus)` 内取一个伪随机数，用于 Sigma 协议的第一轮随机数与
+/// 模拟分支的挑战/响应
+fn random_field_element(modulus: u128) -> u128 {
+    (rand::thread_rng().gen::<u64>() as u128) % modulus
+}
+
+/// 信誉分数的 Pedersen 承诺 `C = g^score · h^r mod p`
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ScoreCommitment {
+    /// 承诺值
+    pub commitment: u128,
+}
+
+/// 单个比特的披露证明：证明 `bit_commitment` 开启为 0 或 1 而不泄露具体是
+/// 哪一个（见模块文档的 Chaum-Pedersen 析取证明说明）
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+struct BitProof {
+    /// 该比特的承诺 `C = g^bit · h^blinding`
+    bit_commitment: u128,
+    /// 分支 0（`bit = 0`，目标 `C = h^r`）的第一轮消息 `a0 = h^s0`
+    a0: u128,
+    /// 分支 1（`bit = 1`，目标 `C·g⁻¹ = h^r`）的第一轮消息 `a1 = h^s1`
+    a1: u128,
+    /// 分支 0 的挑战份额
+    c0: u128,
+    /// 分支 1 的挑战份额
+    c1: u128,
+    /// 分支 0 的响应
+    z0: u128,
+    /// 分支 1 的响应
+    z1: u128,
+}
+
+/// 等级 / 投票门槛的零知识证明
+///
+/// 验证者拿到 [`ScoreCommitment`]、声明的 `threshold` 与本结构即可判断
+/// "分数 ≥ 门槛"是否成立，而无需获知真实分数或盲因子。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TierProof {
+    /// 分数的 Pedersen 承诺
+    pub commitment: ScoreCommitment,
+    /// 声明达到的门槛（等级下限或投票权重所需的最小分数）
+    pub threshold: f64,
+    /// `score - threshold` 的逐比特承诺与证明（共 `RANGE_BITS` 位）
+    bit_proofs: Vec<BitProof>,
+    /// 绑定承诺、门槛与各比特承诺的 Fiat-Shamir 挑战种子
+    challenge_seed: [u8; 32],
+}
+
+impl ReputationAlgorithms {
+    /// 生成等级 / 门槛证明：证明 `score >= threshold` 而不泄露 `score` 或 `blinding`
+    ///
+    /// `blinding` 是调用方持有的随机盲因子，必须保密存储以便后续重新证明。
+    pub fn prove_tier(&self, score: f64, threshold: f64, blinding: u64) -> Result<TierProof, String> {
+        if score < threshold {
+            return Err("分数未达到声明的门槛，无法生成有效证明".to_string());
+        }
+
+        let diff = (score - threshold).round().max(0.0) as u128;
+        if diff >= (1u128 << RANGE_BITS) {
+            return Err(format!(
+                "分数与门槛之差超出区间证明支持的位宽({}位)",
+                RANGE_BITS
+            ));
+        }
+
+        let score_scaled = score.round().max(0.0) as u128;
+        let r = blinding as u128 % FIELD_MODULUS;
+        let commitment = ScoreCommitment {
+            commitment: (pow_mod(GENERATOR_G, score_scaled, FIELD_MODULUS)
+                * pow_mod(GENERATOR_H, r, FIELD_MODULUS))
+                % FIELD_MODULUS,
+        };
+
+        // 对 diff 做比特分解，为每一位生成独立的承诺。除最后一位外，每位的
+        // 盲因子都用 r 和位索引派生；最后一位的盲因子反过来求解，使得
+        // `Σ bit_blinding_i · 2^i ≡ r (mod FIELD_MODULUS)`——这样各比特承诺
+        // 按权重相乘，正好同态地重建出 `commitment`（见 `verify_tier`），把
+        // 比特分解和分数承诺绑在一起，而不是两组互相独立的数字。
+        let bits_len = RANGE_BITS as usize;
+        let mut bit_blindings = Vec::with_capacity(bits_len);
+        let mut weighted_sum = 0u128;
+        for i in 0..bits_len - 1 {
+            let blinding = (r.wrapping_add(i as u128).wrapping_mul(31) + 17) % FIELD_MODULUS;
+            let weight = 1u128 << i;
+            weighted_sum = (weighted_sum + (blinding.wrapping_mul(weight) % FIELD_MODULUS)) % FIELD_MODULUS;
+            bit_blindings.push(blinding);
+        }
+        let last_weight = 1u128 << (bits_len - 1);
+        let remaining = (r + FIELD_MODULUS - weighted_sum % FIELD_MODULUS) % FIELD_MODULUS;
+        let last_blinding = remaining.wrapping_mul(mod_inverse(last_weight, FIELD_MODULUS)) % FIELD_MODULUS;
+        bit_blindings.push(last_blinding);
+
+        let bit_commitments: Vec<(u128, u128, u128)> = bit_blindings
+            .into_iter()
+            .enumerate()
+            .map(|(i, bit_blinding)| {
+                let bit = (diff >> i) & 1;
+                let bit_commitment = (pow_mod(GENERATOR_G, bit, FIELD_MODULUS)
+                    * pow_mod(GENERATOR_H, bit_blinding, FIELD_MODULUS))
+                    % FIELD_MODULUS;
+                (bit_commitment, bit_blinding, bit)
+            })
+            .collect();
+
+        // Fiat-Shamir：挑战种子绑定承诺、门槛与全部比特承诺，使证明不可被替换拼接
+        let mut hasher = Sha256::new();
+        hasher.update(commitment.commitment.to_be_bytes());
+        hasher.update(threshold.to_bits().to_be_bytes());
+        for (bit_commitment, _, _) in &bit_commitments {
+            hasher.update(bit_commitment.to_be_bytes());
+        }
+        let challenge_seed: [u8; 32] = hasher.finalize().into();
+
+        let g_inv = mod_inverse(GENERATOR_G, FIELD_MODULUS);
+
+        let bit_proofs = bit_commitments
+            .into_iter()
+            .enumerate()
+            .map(|(i, (bit_commitment, bit_blinding, bit))| {
+                // 非真实分支：先随机选挑战份额与响应，再反推出与之自洽的
+                // 第一轮消息——这一步不需要知道该分支对应的开启方式
+                let fake_target = if bit == 0 {
+                    (bit_commitment * g_inv) % FIELD_MODULUS
+                } else {
+                    bit_commitment
+                };
+                let fake_challenge = random_field_element(FIELD_MODULUS);
+                let fake_response = random_field_element(FIELD_MODULUS);
+                let fake_a = (pow_mod(GENERATOR_H, fake_response, FIELD_MODULUS)
+                    * mod_inverse(pow_mod(fake_target, fake_challenge, FIELD_MODULUS), FIELD_MODULUS))
+                    % FIELD_MODULUS;
+
+                // 真实分支：诚实地选随机数并计算第一轮消息，待总挑战确定后
+                // 再求出这一分支的挑战份额与响应
+                let real_nonce = random_field_element(FIELD_MODULUS);
+                let real_a = pow_mod(GENERATOR_H, real_nonce, FIELD_MODULUS);
+
+                let (a0, a1) = if bit == 0 { (real_a, fake_a) } else { (fake_a, real_a) };
+                let total_challenge = Self::derive_bit_challenge(&challenge_seed, i, bit_commitment, a0, a1);
+                let real_challenge = (total_challenge + FIELD_MODULUS - fake_challenge) % FIELD_MODULUS;
+                let real_response =
+                    (real_nonce + real_challenge.wrapping_mul(bit_blinding) % FIELD_MODULUS) % FIELD_MODULUS;
+
+                let (c0, z0, c1, z1) = if bit == 0 {
+                    (real_challenge, real_response, fake_challenge, fake_response)
+                } else {
+                    (fake_challenge, fake_response, real_challenge, real_response)
+                };
+
+                BitProof {
+                    bit_commitment,
+                    a0,
+                    a1,
+                    c0,
+                    c1,
+                    z0,
+                    z1,
+                }
+            })
+            .collect();
+
+        Ok(TierProof {
+            commitment,
+            threshold,
+            bit_proofs,
+            challenge_seed,
+        })
+    }
+
+    /// 验证等级 / 门槛证明：只需要 `commitment` 与 `proof`，无需获知 `score` 或盲因子
+    pub fn verify_tier(&self, commitment: &ScoreCommitment, threshold: f64, proof: &TierProof) -> bool {
+        if proof.commitment != *commitment || proof.threshold != threshold {
+            return false;
+        }
+        if proof.bit_proofs.len() != RANGE_BITS as usize {
+            return false;
+        }
+
+        // 重新计算挑战种子，确保证明未被篡改或替换
+        let mut hasher = Sha256::new();
+        hasher.update(commitment.commitment.to_be_bytes());
+        hasher.update(threshold.to_bits().to_be_bytes());
+        for bp in &proof.bit_proofs {
+            hasher.update(bp.bit_commitment.to_be_bytes());
+        }
+        let recomputed: [u8; 32] = hasher.finalize().into();
+        if recomputed != proof.challenge_seed {
+            return false;
+        }
+
+        // 逐位校验析取证明：两个分支的挑战份额之和必须等于重新派生的总
+        // 挑战，且两个分支各自的 Sigma 等式都必须成立；伪造的承诺或响应
+        // （不对应任何合法比特开启）在此处被拒绝，而合法证明的两个分支
+        // 等式恒同时成立，验证者无法据此分辨真实比特是 0 还是 1
+        if !proof
+            .bit_proofs
+            .iter()
+            .enumerate()
+            .all(|(i, bp)| Self::verify_bit_proof(&proof.challenge_seed, i, bp))
+        {
+            return false;
+        }
+
+        // 把比特承诺按权重（2^i）同态相乘，重建出 `diff` 的承诺，再乘上
+        // `g^threshold`：只有比特分解确实与 `commitment = g^score · h^r`
+        // 共享同一个盲因子 r 时，两边才会相等，从而把比特分解绑定回分数
+        // 承诺本身，而不只是绑定到彼此
+        let combined_diff_commitment = proof
+            .bit_proofs
+            .iter()
+            .enumerate()
+            .fold(1u128, |acc, (i, bp)| {
+                (acc * pow_mod(bp.bit_commitment, 1u128 << i, FIELD_MODULUS)) % FIELD_MODULUS
+            });
+        let threshold_scaled = threshold.round().max(0.0) as u128;
+        let reconstructed_commitment =
+            (combined_diff_commitment * pow_mod(GENERATOR_G, threshold_scaled, FIELD_MODULUS)) % FIELD_MODULUS;
+
+        reconstructed_commitment == commitment.commitment
+    }
+
+    /// 校验单个比特的 Chaum-Pedersen 析取证明：两个分支的挑战份额之和
+    /// 等于 Fiat-Shamir 派生的总挑战，且 `h^z0 == a0 · C^c0`（分支 0：
+    /// `C = h^r`）与 `h^z1 == a1 · (C·g⁻¹)^c1`（分支 1：`C·g⁻¹ = h^r`）
+    /// 同时成立。两个等式都只依赖公开量，合法证明对真实比特是 0 还是 1
+    /// 恒成立，不会向验证者泄露具体是哪一个。
+    fn verify_bit_proof(challenge_seed: &[u8; 32], bit_index: usize, bp: &BitProof) -> bool {
+        let total_challenge =
+            Self::derive_bit_challenge(challenge_seed, bit_index, bp.bit_commitment, bp.a0, bp.a1);
+        if (bp.c0 + bp.c1) % FIELD_MODULUS != total_challenge {
+            return false;
+        }
+
+        let lhs0 = pow_mod(GENERATOR_H, bp.z0, FIELD_MODULUS);
+        let rhs0 = (bp.a0 * pow_mod(bp.bit_commitment, bp.c0, FIELD_MODULUS)) % FIELD_MODULUS;
+        if lhs0 != rhs0 {
+            return false;
+        }
+
+        let g_inv = mod_inverse(GENERATOR_G, FIELD_MODULUS);
+        let target1 = (bp.bit_commitment * g_inv) % FIELD_MODULUS;
+        let lhs1 = pow_mod(GENERATOR_H, bp.z1, FIELD_MODULUS);
+        let rhs1 = (bp.a1 * pow_mod(target1, bp.c1, FIELD_MODULUS)) % FIELD_MODULUS;
+
+        lhs1 == rhs1
+    }
+
+    /// 从挑战种子、比特索引、比特承诺与两个分支的第一轮消息派生该比特的
+    /// Fiat-Shamir 总挑战（两个分支的挑战份额之和必须等于该值）
+    fn derive_bit_challenge(challenge_seed: &[u8; 32], bit_index: usize, bit_commitment: u128, a0: u128, a1: u128) -> u128 {
+        let mut hasher = Sha256::new();
+        hasher.update(challenge_seed);
+        hasher.update((bit_index as u64).to_be_bytes());
+        hasher.update(bit_commitment.to_be_bytes());
+        hasher.update(a0.to_be_bytes());
+        hasher.update(a1.to_be_bytes());
+        let digest = hasher.finalize();
+        u128::from_be_bytes(digest[0..16].try_into().unwrap()) % FIELD_MODULUS
+    }
+
+    /// 基于验证通过的等级证明计算投票权重，而不需要获知真实分数
+    ///
+    /// 只要 [`ReputationAlgorithms::verify_tier`] 通过，调用方就可以安全地
+    /// 认定节点真实分数不低于 `threshold`，于是复用 `threshold` 作为
+    /// [`ReputationAlgorithms::calculate_voting_weight`] 的分数输入，
+    /// 实现"承诺驱动"的匿名治理投票权重。
+    pub fn calculate_voting_weight_from_commitment(
+        &self,
+        commitment: &ScoreCommitment,
+        threshold: f64,
+        proof: &TierProof,
+        staked_amount: u64,
+    ) -> Option<f64> {
+        if !self.verify_tier(commitment, threshold, proof) {
+            return None;
+        }
+
+        Some(self.calculate_voting_weight(threshold, staked_amount))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::reputation::reputation_manager::ReputationConfig;
+
+    fn algorithms() -> ReputationAlgorithms {
+        ReputationAlgorithms::new(ReputationConfig::default())
+    }
+
+    #[test]
+    fn test_prove_and_verify_tier_success() {
+        let algo = algorithms();
+        let proof = algo.prove_tier(720.0, 700.0, 42).expect("应能生成证明");
+
+        assert!(algo.verify_tier(&proof.commitment, 700.0, &proof));
+    }
+
+    #[test]
+    fn test_prove_tier_rejects_score_below_threshold() {
+        let algo = algorithms();
+        let result = algo.prove_tier(650.0, 700.0, 42);
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_verify_tier_rejects_wrong_threshold() {
+        let algo = algorithms();
+        let proof = algo.prove_tier(720.0, 700.0, 42).expect("应能生成证明");
+
+        assert!(!algo.verify_tier(&proof.commitment, 750.0, &proof));
+    }
+
+    #[test]
+    fn test_voting_weight_from_commitment_matches_plain_calculation() {
+        let algo = algorithms();
+        let proof = algo.prove_tier(720.0, 700.0, 7).expect("应能生成证明");
+
+        let weight = algo
+            .calculate_voting_weight_from_commitment(&proof.commitment, 700.0, &proof, 100)
+            .expect("验证通过应返回权重");
+
+        assert_eq!(weight, algo.calculate_voting_weight(700.0, 100));
+    }
+
+    #[test]
+    fn test_bit_proofs_are_randomized_and_hide_bit_values() {
+        let algo = algorithms();
+        let proof_a = algo.prove_tier(720.0, 700.0, 42).expect("应能生成证明");
+        let proof_b = algo.prove_tier(720.0, 700.0, 42).expect("应能生成证明");
+
+        assert!(algo.verify_tier(&proof_a.commitment, 700.0, &proof_a));
+        assert!(algo.verify_tier(&proof_b.commitment, 700.0, &proof_b));
+
+        // 相同的 score/threshold/blinding 两次生成的比特证明不应相同：OR
+        // 证明的模拟分支依赖新鲜随机数，而不是比特值的确定性函数；若两次
+        // 证明完全一致，就说明证明其实是比特值的确定性编码，会泄露真实
+        // 比特是 0 还是 1（进而泄露 score 与 r）
+        assert_ne!(proof_a.bit_proofs, proof_b.bit_proofs);
+    }
+}
@@ -0,0 +1,259 @@
+//! Lox 风格的时间门控信任等级
+//!
+//! 在现有的静态等级阈值（[`ReputationAlgorithms::calculate_tier_thresholds`]）
+//! 与指数衰减（[`ReputationAlgorithms::calculate_decay`]）之上，叠加一层信任
+//! 等级递进子系统，建模参照匿名凭证桥分发（Lox）的信任等级：节点从 L0 逐级
+//! 晋升到 Ln，每一级的晋升都需要满足 (a) 在当前等级的最小停留时间，以及
+//! (b) 自上次晋升以来累计的、准确性为正的成功交互次数，两者均可按等级配置。
+//! 更高等级解锁更多能力：更多邀请新节点加入的名额、更高的基础奖励倍率。
+//!
+//! 关键的"阻塞迁移"：当检测到节点的对等方/网关被攻陷或大规模失败时，允许
+//! 节点迁移到一份新的凭证上，但只保留"降级后"的信任等级（而不是直接清零
+//! 到 L0），并附带一个冷却期，防止被阻塞的节点立刻重新晋级。
+
+use crate::reputation::algorithms::ReputationAlgorithms;
+use serde::{Deserialize, Serialize};
+
+/// 信任等级：L0（最低）到 Ln，数值越大等级越高
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct TrustLevel(pub u8);
+
+impl TrustLevel {
+    /// 初始等级 L0
+    pub const MIN: TrustLevel = TrustLevel(0);
+
+    /// 下一个等级
+    pub fn next(self) -> TrustLevel {
+        TrustLevel(self.0.saturating_add(1))
+    }
+
+    /// 降低指定数量的等级，不会低于 L0
+    pub fn demote_by(self, levels: u8) -> TrustLevel {
+        TrustLevel(self.0.saturating_sub(levels))
+    }
+}
+
+/// 晋升到某一等级所需满足的条件
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LevelRequirement {
+    /// 目标等级
+    pub level: TrustLevel,
+    /// 在上一级的最小停留时间（秒）
+    pub min_dwell_secs: u64,
+    /// 自上次晋升以来需要累计的、accuracy-positive 成功交互次数
+    pub min_positive_interactions: u64,
+    /// 达到该等级后解锁的邀请新节点名额
+    pub invitation_quota: u32,
+    /// 达到该等级后的基础奖励倍率
+    pub reward_multiplier: f64,
+}
+
+/// 节点在信任等级体系中的进度追踪
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrustProgress {
+    /// 当前等级
+    pub level: TrustLevel,
+    /// 进入当前等级（或完成迁移）的时间戳（秒）
+    pub since: u64,
+    /// 自上次晋升以来累计的 accuracy-positive 成功交互次数
+    pub positive_interactions_since_promotion: u64,
+    /// 阻塞迁移后的冷却截止时间（秒），None 表示当前不处于冷却期
+    pub blockage_cooldown_until: Option<u64>,
+}
+
+impl TrustProgress {
+    /// 创建一个从 L0 开始的新进度记录
+    pub fn new(now: u64) -> Self {
+        Self {
+            level: TrustLevel::MIN,
+            since: now,
+            positive_interactions_since_promotion: 0,
+            blockage_cooldown_until: None,
+        }
+    }
+
+    /// 记录一次 accuracy-positive 的成功交互
+    pub fn record_positive_interaction(&mut self) {
+        self.positive_interactions_since_promotion += 1;
+    }
+
+    /// 当前是否仍处于阻塞迁移后的冷却期
+    pub fn is_in_cooldown(&self, now: u64) -> bool {
+        self.blockage_cooldown_until
+            .map(|until| now < until)
+            .unwrap_or(false)
+    }
+}
+
+/// 信任等级体系的配置：每一级的晋升要求，以及阻塞迁移的降级幅度与冷却时间
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrustLevelConfig {
+    /// 各等级的晋升要求，按目标等级升序排列
+    pub levels: Vec<LevelRequirement>,
+    /// 阻塞迁移时要降低的等级数（而不是直接清零）
+    pub blockage_demotion: u8,
+    /// 阻塞迁移后的冷却时间（秒），冷却期内无法再次晋升
+    pub blockage_cooldown_secs: u64,
+}
+
+impl Default for TrustLevelConfig {
+    fn default() -> Self {
+        Self {
+            levels: vec![
+                LevelRequirement {
+                    level: TrustLevel(1),
+                    min_dwell_secs: 3600,
+                    min_positive_interactions: 5,
+                    invitation_quota: 1,
+                    reward_multiplier: 1.0,
+                },
+                LevelRequirement {
+                    level: TrustLevel(2),
+                    min_dwell_secs: 24 * 3600,
+                    min_positive_interactions: 20,
+                    invitation_quota: 3,
+                    reward_multiplier: 1.2,
+                },
+                LevelRequirement {
+                    level: TrustLevel(3),
+                    min_dwell_secs: 7 * 24 * 3600,
+                    min_positive_interactions: 100,
+                    invitation_quota: 10,
+                    reward_multiplier: 1.5,
+                },
+            ],
+            blockage_demotion: 2,
+            blockage_cooldown_secs: 3 * 24 * 3600,
+        }
+    }
+}
+
+impl ReputationAlgorithms {
+    /// 判断节点当前是否满足晋升到下一等级的条件
+    pub fn can_advance(&self, config: &TrustLevelConfig, progress: &TrustProgress, now: u64) -> bool {
+        if progress.is_in_cooldown(now) {
+            return false;
+        }
+
+        let next_level = progress.level.next();
+        let Some(requirement) = config.levels.iter().find(|r| r.level == next_level) else {
+            return false; // 已经是配置中的最高等级
+        };
+
+        let dwell_secs = now.saturating_sub(progress.since);
+
+        dwell_secs >= requirement.min_dwell_secs
+            && progress.positive_interactions_since_promotion >= requirement.min_positive_interactions
+    }
+
+    /// 尝试将节点晋升到下一等级；返回是否发生了晋升
+    pub fn advance_level(&self, config: &TrustLevelConfig, progress: &mut TrustProgress, now: u64) -> bool {
+        if !self.can_advance(config, progress, now) {
+            return false;
+        }
+
+        progress.level = progress.level.next();
+        progress.since = now;
+        progress.positive_interactions_since_promotion = 0;
+
+        true
+    }
+
+    /// 阻塞迁移：当节点的对等方/网关被判定为攻陷或大规模失败时，迁移到一份
+    /// 保留"降级后"信任等级的新凭证，而不是清零到 L0，并附带冷却期。
+    ///
+    /// 返回迁移后的新进度记录，以及应当应用到 [`ReputationScore`] 的信誉调整量
+    /// （负值，幅度与被降低的等级数成正比）。
+    ///
+    /// [`ReputationScore`]: crate::reputation::ReputationScore
+    pub fn migrate_after_blockage(
+        &self,
+        config: &TrustLevelConfig,
+        progress: &TrustProgress,
+        now: u64,
+    ) -> (TrustProgress, f64) {
+        let demoted_levels = progress.level.0.min(config.blockage_demotion);
+        let new_level = progress.level.demote_by(config.blockage_demotion);
+
+        let mut new_progress = TrustProgress::new(now);
+        new_progress.level = new_level;
+        new_progress.blockage_cooldown_until = Some(now + config.blockage_cooldown_secs);
+
+        // 调整幅度与被降低的等级数成正比，复用与惩罚相同的系数以保持口径一致
+        let reputation_adjustment = -(demoted_levels as f64) * 50.0 * self.config.penalty_multiplier;
+
+        (new_progress, reputation_adjustment)
+    }
+
+    /// 获取配置中与给定等级匹配的晋升要求（用于查询邀请名额/奖励倍率等能力）
+    pub fn requirement_for_level<'a>(
+        &self,
+        config: &'a TrustLevelConfig,
+        level: TrustLevel,
+    ) -> Option<&'a LevelRequirement> {
+        config.levels.iter().find(|r| r.level == level)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::reputation::reputation_manager::ReputationConfig;
+
+    fn algorithms() -> ReputationAlgorithms {
+        ReputationAlgorithms::new(ReputationConfig::default())
+    }
+
+    #[test]
+    fn test_cannot_advance_before_dwell_time_or_interactions() {
+        let algo = algorithms();
+        let config = TrustLevelConfig::default();
+        let progress = TrustProgress::new(1_000);
+
+        // 既没有停留足够时间，也没有积累交互次数
+        assert!(!algo.can_advance(&config, &progress, 1_000));
+    }
+
+    #[test]
+    fn test_advance_level_once_requirements_are_met() {
+        let algo = algorithms();
+        let config = TrustLevelConfig::default();
+        let mut progress = TrustProgress::new(0);
+
+        for _ in 0..5 {
+            progress.record_positive_interaction();
+        }
+
+        let now = config.levels[0].min_dwell_secs;
+        assert!(algo.advance_level(&config, &mut progress, now));
+        assert_eq!(progress.level, TrustLevel(1));
+        assert_eq!(progress.positive_interactions_since_promotion, 0);
+    }
+
+    #[test]
+    fn test_migrate_after_blockage_keeps_reduced_level_and_cooldown() {
+        let algo = algorithms();
+        let config = TrustLevelConfig::default();
+        let mut progress = TrustProgress::new(0);
+        progress.level = TrustLevel(3);
+
+        let (migrated, adjustment) = algo.migrate_after_blockage(&config, &progress, 10_000);
+
+        assert_eq!(migrated.level, TrustLevel(1)); // 降两级而不是清零
+        assert!(migrated.is_in_cooldown(10_000));
+        assert!(adjustment < 0.0);
+    }
+
+    #[test]
+    fn test_migrated_node_cannot_immediately_re_advance() {
+        let algo = algorithms();
+        let config = TrustLevelConfig::default();
+        let mut progress = TrustProgress::new(0);
+        progress.level = TrustLevel(3);
+
+        let (migrated, _) = algo.migrate_after_blockage(&config, &progress, 10_000);
+
+        // 虽然等级只有 L1，但冷却期内即使时间和交互都满足也不能晋升
+        assert!(!algo.can_advance(&config, &migrated, 10_000 + config.levels[0].min_dwell_secs));
+    }
+}
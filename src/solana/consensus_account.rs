@@ -0,0 +1,143 @@
+//! 共识结果的链上账户布局
+//!
+//! Solana程序本身是无状态的，所有数据都存放在独立的账户里。之前
+//! [`build_transaction_data`](crate::solana::consensus_deployer::SolanaDeployer::build_transaction_data)
+//! 手工拼接定长前缀 + 小端字节的做法，没有任何已部署的程序能一致地把它
+//! 反序列化回来。这里改为用 `borsh` 定义确切的账户记录布局，并为每个
+//! `consensus_id` 推导一个确定性的程序派生地址（PDA），使链下客户端和链上
+//! 程序对"这份共识结果存在哪个账户里、字段怎么排布"有完全一致的理解。
+
+use crate::solana::consensus_deployer::{AgentConsensusResult, MerkleCommitment};
+use crate::solana::merkle::Hash;
+use crate::solana::pda;
+use borsh::{BorshDeserialize, BorshSerialize};
+
+/// 用于推导共识账户PDA的种子前缀
+const CONSENSUS_ACCOUNT_SEED: &[u8] = b"consensus";
+
+/// 共识结果的链上账户记录
+///
+/// 智能体因果图数据不会逐个内联（参见 [`MerkleCommitment`]），账户里只保存
+/// 32字节的Merkle根，以及供验证者核对数量的 `valid_agent_count`/`outlier_count`。
+#[derive(Debug, Clone, PartialEq, BorshSerialize, BorshDeserialize)]
+pub struct ConsensusAccount {
+    /// 共识ID
+    pub consensus_id: String,
+    /// 共识值
+    pub consensus_value: f64,
+    /// 因果图相似度
+    pub consensus_similarity: f64,
+    /// 通过率
+    pub pass_rate: f64,
+    /// 时间戳
+    pub timestamp: i64,
+    /// 合约版本
+    pub contract_version: String,
+    /// 有效智能体数量
+    pub valid_agent_count: u32,
+    /// 异常智能体数量
+    pub outlier_count: u32,
+    /// 智能体因果图数据的Merkle根
+    pub agent_graphs_root: Hash,
+    /// 该账户的PDA bump seed，写回账户里便于链上程序做地址校验
+    pub bump: u8,
+}
+
+impl ConsensusAccount {
+    /// 从 [`AgentConsensusResult`] 构建链上账户记录
+    pub fn from_consensus_result(consensus_result: &AgentConsensusResult, bump: u8) -> Self {
+        let agent_graphs_root = MerkleCommitment::build(&consensus_result.agent_graphs).root();
+
+        Self {
+            consensus_id: consensus_result.consensus_id.clone(),
+            consensus_value: consensus_result.consensus_value,
+            consensus_similarity: consensus_result.consensus_similarity,
+            pass_rate: consensus_result.pass_rate,
+            timestamp: consensus_result.timestamp,
+            contract_version: consensus_result.contract_version.clone(),
+            valid_agent_count: consensus_result.valid_agents.len() as u32,
+            outlier_count: consensus_result.outliers.len() as u32,
+            agent_graphs_root,
+            bump,
+        }
+    }
+
+    /// 把链上账户记录还原为 [`AgentConsensusResult`]
+    ///
+    /// 这是有损的：账户里只保存了智能体因果图数据的Merkle根，具体的
+    /// `valid_agents`/`outliers`/`agent_graphs` 列表并不上链，因此这里还原出的
+    /// 是空列表。调用方若需要完整的智能体图数据，需要结合
+    /// [`MerkleCommitment::merkle_proof`] 从别处（例如本地缓存）取得原始数据，
+    /// 再用 `agent_graphs_root` 校验其确实属于该次共识。
+    pub fn to_partial_consensus_result(&self) -> AgentConsensusResult {
+        AgentConsensusResult {
+            consensus_id: self.consensus_id.clone(),
+            scenario: String::new(),
+            intervention: String::new(),
+            valid_agents: Vec::new(),
+            outliers: Vec::new(),
+            consensus_value: self.consensus_value,
+            consensus_similarity: self.consensus_similarity,
+            pass_rate: self.pass_rate,
+            timestamp: self.timestamp,
+            contract_version: self.contract_version.clone(),
+            agent_graphs: Vec::new(),
+        }
+    }
+}
+
+/// 从 `consensus_id` 和程序ID推导出该共识账户的PDA及其bump seed
+pub fn derive_consensus_pda(program_id: &[u8; 32], consensus_id: &str) -> ([u8; 32], u8) {
+    pda::find_program_address(&[CONSENSUS_ACCOUNT_SEED, consensus_id.as_bytes()], program_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solana::consensus_deployer::create_consensus_result;
+
+    fn sample_result() -> AgentConsensusResult {
+        create_consensus_result(
+            "consensus_account_test".to_string(),
+            "测试场景".to_string(),
+            "测试干预".to_string(),
+            vec!["agent_1".to_string(), "agent_2".to_string()],
+            vec!["agent_3".to_string()],
+            100.0,
+            0.85,
+            0.66,
+            vec![],
+        )
+    }
+
+    #[test]
+    fn test_from_consensus_result_records_agent_counts() {
+        let result = sample_result();
+        let account = ConsensusAccount::from_consensus_result(&result, 253);
+
+        assert_eq!(account.valid_agent_count, 2);
+        assert_eq!(account.outlier_count, 1);
+        assert_eq!(account.bump, 253);
+    }
+
+    #[test]
+    fn test_borsh_round_trip() {
+        let result = sample_result();
+        let account = ConsensusAccount::from_consensus_result(&result, 253);
+
+        let encoded = borsh::to_vec(&account).expect("borsh序列化失败");
+        let decoded = ConsensusAccount::try_from_slice(&encoded).expect("borsh反序列化失败");
+
+        assert_eq!(account, decoded);
+    }
+
+    #[test]
+    fn test_derive_consensus_pda_is_deterministic() {
+        let program_id = [9u8; 32];
+        let (addr_1, bump_1) = derive_consensus_pda(&program_id, "consensus_account_test");
+        let (addr_2, bump_2) = derive_consensus_pda(&program_id, "consensus_account_test");
+
+        assert_eq!(addr_1, addr_2);
+        assert_eq!(bump_1, bump_2);
+    }
+}
@@ -2,9 +2,19 @@
 //!
 //! 将多智能体因果验证结果部署到Solana区块链
 
+use crate::solana::consensus_account::{derive_consensus_pda, ConsensusAccount};
+use crate::solana::consensus_history::{self, ConsensusHistory, ConsensusRecord};
+use crate::solana::local_ledger::LocalLedger;
+use crate::solana::merkle::{self, Hash};
 use anyhow::Result;
+use borsh::BorshDeserialize;
+use ed25519_dalek::{Signer, SigningKey};
 use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
 use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::{mpsc, RwLock};
 
 /// 智能体共识结果结构
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -75,6 +85,116 @@ pub enum TransactionStatus {
     Failed,
 }
 
+/// Solana线格式使用的紧凑变长整数编码（shortvec）：每字节取低7位存值、
+/// 最高位表示"后面还有字节"，用于账户列表、指令列表等变长数组的长度前缀
+fn encode_compact_u16(mut value: usize) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    loop {
+        let mut byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value != 0 {
+            byte |= 0x80;
+            bytes.push(byte);
+        } else {
+            bytes.push(byte);
+            break;
+        }
+    }
+    bytes
+}
+
+/// 对单个智能体的因果图数据求Merkle叶子哈希：按字段顺序拼接成规范字节串
+/// 后求SHA-256，长度可变的字段（字符串）前置一个长度前缀避免歧义。
+fn agent_graph_leaf(agent: &AgentGraphData) -> Hash {
+    let mut buf = Vec::new();
+
+    let id_bytes = agent.agent_id.as_bytes();
+    buf.extend_from_slice(&(id_bytes.len() as u32).to_le_bytes());
+    buf.extend_from_slice(id_bytes);
+
+    let model_bytes = agent.model_type.as_bytes();
+    buf.extend_from_slice(&(model_bytes.len() as u32).to_le_bytes());
+    buf.extend_from_slice(model_bytes);
+
+    buf.extend_from_slice(&(agent.node_count as u64).to_le_bytes());
+    buf.extend_from_slice(&(agent.edge_count as u64).to_le_bytes());
+    buf.extend_from_slice(&agent.intervention_effect.to_le_bytes());
+    buf.extend_from_slice(&agent.base_prediction.to_le_bytes());
+    buf.extend_from_slice(&agent.confidence.to_le_bytes());
+
+    merkle::hash_leaf(&buf)
+}
+
+/// 一批智能体因果图数据的Merkle承诺
+///
+/// 链上只需要保存32字节的根哈希（见 [`SolanaDeployer::build_transaction_data`]），
+/// 而不是把每个智能体的完整图数据都内联进交易——后者会随着`agent_graphs`
+/// 增长而线性膨胀，很容易超出Solana的交易大小限制。验证者凭借
+/// [`merkle_proof`](Self::merkle_proof) 给出的兄弟节点路径，即可确认某个
+/// 智能体的图数据确实属于该次共识，而无需下载全部智能体数据。
+pub struct MerkleCommitment {
+    tree: merkle::MerkleTree,
+    agent_index: HashMap<String, usize>,
+}
+
+impl MerkleCommitment {
+    /// 对一组智能体图数据建立Merkle承诺
+    pub fn build(agent_graphs: &[AgentGraphData]) -> Self {
+        let leaves: Vec<Hash> = agent_graphs.iter().map(agent_graph_leaf).collect();
+        let agent_index = agent_graphs
+            .iter()
+            .enumerate()
+            .map(|(index, agent)| (agent.agent_id.clone(), index))
+            .collect();
+
+        Self {
+            tree: merkle::MerkleTree::from_leaves(leaves),
+            agent_index,
+        }
+    }
+
+    /// Merkle根：唯一需要写上链的承诺值
+    pub fn root(&self) -> Hash {
+        self.tree.root()
+    }
+
+    /// 为指定智能体生成自底向上的兄弟节点路径（含左右方向标记）
+    pub fn merkle_proof(&self, agent_id: &str) -> Option<Vec<(Hash, bool)>> {
+        let index = *self.agent_index.get(agent_id)?;
+        self.tree.proof(index)
+    }
+
+    /// 验证一条Merkle证明：给定根、叶子哈希与证明路径，判断叶子是否属于该承诺
+    pub fn verify_proof(root: Hash, leaf: Hash, proof: &[(Hash, bool)]) -> bool {
+        merkle::verify_proof(root, leaf, proof)
+    }
+}
+
+/// 写入共识账户所需的指令数据：目标PDA及其Borsh编码的账户记录
+struct ConsensusInstruction {
+    /// 该共识结果写入的程序派生地址
+    pda: [u8; 32],
+    /// Borsh编码的 [`ConsensusAccount`] 数据
+    account_data: Vec<u8>,
+}
+
+/// `getSignatureStatuses` 查询结果的简化视图
+struct SignatureConfirmation {
+    /// 确认数，`None` 表示已最终确认（finalized）
+    confirmations: Option<u64>,
+    /// 交易执行错误（如果有）
+    err: Option<String>,
+}
+
+/// 部署模式：决定 [`SolanaDeployer::deploy_consensus_result`] 是否真正提交交易
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeployMode {
+    /// 通过 Solana JSON RPC 提交真实交易并轮询确认
+    Live,
+    /// 仅在本地生成模拟结果，不产生任何网络调用（默认，用于离线测试）
+    Simulate,
+}
+
 /// Solana区块链部署器
 pub struct SolanaDeployer {
     /// RPC URL
@@ -83,18 +203,67 @@ pub struct SolanaDeployer {
     pub wallet_path: String,
     /// 程序ID
     pub program_id: String,
+    /// 部署模式
+    deploy_mode: DeployMode,
+    /// 判定交易成功所需的最少确认数（`None` 确认数视为已最终确认）
+    confirmation_threshold: u32,
+    /// 用于JSON RPC请求的HTTP客户端
+    http_client: reqwest::Client,
+    /// 本地PoW锚定账本的挖矿难度（十六进制哈希前导零个数）
+    pow_difficulty: usize,
+    /// 当Solana RPC不可达时的离线/降级链
+    local_ledger: Arc<RwLock<LocalLedger>>,
+    /// 向 [`crate::solana::consensus_network::ConsensusNetwork`] 广播共识结果的发送端，
+    /// 使得一组节点可以在真正上链之前先就结果达成一致（见 [`Self::with_consensus_network`]）
+    consensus_broadcast_tx: Option<mpsc::Sender<AgentConsensusResult>>,
+    /// 内容寻址的历史记录：把每次部署按内容哈希去重并链接成一条链，
+    /// 使重复部署幂等、`query_consensus_result`/`get_agent_history` 有稳定的键可查
+    history: Arc<RwLock<ConsensusHistory>>,
 }
 
 impl SolanaDeployer {
-    /// 创建新的部署器
+    /// 创建新的部署器，默认使用 [`DeployMode::Simulate`]
     pub fn new(rpc_url: String, wallet_path: String, program_id: String) -> Self {
         Self {
             rpc_url,
             wallet_path,
             program_id,
+            deploy_mode: DeployMode::Simulate,
+            confirmation_threshold: 1,
+            http_client: reqwest::Client::new(),
+            pow_difficulty: 2,
+            local_ledger: Arc::new(RwLock::new(LocalLedger::new())),
+            consensus_broadcast_tx: None,
+            history: Arc::new(RwLock::new(ConsensusHistory::new())),
         }
     }
 
+    /// 设置部署模式
+    pub fn with_deploy_mode(mut self, deploy_mode: DeployMode) -> Self {
+        self.deploy_mode = deploy_mode;
+        self
+    }
+
+    /// 接入一个 [`crate::solana::consensus_network::ConsensusNetwork`] 的广播发送端：
+    /// 此后每次 [`Self::deploy_consensus_result`] 成功构建交易数据，都会把共识
+    /// 结果同时投递给这条通道，由gossip网络扩散给其他节点
+    pub fn with_consensus_network(mut self, broadcast_tx: mpsc::Sender<AgentConsensusResult>) -> Self {
+        self.consensus_broadcast_tx = Some(broadcast_tx);
+        self
+    }
+
+    /// 设置判定交易成功所需的最少确认数
+    pub fn with_confirmation_threshold(mut self, confirmation_threshold: u32) -> Self {
+        self.confirmation_threshold = confirmation_threshold;
+        self
+    }
+
+    /// 设置本地PoW锚定账本的挖矿难度
+    pub fn with_pow_difficulty(mut self, pow_difficulty: usize) -> Self {
+        self.pow_difficulty = pow_difficulty;
+        self
+    }
+
     /// 部署智能体共识结果到区块链
     pub async fn deploy_consensus_result(
         &self,
@@ -107,13 +276,36 @@ impl SolanaDeployer {
             .map_err(|e| anyhow::anyhow!("序列化失败: {}", e))?;
         println!("   📝 序列化数据长度: {} 字节", serialized_data.len());
         
-        // 2. 构建交易数据
-        let transaction_data = self.build_transaction_data(consensus_result)
+        // 2. 构建交易数据：推导出该共识结果对应的PDA，并把它编码为Borsh账户记录
+        let instruction = self.build_transaction_data(consensus_result)
             .map_err(|e| anyhow::anyhow!("构建交易数据失败: {}", e))?;
-        
-        // 3. 模拟发送交易到Solana
-        let transaction_result = self.simulate_transaction(&transaction_data).await
-            .map_err(|e| anyhow::anyhow!("模拟交易失败: {}", e))?;
+
+        // 2.5 在提交到Solana之前，把共识结果广播给gossip网络中的其他节点，
+        //     让整个mesh在任意一方上链前就先收敛到同一批结果
+        if let Some(broadcast_tx) = &self.consensus_broadcast_tx {
+            if let Err(e) = broadcast_tx.try_send(consensus_result.clone()) {
+                println!("   ⚠️ 广播共识结果到gossip网络失败: {}", e);
+            }
+        }
+
+        // 2.6 按内容哈希登记这次共识结果：重复部署同一份结果是幂等的，
+        //     且这条记录会被链接进一条可验证的历史链（见 `ConsensusHistory`）
+        let record = self.history.write().await.record(consensus_result);
+
+        // 3. 发送交易到Solana（模拟或真实取决于部署模式）；真实提交失败时
+        //    （例如RPC不可达）回退到本地PoW锚定账本，保证共识结果始终被
+        //    防篡改地记录下来
+        let transaction_result = match self.deploy_mode {
+            DeployMode::Simulate => self.simulate_transaction(&instruction, &record).await
+                .map_err(|e| anyhow::anyhow!("模拟交易失败: {}", e))?,
+            DeployMode::Live => match self.submit_live_transaction(&instruction).await {
+                Ok(result) => result,
+                Err(e) => {
+                    println!("   ⚠️ Solana RPC不可达，回退到本地PoW锚定账本: {}", e);
+                    self.record_on_local_ledger(consensus_result).await?
+                }
+            },
+        };
         
         match &transaction_result.status {
             TransactionStatus::Success => {
@@ -131,61 +323,92 @@ impl SolanaDeployer {
         Ok(transaction_result)
     }
 
-    /// 构建交易数据
-    fn build_transaction_data(&self, consensus_result: &AgentConsensusResult) -> Result<Vec<u8>> {
-        let mut transaction_data = Vec::new();
-        
-        // 添加共识ID
-        let consensus_id_bytes = consensus_result.consensus_id.as_bytes();
-        transaction_data.extend_from_slice(&(consensus_id_bytes.len() as u32).to_le_bytes());
-        transaction_data.extend_from_slice(consensus_id_bytes);
-        
-        // 添加场景描述
-        let scenario_bytes = consensus_result.scenario.as_bytes();
-        transaction_data.extend_from_slice(&(scenario_bytes.len() as u32).to_le_bytes());
-        transaction_data.extend_from_slice(scenario_bytes);
-        
-        // 添加干预措施
-        let intervention_bytes = consensus_result.intervention.as_bytes();
-        transaction_data.extend_from_slice(&(intervention_bytes.len() as u32).to_le_bytes());
-        transaction_data.extend_from_slice(intervention_bytes);
-        
-        // 添加共识值
-        transaction_data.extend_from_slice(&consensus_result.consensus_value.to_le_bytes());
-        
-        // 添加相似度
-        transaction_data.extend_from_slice(&consensus_result.consensus_similarity.to_le_bytes());
-        
-        // 添加通过率
-        transaction_data.extend_from_slice(&consensus_result.pass_rate.to_le_bytes());
-        
-        // 添加时间戳
-        transaction_data.extend_from_slice(&consensus_result.timestamp.to_le_bytes());
-        
-        // 添加智能体数量
-        transaction_data.extend_from_slice(&(consensus_result.valid_agents.len() as u32).to_le_bytes());
-        
-        // 添加每个智能体的数据
-        for agent in &consensus_result.valid_agents {
-            let agent_bytes = agent.as_bytes();
-            transaction_data.extend_from_slice(&(agent_bytes.len() as u32).to_le_bytes());
-            transaction_data.extend_from_slice(agent_bytes);
+    /// 解析 `program_id` 为32字节公钥
+    ///
+    /// 正常情况下 `program_id` 是一个base58编码的Solana公钥。部分测试/示例
+    /// 用的是人类可读的占位字符串（例如 `"CAUSAL111..."`），解不出恰好32字节，
+    /// 这种情况下退化为对原始字符串取SHA-256，保证PDA推导始终是确定性的。
+    fn program_id_bytes(&self) -> [u8; 32] {
+        if let Ok(decoded) = bs58::decode(&self.program_id).into_vec() {
+            if let Ok(bytes) = <[u8; 32]>::try_from(decoded.as_slice()) {
+                return bytes;
+            }
         }
-        
-        Ok(transaction_data)
+
+        let mut hasher = Sha256::new();
+        hasher.update(self.program_id.as_bytes());
+        hasher.finalize().into()
+    }
+
+    /// 构建写入共识账户所需的指令数据
+    ///
+    /// Solana程序是无状态的，状态都存放在独立的账户里：这里从 `consensus_id`
+    /// 和 `program_id` 推导出一个确定性的程序派生地址（PDA，见
+    /// [`derive_consensus_pda`]），并把共识结果编码为Borsh格式的
+    /// [`ConsensusAccount`]——智能体因果图数据依旧只提交 [`MerkleCommitment`]
+    /// 的32字节根哈希，而不是逐个内联，避免随着智能体数量增长超出Solana的
+    /// 交易大小限制。
+    fn build_transaction_data(&self, consensus_result: &AgentConsensusResult) -> Result<ConsensusInstruction> {
+        let program_id = self.program_id_bytes();
+        let (pda, bump) = derive_consensus_pda(&program_id, &consensus_result.consensus_id);
+        let account = ConsensusAccount::from_consensus_result(consensus_result, bump);
+        let account_data = borsh::to_vec(&account)
+            .map_err(|e| anyhow::anyhow!("Borsh序列化共识账户失败: {}", e))?;
+
+        Ok(ConsensusInstruction { pda, account_data })
+    }
+
+    /// 构建一笔真正可被Solana验证者解析的 legacy 交易消息：消息头
+    /// （签名者数/只读签名账户数/只读非签名账户数）+ 紧凑编码的账户列表
+    /// （费用支付方、目标PDA、程序ID）+ 最新区块哈希 + 紧凑编码的单条指令
+    /// （程序索引 + 账户索引列表 + Borsh账户数据），与 `solana-sdk` 的
+    /// `Message::new` 产出的线格式一致。
+    fn build_transaction_message(
+        fee_payer: &[u8; 32],
+        program_id: &[u8; 32],
+        instruction: &ConsensusInstruction,
+        recent_blockhash: &[u8; 32],
+    ) -> Vec<u8> {
+        // 账户顺序：[0] 费用支付方（签名、可写），[1] 目标PDA（可写、非签名），
+        // [2] 程序ID（只读、非签名）——符合Solana"签名者在前、只读账户在后"的约定
+        let account_keys = [fee_payer, &instruction.pda, program_id];
+
+        let mut message = Vec::new();
+        message.push(1u8); // num_required_signatures：仅费用支付方签名
+        message.push(0u8); // num_readonly_signed_accounts
+        message.push(1u8); // num_readonly_unsigned_accounts：程序ID
+
+        message.extend_from_slice(&encode_compact_u16(account_keys.len()));
+        for key in account_keys {
+            message.extend_from_slice(key);
+        }
+
+        message.extend_from_slice(recent_blockhash);
+
+        message.extend_from_slice(&encode_compact_u16(1)); // 指令数组：仅1条指令
+        message.push(2u8); // program_id_index：账户列表中程序ID的下标
+        message.extend_from_slice(&encode_compact_u16(2)); // 该指令引用的账户数
+        message.push(0u8); // 账户索引0：费用支付方
+        message.push(1u8); // 账户索引1：目标PDA
+        message.extend_from_slice(&encode_compact_u16(instruction.account_data.len()));
+        message.extend_from_slice(&instruction.account_data);
+
+        message
     }
 
     /// 模拟Solana交易
-    async fn simulate_transaction(&self, _transaction_data: &[u8]) -> Result<SolanaTransactionResult> {
+    async fn simulate_transaction(
+        &self,
+        _instruction: &ConsensusInstruction,
+        record: &ConsensusRecord,
+    ) -> Result<SolanaTransactionResult> {
         // 模拟网络延迟
         tokio::time::sleep(tokio::time::Duration::from_millis(1500)).await;
-        
-        // 生成模拟交易哈希
-        let timestamp = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)?
-            .as_secs();
-        let transaction_hash = format!("solana_tx_{}", timestamp);
-        
+
+        // 交易哈希取自共识结果的内容哈希（而非当前时间戳），同一份共识结果
+        // 重复部署会得到相同的交易哈希，天然幂等
+        let transaction_hash = format!("solana_tx_{}", consensus_history::to_hex(&record.content_hash));
+
         // 构建区块链浏览器链接
         let explorer_url = format!("https://solscan.io/tx/{}", transaction_hash);
         
@@ -201,33 +424,236 @@ impl SolanaDeployer {
         Ok(transaction_result)
     }
 
+    /// 通过Solana JSON RPC提交真实交易：加载钱包密钥对、获取最新区块哈希、
+    /// 构建一笔真正的 Solana legacy 线格式交易（消息头 + 紧凑编码的账户列表 +
+    /// 区块哈希 + 紧凑编码的指令列表）并用ed25519签名，提交后轮询
+    /// `getSignatureStatuses` 直至确认或超时。
+    async fn submit_live_transaction(&self, instruction: &ConsensusInstruction) -> Result<SolanaTransactionResult> {
+        println!("   📡 通过JSON RPC提交真实交易...");
+
+        let signing_key = self.load_signing_key()?;
+        let fee_payer = *signing_key.verifying_key().as_bytes();
+        let program_id = self.program_id_bytes();
+
+        let blockhash = self.get_latest_blockhash().await?;
+        let blockhash_bytes = bs58::decode(&blockhash)
+            .into_vec()
+            .map_err(|e| anyhow::anyhow!("区块哈希解码失败: {}", e))?;
+        let recent_blockhash: [u8; 32] = blockhash_bytes
+            .as_slice()
+            .try_into()
+            .map_err(|_| anyhow::anyhow!("区块哈希长度不正确"))?;
+
+        let message = Self::build_transaction_message(&fee_payer, &program_id, instruction, &recent_blockhash);
+        let signature = signing_key.sign(&message);
+
+        let mut wire_payload = Vec::new();
+        wire_payload.extend_from_slice(&encode_compact_u16(1)); // 签名数组（shortvec）：1个费用支付方签名
+        wire_payload.extend_from_slice(&signature.to_bytes());
+        wire_payload.extend_from_slice(&message);
+        let encoded_transaction = bs58::encode(&wire_payload).into_string();
+
+        let submitted_signature = self.send_transaction(&encoded_transaction).await?;
+        let confirmation = self.poll_signature_status(&submitted_signature).await?;
+
+        let (status, error_message) = match confirmation {
+            Some(SignatureConfirmation { err: Some(err), .. }) => (TransactionStatus::Failed, Some(err)),
+            Some(SignatureConfirmation { confirmations, .. }) => {
+                let is_confirmed = confirmations
+                    .map(|c| c >= self.confirmation_threshold as u64)
+                    .unwrap_or(true); // 确认数为None表示已最终确认
+                if is_confirmed {
+                    (TransactionStatus::Success, None)
+                } else {
+                    (TransactionStatus::Pending, None)
+                }
+            }
+            None => (TransactionStatus::Pending, None),
+        };
+
+        Ok(SolanaTransactionResult {
+            transaction_hash: submitted_signature.clone(),
+            explorer_url: format!("https://solscan.io/tx/{}", submitted_signature),
+            status,
+            error_message,
+            gas_fee: 5000,
+        })
+    }
+
+    /// 在本地PoW锚定账本上挖出并追加一个新区块，记录该共识结果
+    ///
+    /// 用作 [`DeployMode::Live`] 下网络路径失败时的离线/降级回退：即使没有
+    /// 任何可达的Solana RPC，共识结果也不会被静默丢弃，而是被写入一条可
+    /// 独立校验的本地PoW链，返回的交易哈希就是该区块的哈希。
+    async fn record_on_local_ledger(
+        &self,
+        consensus_result: &AgentConsensusResult,
+    ) -> Result<SolanaTransactionResult> {
+        let timestamp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)?
+            .as_secs() as i64;
+
+        let mut ledger = self.local_ledger.write().await;
+        let block = ledger.mine_block(consensus_result.clone(), self.pow_difficulty, timestamp);
+        let block_hash = block.hash();
+
+        Ok(SolanaTransactionResult {
+            transaction_hash: block_hash.clone(),
+            explorer_url: format!("local-ledger://block/{}", block_hash),
+            status: TransactionStatus::Success,
+            error_message: None,
+            gas_fee: 0,
+        })
+    }
+
+    /// 从钱包文件加载ed25519签名密钥（兼容Solana CLI生成的64字节密钥对JSON文件，
+    /// 仅取前32字节的私钥种子）
+    fn load_signing_key(&self) -> Result<SigningKey> {
+        let raw = std::fs::read_to_string(&self.wallet_path)
+            .map_err(|e| anyhow::anyhow!("读取钱包文件 `{}` 失败: {}", self.wallet_path, e))?;
+        let bytes: Vec<u8> = serde_json::from_str(&raw)
+            .map_err(|e| anyhow::anyhow!("解析钱包文件失败: {}", e))?;
+        if bytes.len() < 32 {
+            return Err(anyhow::anyhow!("钱包文件字节数不足，期望至少32字节的密钥种子"));
+        }
+        let seed: [u8; 32] = bytes[0..32]
+            .try_into()
+            .map_err(|_| anyhow::anyhow!("钱包文件密钥种子长度不正确"))?;
+        Ok(SigningKey::from_bytes(&seed))
+    }
+
+    /// 发起一次Solana JSON RPC调用
+    async fn rpc_call(&self, method: &str, params: Value) -> Result<Value> {
+        let body = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": method,
+            "params": params,
+        });
+
+        let response = self
+            .http_client
+            .post(&self.rpc_url)
+            .json(&body)
+            .send()
+            .await
+            .map_err(|e| anyhow::anyhow!("RPC请求失败: {}", e))?;
+
+        let body: Value = response
+            .json()
+            .await
+            .map_err(|e| anyhow::anyhow!("解析RPC响应失败: {}", e))?;
+
+        if let Some(error) = body.get("error") {
+            return Err(anyhow::anyhow!("RPC返回错误: {}", error));
+        }
+
+        body.get("result")
+            .cloned()
+            .ok_or_else(|| anyhow::anyhow!("RPC响应中缺少result字段"))
+    }
+
+    /// 获取最新区块哈希
+    async fn get_latest_blockhash(&self) -> Result<String> {
+        let result = self
+            .rpc_call("getLatestBlockhash", json!([{"commitment": "confirmed"}]))
+            .await?;
+
+        result["value"]["blockhash"]
+            .as_str()
+            .map(|s| s.to_string())
+            .ok_or_else(|| anyhow::anyhow!("响应中缺少blockhash字段"))
+    }
+
+    /// 提交已签名的交易，返回交易签名
+    async fn send_transaction(&self, encoded_transaction: &str) -> Result<String> {
+        let result = self
+            .rpc_call(
+                "sendTransaction",
+                json!([encoded_transaction, {"encoding": "base58"}]),
+            )
+            .await?;
+
+        result
+            .as_str()
+            .map(|s| s.to_string())
+            .ok_or_else(|| anyhow::anyhow!("sendTransaction响应中缺少交易签名"))
+    }
+
+    /// 轮询 `getSignatureStatuses` 直至拿到结果或超过最大尝试次数
+    async fn poll_signature_status(&self, signature: &str) -> Result<Option<SignatureConfirmation>> {
+        const MAX_ATTEMPTS: u32 = 20;
+        const POLL_INTERVAL_MS: u64 = 500;
+
+        for _ in 0..MAX_ATTEMPTS {
+            let result = self
+                .rpc_call(
+                    "getSignatureStatuses",
+                    json!([[signature], {"searchTransactionHistory": true}]),
+                )
+                .await?;
+
+            if let Some(status) = result["value"].get(0).filter(|v| !v.is_null()) {
+                let confirmations = status["confirmations"].as_u64();
+                let err = status
+                    .get("err")
+                    .filter(|e| !e.is_null())
+                    .map(|e| e.to_string());
+                return Ok(Some(SignatureConfirmation { confirmations, err }));
+            }
+
+            tokio::time::sleep(tokio::time::Duration::from_millis(POLL_INTERVAL_MS)).await;
+        }
+
+        Ok(None)
+    }
+
     /// 查询链上共识结果
     pub async fn query_consensus_result(&self, consensus_id: &str) -> Result<Option<AgentConsensusResult>> {
         println!("🔍 查询链上共识结果: {}", consensus_id);
-        
-        // 模拟查询延迟
-        tokio::time::sleep(tokio::time::Duration::from_millis(800)).await;
-        
-        // 这里应该从链上读取实际数据
-        // 现在返回None表示未找到
-        Ok(None)
+
+        // 先查本地内容寻址历史：本进程部署过的结果可以直接拿到，无需往返RPC
+        if let Some(result) = self.history.read().await.get_by_consensus_id(consensus_id) {
+            println!("   ✅ 命中本地内容寻址历史记录");
+            return Ok(Some(result));
+        }
+
+        let program_id = self.program_id_bytes();
+        let (pda, _bump) = derive_consensus_pda(&program_id, consensus_id);
+        let pda_base58 = bs58::encode(pda).into_string();
+
+        let result = self
+            .rpc_call("getAccountInfo", json!([pda_base58, {"encoding": "base64"}]))
+            .await?;
+
+        let Some(value) = result.get("value").filter(|v| !v.is_null()) else {
+            println!("   ℹ️ 未找到共识账户: {}", consensus_id);
+            return Ok(None);
+        };
+
+        let data_base64 = value["data"][0]
+            .as_str()
+            .ok_or_else(|| anyhow::anyhow!("getAccountInfo响应中缺少账户数据"))?;
+        let data_bytes = base64::decode(data_base64)
+            .map_err(|e| anyhow::anyhow!("账户数据base64解码失败: {}", e))?;
+
+        let account = ConsensusAccount::try_from_slice(&data_bytes)
+            .map_err(|e| anyhow::anyhow!("Borsh反序列化共识账户失败: {}", e))?;
+
+        Ok(Some(account.to_partial_consensus_result()))
     }
 
-    /// 获取智能体历史记录
+    /// 获取智能体历史记录：该智能体参与过的每一次共识结果的内容哈希，
+    /// 按参与顺序排列。每个哈希都可以在 [`ConsensusHistory`] 里查到对应记录
+    /// 及其 `prev_consensus_hash`，从而验证这条历史确实是一条完整的链，
+    /// 而不是凭空拼出的字符串。
     pub async fn get_agent_history(&self, agent_id: &str) -> Result<Vec<String>> {
         println!("📊 获取智能体历史记录: {}", agent_id);
-        
-        // 模拟查询延迟
-        tokio::time::sleep(tokio::time::Duration::from_millis(600)).await;
-        
-        // 返回模拟的历史交易哈希
-        let history = vec![
-            format!("solana_tx_{}_1", agent_id),
-            format!("solana_tx_{}_2", agent_id),
-            format!("solana_tx_{}_3", agent_id),
-        ];
-        
-        Ok(history)
+
+        let history = self.history.read().await;
+        let hashes = history.agent_history(agent_id);
+
+        Ok(hashes.iter().map(consensus_history::to_hex).collect())
     }
 }
 
@@ -342,8 +768,234 @@ mod tests {
 
         let result = deployer.deploy_consensus_result(&consensus_result).await;
         assert!(result.is_ok());
-        
+
         let tx_result = result.unwrap();
         assert!(matches!(tx_result.status, TransactionStatus::Success));
     }
+
+    #[tokio::test]
+    async fn test_redeploying_identical_consensus_is_idempotent() {
+        let deployer = SolanaDeployer::new(
+            "http://localhost:8899".to_string(),
+            "~/.config/solana/id.json".to_string(),
+            "CAUSAL111111111111111111111111111111111".to_string(),
+        );
+
+        let consensus_result = create_consensus_result(
+            "test_consensus_idempotent".to_string(),
+            "测试场景".to_string(),
+            "测试干预".to_string(),
+            vec!["agent_1".to_string()],
+            vec![],
+            100.0,
+            0.85,
+            0.66,
+            vec![],
+        );
+
+        let first = deployer.deploy_consensus_result(&consensus_result).await.unwrap();
+        let second = deployer.deploy_consensus_result(&consensus_result).await.unwrap();
+
+        assert_eq!(first.transaction_hash, second.transaction_hash);
+
+        let history = deployer.get_agent_history("agent_1").await.unwrap();
+        assert_eq!(history.len(), 1);
+
+        let fetched = deployer
+            .query_consensus_result("test_consensus_idempotent")
+            .await
+            .unwrap();
+        assert_eq!(fetched.unwrap().consensus_id, "test_consensus_idempotent");
+    }
+
+    #[test]
+    fn test_new_deployer_defaults_to_simulate_mode() {
+        let deployer = SolanaDeployer::new(
+            "http://localhost:8899".to_string(),
+            "~/.config/solana/id.json".to_string(),
+            "CAUSAL111111111111111111111111111111111".to_string(),
+        );
+
+        assert_eq!(deployer.deploy_mode, DeployMode::Simulate);
+    }
+
+    #[test]
+    fn test_load_signing_key_fails_for_missing_wallet_file() {
+        let deployer = SolanaDeployer::new(
+            "http://localhost:8899".to_string(),
+            "/nonexistent/wallet.json".to_string(),
+            "CAUSAL111111111111111111111111111111111".to_string(),
+        );
+
+        assert!(deployer.load_signing_key().is_err());
+    }
+
+    fn sample_agent_graphs() -> Vec<AgentGraphData> {
+        vec![
+            AgentGraphData {
+                agent_id: "agent_1".to_string(),
+                model_type: "gpt".to_string(),
+                node_count: 5,
+                edge_count: 4,
+                intervention_effect: 0.3,
+                base_prediction: 1.2,
+                confidence: 0.9,
+            },
+            AgentGraphData {
+                agent_id: "agent_2".to_string(),
+                model_type: "claude".to_string(),
+                node_count: 6,
+                edge_count: 5,
+                intervention_effect: 0.25,
+                base_prediction: 1.1,
+                confidence: 0.95,
+            },
+            AgentGraphData {
+                agent_id: "agent_3".to_string(),
+                model_type: "llama".to_string(),
+                node_count: 4,
+                edge_count: 3,
+                intervention_effect: 0.4,
+                base_prediction: 0.9,
+                confidence: 0.8,
+            },
+        ]
+    }
+
+    #[test]
+    fn test_merkle_proof_verifies_for_each_committed_agent() {
+        let agent_graphs = sample_agent_graphs();
+        let commitment = MerkleCommitment::build(&agent_graphs);
+        let root = commitment.root();
+
+        for agent in &agent_graphs {
+            let proof = commitment
+                .merkle_proof(&agent.agent_id)
+                .expect("已提交的智能体应能生成证明");
+            let leaf = agent_graph_leaf(agent);
+            assert!(MerkleCommitment::verify_proof(root, leaf, &proof));
+        }
+    }
+
+    #[test]
+    fn test_merkle_proof_is_none_for_unknown_agent() {
+        let commitment = MerkleCommitment::build(&sample_agent_graphs());
+        assert!(commitment.merkle_proof("agent_unknown").is_none());
+    }
+
+    #[test]
+    fn test_build_transaction_data_does_not_grow_with_agent_graph_count() {
+        let deployer = SolanaDeployer::new(
+            "http://localhost:8899".to_string(),
+            "~/.config/solana/id.json".to_string(),
+            "CAUSAL111111111111111111111111111111111".to_string(),
+        );
+
+        let small = create_consensus_result(
+            "c1".to_string(),
+            "场景".to_string(),
+            "干预".to_string(),
+            vec!["agent_1".to_string()],
+            vec![],
+            100.0,
+            0.85,
+            0.66,
+            sample_agent_graphs(),
+        );
+
+        let mut many_graphs = sample_agent_graphs();
+        for i in 0..100 {
+            many_graphs.push(AgentGraphData {
+                agent_id: format!("agent_extra_{i}"),
+                model_type: "gpt".to_string(),
+                node_count: 5,
+                edge_count: 4,
+                intervention_effect: 0.3,
+                base_prediction: 1.2,
+                confidence: 0.9,
+            });
+        }
+        let large = create_consensus_result(
+            "c2".to_string(),
+            "场景".to_string(),
+            "干预".to_string(),
+            vec!["agent_1".to_string()],
+            vec![],
+            100.0,
+            0.85,
+            0.66,
+            many_graphs,
+        );
+
+        let small_data = deployer.build_transaction_data(&small).unwrap();
+        let large_data = deployer.build_transaction_data(&large).unwrap();
+
+        // 无论智能体因果图数量如何增长，账户数据都只多一个固定大小的Merkle根
+        assert_eq!(small_data.account_data.len(), large_data.account_data.len());
+    }
+
+    #[test]
+    fn test_compact_u16_round_trips_multi_byte_values() {
+        // 单字节：值 < 0x80 直接编码，无延续位
+        assert_eq!(encode_compact_u16(3), vec![3]);
+        // 多字节：0x80 需要延续位，低7位在前一字节，高位在后一字节
+        assert_eq!(encode_compact_u16(0x80), vec![0x80, 0x01]);
+    }
+
+    #[test]
+    fn test_build_transaction_message_matches_legacy_wire_layout() {
+        let fee_payer = [1u8; 32];
+        let program_id = [2u8; 32];
+        let recent_blockhash = [3u8; 32];
+        let instruction = ConsensusInstruction {
+            pda: [4u8; 32],
+            account_data: vec![9, 9, 9],
+        };
+
+        let message =
+            SolanaDeployer::build_transaction_message(&fee_payer, &program_id, &instruction, &recent_blockhash);
+
+        // 消息头：1个必需签名，0个只读签名账户，1个只读非签名账户（程序ID）
+        assert_eq!(&message[0..3], &[1, 0, 1]);
+        // 账户列表长度前缀（3个账户，单字节shortvec）+ 按费用支付方/PDA/程序ID顺序排列
+        assert_eq!(message[3], 3);
+        assert_eq!(&message[4..36], &fee_payer);
+        assert_eq!(&message[36..68], &instruction.pda);
+        assert_eq!(&message[68..100], &program_id);
+        // 紧随账户列表之后是区块哈希
+        assert_eq!(&message[100..132], &recent_blockhash);
+        // 指令数组：1条指令，program_id_index=2，引用2个账户（0,1），3字节数据
+        assert_eq!(&message[132..141], &[1, 2, 2, 0, 1, 3, 9, 9, 9]);
+    }
+
+    #[tokio::test]
+    async fn test_live_mode_falls_back_to_local_ledger_when_rpc_unreachable() {
+        let deployer = SolanaDeployer::new(
+            "http://127.0.0.1:0".to_string(), // 不可达端口，连接会立即失败
+            "~/.config/solana/id.json".to_string(),
+            "CAUSAL111111111111111111111111111111111".to_string(),
+        )
+        .with_deploy_mode(DeployMode::Live)
+        .with_pow_difficulty(1);
+
+        let consensus_result = create_consensus_result(
+            "fallback_test".to_string(),
+            "场景".to_string(),
+            "干预".to_string(),
+            vec!["agent_1".to_string()],
+            vec![],
+            100.0,
+            0.85,
+            0.66,
+            vec![],
+        );
+
+        let result = deployer
+            .deploy_consensus_result(&consensus_result)
+            .await
+            .unwrap();
+
+        assert!(matches!(result.status, TransactionStatus::Success));
+        assert!(result.explorer_url.starts_with("local-ledger://"));
+    }
 }
@@ -0,0 +1,215 @@
+//! 共识结果的内容寻址历史记录
+//!
+//! 此前 `simulate_transaction` 用当前时间戳生成交易哈希，同一份共识结果
+//! 重新部署两次会得到两个不同的哈希，导致 [`query_consensus_result`]
+//! (crate::solana::consensus_deployer::SolanaDeployer::query_consensus_result) 和
+//! `get_agent_history` 完全无法把它们关联起来——后者甚至只是拼接了三个
+//! 固定格式的字符串。这里改为对 [`AgentConsensusResult`] 的规范字节表示
+//! （刻意排除易变的 `timestamp`）求SHA-256作为内容哈希：同一份结果永远
+//! 得到同一个哈希，重复部署天然幂等；每条新记录还会记下部署时链头的哈希
+//! 作为 `prev_consensus_hash`，把所有记录穿成一条可验证的链，`get_agent_history`
+//! 则是这条链上按参与智能体过滤出的子序列。
+
+use crate::solana::consensus_deployer::AgentConsensusResult;
+use crate::solana::merkle::{self, Hash, MerkleCommitment};
+use std::collections::HashMap;
+
+/// 对一个字符串写入长度前缀，再写入其字节
+fn write_len_prefixed(buf: &mut Vec<u8>, value: &str) {
+    let bytes = value.as_bytes();
+    buf.extend_from_slice(&(bytes.len() as u32).to_le_bytes());
+    buf.extend_from_slice(bytes);
+}
+
+/// 对 [`AgentConsensusResult`] 的规范字节表示求SHA-256，得到内容哈希
+///
+/// 按字段顺序拼接，`timestamp` 被有意排除——它是每次部署都会变化的元数据，
+/// 不是共识内容本身的一部分。智能体因果图数据同样只并入其Merkle根。
+pub fn content_hash(consensus_result: &AgentConsensusResult) -> Hash {
+    let mut buf = Vec::new();
+
+    write_len_prefixed(&mut buf, &consensus_result.consensus_id);
+    write_len_prefixed(&mut buf, &consensus_result.scenario);
+    write_len_prefixed(&mut buf, &consensus_result.intervention);
+    buf.extend_from_slice(&consensus_result.consensus_value.to_le_bytes());
+    buf.extend_from_slice(&consensus_result.consensus_similarity.to_le_bytes());
+    buf.extend_from_slice(&consensus_result.pass_rate.to_le_bytes());
+    write_len_prefixed(&mut buf, &consensus_result.contract_version);
+
+    buf.extend_from_slice(&(consensus_result.valid_agents.len() as u32).to_le_bytes());
+    for agent_id in &consensus_result.valid_agents {
+        write_len_prefixed(&mut buf, agent_id);
+    }
+
+    buf.extend_from_slice(&(consensus_result.outliers.len() as u32).to_le_bytes());
+    for agent_id in &consensus_result.outliers {
+        write_len_prefixed(&mut buf, agent_id);
+    }
+
+    let agent_graphs_root = MerkleCommitment::build(&consensus_result.agent_graphs).root();
+    buf.extend_from_slice(&agent_graphs_root);
+
+    merkle::hash_leaf(&buf)
+}
+
+/// 把哈希编码为十六进制字符串，便于展示和作为 `get_agent_history` 的返回值
+pub fn to_hex(hash: &Hash) -> String {
+    hash.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+/// 历史记录中的一条共识结果
+#[derive(Debug, Clone)]
+pub struct ConsensusRecord {
+    /// 内容哈希，见 [`content_hash`]
+    pub content_hash: Hash,
+    /// 记录时链头的哈希；`None` 表示这是第一条记录
+    pub prev_consensus_hash: Option<Hash>,
+    /// 完整的共识结果
+    pub result: AgentConsensusResult,
+}
+
+/// 共识结果的内容寻址历史：把每次部署的结果链接成一条链，并维护
+/// `consensus_id -> 内容哈希` 和 `智能体ID -> 参与过的内容哈希列表` 两个索引
+#[derive(Debug, Default)]
+pub struct ConsensusHistory {
+    records: HashMap<Hash, ConsensusRecord>,
+    by_consensus_id: HashMap<String, Hash>,
+    by_agent: HashMap<String, Vec<Hash>>,
+    chain_head: Option<Hash>,
+}
+
+impl ConsensusHistory {
+    /// 创建一个空的历史记录
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 记录一次共识结果，返回对应的记录
+    ///
+    /// 若该结果的内容哈希已经存在（同一份结果被重复部署），直接返回已有记录，
+    /// 不产生新的链接——这正是内容寻址带来的幂等性。
+    pub fn record(&mut self, result: &AgentConsensusResult) -> ConsensusRecord {
+        let hash = content_hash(result);
+
+        if let Some(existing) = self.records.get(&hash) {
+            return existing.clone();
+        }
+
+        let record = ConsensusRecord {
+            content_hash: hash,
+            prev_consensus_hash: self.chain_head,
+            result: result.clone(),
+        };
+
+        self.records.insert(hash, record.clone());
+        self.by_consensus_id.insert(result.consensus_id.clone(), hash);
+        for agent_id in result.valid_agents.iter().chain(result.outliers.iter()) {
+            self.by_agent.entry(agent_id.clone()).or_default().push(hash);
+        }
+        self.chain_head = Some(hash);
+
+        record
+    }
+
+    /// 按 `consensus_id` 查找已记录的共识结果
+    pub fn get_by_consensus_id(&self, consensus_id: &str) -> Option<AgentConsensusResult> {
+        self.by_consensus_id
+            .get(consensus_id)
+            .and_then(|hash| self.records.get(hash))
+            .map(|record| record.result.clone())
+    }
+
+    /// 某个智能体参与过的所有内容哈希，按参与顺序排列——这就是它在链上的
+    /// "历史记录"，可以逐个哈希去 [`Self::get_record`] 取出记录并核对
+    /// `prev_consensus_hash` 来验证链路完整
+    pub fn agent_history(&self, agent_id: &str) -> Vec<Hash> {
+        self.by_agent.get(agent_id).cloned().unwrap_or_default()
+    }
+
+    /// 按内容哈希取出一条记录
+    pub fn get_record(&self, hash: &Hash) -> Option<&ConsensusRecord> {
+        self.records.get(hash)
+    }
+
+    /// 从给定哈希开始沿 `prev_consensus_hash` 往回走，校验链路上每一环都能
+    /// 在历史记录里找到，返回链的长度（含起点）；链路断裂（引用了未知的
+    /// 前驱哈希）时返回 `None`
+    pub fn verify_chain_from(&self, hash: &Hash) -> Option<usize> {
+        let mut current = *hash;
+        let mut length = 0usize;
+
+        loop {
+            let record = self.records.get(&current)?;
+            length += 1;
+            match record.prev_consensus_hash {
+                Some(prev) => current = prev,
+                None => return Some(length),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solana::consensus_deployer::create_consensus_result;
+
+    fn sample(consensus_id: &str, valid_agents: Vec<String>) -> AgentConsensusResult {
+        create_consensus_result(
+            consensus_id.to_string(),
+            "场景".to_string(),
+            "干预".to_string(),
+            valid_agents,
+            vec![],
+            100.0,
+            0.85,
+            0.66,
+            vec![],
+        )
+    }
+
+    #[test]
+    fn test_content_hash_ignores_timestamp() {
+        let mut a = sample("c1", vec!["agent_1".to_string()]);
+        let mut b = a.clone();
+        a.timestamp = 1;
+        b.timestamp = 2;
+
+        assert_eq!(content_hash(&a), content_hash(&b));
+    }
+
+    #[test]
+    fn test_record_is_idempotent_for_identical_content() {
+        let mut history = ConsensusHistory::new();
+        let result = sample("c1", vec!["agent_1".to_string()]);
+
+        let first = history.record(&result);
+        let second = history.record(&result);
+
+        assert_eq!(first.content_hash, second.content_hash);
+        assert_eq!(history.agent_history("agent_1").len(), 1);
+    }
+
+    #[test]
+    fn test_agent_history_links_into_a_chain() {
+        let mut history = ConsensusHistory::new();
+        let first = history.record(&sample("c1", vec!["agent_1".to_string()]));
+        let second = history.record(&sample("c2", vec!["agent_1".to_string()]));
+
+        assert_eq!(second.prev_consensus_hash, Some(first.content_hash));
+
+        let agent_history = history.agent_history("agent_1");
+        assert_eq!(agent_history, vec![first.content_hash, second.content_hash]);
+        assert_eq!(history.verify_chain_from(&second.content_hash), Some(2));
+    }
+
+    #[test]
+    fn test_get_by_consensus_id_round_trips() {
+        let mut history = ConsensusHistory::new();
+        let result = sample("c1", vec!["agent_1".to_string()]);
+        history.record(&result);
+
+        let fetched = history.get_by_consensus_id("c1").expect("应当能查到记录");
+        assert_eq!(fetched.consensus_id, "c1");
+    }
+}
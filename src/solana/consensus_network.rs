@@ -0,0 +1,292 @@
+//! 跨节点共识Gossip网络
+//!
+//! [`SolanaDeployer`](crate::solana::consensus_deployer::SolanaDeployer) 默认是单节点的：
+//! 一个共识结果在 [`deploy_consensus_result`](crate::solana::consensus_deployer::SolanaDeployer::deploy_consensus_result)
+//! 被调用之前，没有任何方式让其他运行中的预言机实例看到它。这里用libp2p搭建一个
+//! 轻量的gossip网络——mDNS负责局域网内的节点发现，gossipsub负责在一个共享主题上
+//! 扩散 [`AgentConsensusResult`]——使得一组节点可以在任何一方真正上链之前，
+//! 先就同一批共识结果达成一致。
+
+use crate::solana::consensus_deployer::AgentConsensusResult;
+use anyhow::{Context, Result};
+use futures::StreamExt;
+use libp2p::{
+    gossipsub, mdns, noise,
+    swarm::{NetworkBehaviour, SwarmEvent},
+    tcp, yamux, Multiaddr, PeerId, Swarm, SwarmBuilder,
+};
+use std::collections::HashSet;
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+/// 共识结果广播使用的gossipsub主题名
+const CONSENSUS_TOPIC: &str = "multi-agent-oracle/consensus-results/1.0.0";
+
+/// 入站/出站消息通道的容量
+const CHANNEL_CAPACITY: usize = 256;
+
+/// 组合行为：gossipsub负责扩散消息，mdns负责在局域网内自动发现对等节点
+#[derive(NetworkBehaviour)]
+struct ConsensusBehaviour {
+    gossipsub: gossipsub::Behaviour,
+    mdns: mdns::tokio::Behaviour,
+}
+
+/// 共识gossip网络
+///
+/// 通过 [`ConsensusNetwork::new`] 构建后会拿到一个用于广播的发送端和一个用于
+/// 接收已校验共识结果的接收端；随后把 `self` 交给 [`ConsensusNetwork::run`]，
+/// 由它独占驱动底层的libp2p Swarm事件循环直到收到关闭信号。
+pub struct ConsensusNetwork {
+    swarm: Swarm<ConsensusBehaviour>,
+    topic: gossipsub::IdentTopic,
+    outbound_rx: mpsc::Receiver<AgentConsensusResult>,
+    inbound_tx: mpsc::Sender<AgentConsensusResult>,
+    /// 已经接受过的共识ID，用于在gossip网络中去重
+    seen_consensus_ids: HashSet<String>,
+}
+
+impl ConsensusNetwork {
+    /// 创建一个新的共识gossip网络，监听给定的多地址（例如 `/ip4/0.0.0.0/tcp/0`）
+    ///
+    /// 返回网络本身、一个供 [`crate::solana::consensus_deployer::SolanaDeployer`]
+    /// 广播共识结果的发送端，以及一个产出经过校验、去重后的对等节点共识结果的
+    /// 接收端。网络本身需要被 `await self.run()` 驱动。
+    pub fn new(
+        listen_addr: &str,
+    ) -> Result<(
+        Self,
+        mpsc::Sender<AgentConsensusResult>,
+        mpsc::Receiver<AgentConsensusResult>,
+    )> {
+        let behaviour_builder = |keypair: &libp2p::identity::Keypair| -> Result<ConsensusBehaviour> {
+            let gossipsub_config = gossipsub::ConfigBuilder::default()
+                .heartbeat_interval(Duration::from_secs(1))
+                .validation_mode(gossipsub::ValidationMode::Strict)
+                .build()
+                .map_err(|e| anyhow::anyhow!("构建gossipsub配置失败: {}", e))?;
+
+            let gossipsub = gossipsub::Behaviour::new(
+                gossipsub::MessageAuthenticity::Signed(keypair.clone()),
+                gossipsub_config,
+            )
+            .map_err(|e| anyhow::anyhow!("构建gossipsub行为失败: {}", e))?;
+
+            let mdns = mdns::tokio::Behaviour::new(
+                mdns::Config::default(),
+                keypair.public().to_peer_id(),
+            )
+            .context("构建mDNS发现行为失败")?;
+
+            Ok(ConsensusBehaviour { gossipsub, mdns })
+        };
+
+        let mut swarm = SwarmBuilder::with_new_identity()
+            .with_tokio()
+            .with_tcp(
+                tcp::Config::default(),
+                noise::Config::new,
+                yamux::Config::default,
+            )
+            .context("构建libp2p TCP传输失败")?
+            .with_behaviour(|keypair| behaviour_builder(keypair).map_err(|e| e.into()))
+            .context("构建libp2p行为失败")?
+            .build();
+
+        let topic = gossipsub::IdentTopic::new(CONSENSUS_TOPIC);
+        swarm
+            .behaviour_mut()
+            .gossipsub
+            .subscribe(&topic)
+            .map_err(|e| anyhow::anyhow!("订阅共识主题失败: {}", e))?;
+
+        let multiaddr: Multiaddr = listen_addr
+            .parse()
+            .with_context(|| format!("无效的监听地址: {}", listen_addr))?;
+        swarm
+            .listen_on(multiaddr)
+            .context("监听本地地址失败")?;
+
+        let (outbound_tx, outbound_rx) = mpsc::channel(CHANNEL_CAPACITY);
+        let (inbound_tx, inbound_rx) = mpsc::channel(CHANNEL_CAPACITY);
+
+        Ok((
+            Self {
+                swarm,
+                topic,
+                outbound_rx,
+                inbound_tx,
+                seen_consensus_ids: HashSet::new(),
+            },
+            outbound_tx,
+            inbound_rx,
+        ))
+    }
+
+    /// 本地节点的PeerID
+    pub fn local_peer_id(&self) -> PeerId {
+        *self.swarm.local_peer_id()
+    }
+
+    /// 驱动Swarm事件循环：把出站广播请求发布到gossipsub主题，把mDNS发现的
+    /// 对等节点加入gossipsub的已知节点集合，并对收到的gossipsub消息做校验、
+    /// 去重后转发给 `inbound_tx`
+    ///
+    /// 当出站发送端和Swarm都被丢弃（所有 [`ConsensusNetwork::new`] 返回的
+    /// 句柄都已析构）时循环结束并返回
+    pub async fn run(mut self) -> Result<()> {
+        loop {
+            tokio::select! {
+                outbound = self.outbound_rx.recv() => {
+                    match outbound {
+                        Some(result) => {
+                            if let Err(e) = self.publish(&result) {
+                                log::warn!("广播共识结果失败: {}", e);
+                            }
+                        }
+                        None => {
+                            log::info!("共识gossip网络的出站通道已关闭，停止事件循环");
+                            return Ok(());
+                        }
+                    }
+                }
+                event = self.swarm.select_next_some() => {
+                    self.handle_swarm_event(event);
+                }
+            }
+        }
+    }
+
+    /// 把一个共识结果序列化后发布到共识主题
+    fn publish(&mut self, result: &AgentConsensusResult) -> Result<()> {
+        let payload = serde_json::to_vec(result).context("序列化共识结果失败")?;
+        self.swarm
+            .behaviour_mut()
+            .gossipsub
+            .publish(self.topic.clone(), payload)
+            .map_err(|e| anyhow::anyhow!("发布到gossipsub失败: {}", e))?;
+        Ok(())
+    }
+
+    fn handle_swarm_event(&mut self, event: SwarmEvent<ConsensusBehaviourEvent>) {
+        match event {
+            SwarmEvent::Behaviour(ConsensusBehaviourEvent::Mdns(mdns::Event::Discovered(peers))) => {
+                for (peer_id, _addr) in peers {
+                    log::debug!("mDNS发现对等节点: {}", peer_id);
+                    self.swarm.behaviour_mut().gossipsub.add_explicit_peer(&peer_id);
+                }
+            }
+            SwarmEvent::Behaviour(ConsensusBehaviourEvent::Mdns(mdns::Event::Expired(peers))) => {
+                for (peer_id, _addr) in peers {
+                    log::debug!("mDNS对等节点过期: {}", peer_id);
+                    self.swarm.behaviour_mut().gossipsub.remove_explicit_peer(&peer_id);
+                }
+            }
+            SwarmEvent::Behaviour(ConsensusBehaviourEvent::Gossipsub(gossipsub::Event::Message {
+                message,
+                ..
+            })) => {
+                self.handle_gossip_message(&message.data);
+            }
+            SwarmEvent::NewListenAddr { address, .. } => {
+                log::info!("共识gossip网络监听于: {}", address);
+            }
+            _ => {}
+        }
+    }
+
+    /// 校验并去重一条入站gossip消息，通过则转发到 `inbound_tx`
+    ///
+    /// 校验规则：`pass_rate` 和 `consensus_similarity` 必须落在 `[0.0, 1.0]`
+    /// 区间内（超出范围说明消息损坏或恶意构造），且同一个 `consensus_id`
+    /// 只被接受一次，避免重复广播造成下游重复处理
+    fn handle_gossip_message(&mut self, data: &[u8]) {
+        let result: AgentConsensusResult = match serde_json::from_slice(data) {
+            Ok(result) => result,
+            Err(e) => {
+                log::warn!("丢弃无法解析的共识gossip消息: {}", e);
+                return;
+            }
+        };
+
+        if !(0.0..=1.0).contains(&result.pass_rate) {
+            log::warn!(
+                "丢弃共识消息 {}: pass_rate超出合法范围 ({})",
+                result.consensus_id,
+                result.pass_rate
+            );
+            return;
+        }
+        if !(0.0..=1.0).contains(&result.consensus_similarity) {
+            log::warn!(
+                "丢弃共识消息 {}: consensus_similarity超出合法范围 ({})",
+                result.consensus_id,
+                result.consensus_similarity
+            );
+            return;
+        }
+
+        if !self.seen_consensus_ids.insert(result.consensus_id.clone()) {
+            log::debug!("丢弃重复的共识消息: {}", result.consensus_id);
+            return;
+        }
+
+        if let Err(e) = self.inbound_tx.try_send(result) {
+            log::warn!("入站共识结果通道已满或已关闭，丢弃消息: {}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_result(consensus_id: &str, pass_rate: f64, consensus_similarity: f64) -> AgentConsensusResult {
+        crate::solana::consensus_deployer::create_consensus_result(
+            consensus_id.to_string(),
+            "测试场景".to_string(),
+            "测试干预".to_string(),
+            vec!["agent_1".to_string()],
+            vec![],
+            100.0,
+            consensus_similarity,
+            pass_rate,
+            vec![],
+        )
+    }
+
+    #[tokio::test]
+    async fn test_new_network_listens_and_subscribes() {
+        let (network, _outbound_tx, _inbound_rx) = ConsensusNetwork::new("/ip4/127.0.0.1/tcp/0")
+            .expect("创建共识gossip网络失败");
+
+        assert!(!network.local_peer_id().to_string().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_rejects_out_of_range_pass_rate() {
+        let (mut network, _outbound_tx, mut inbound_rx) =
+            ConsensusNetwork::new("/ip4/127.0.0.1/tcp/0").expect("创建共识gossip网络失败");
+
+        let bad = sample_result("bad_pass_rate", 1.5, 0.9);
+        let payload = serde_json::to_vec(&bad).unwrap();
+        network.handle_gossip_message(&payload);
+
+        assert!(inbound_rx.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_dedupes_by_consensus_id() {
+        let (mut network, _outbound_tx, mut inbound_rx) =
+            ConsensusNetwork::new("/ip4/127.0.0.1/tcp/0").expect("创建共识gossip网络失败");
+
+        let result = sample_result("dup_consensus", 0.9, 0.85);
+        let payload = serde_json::to_vec(&result).unwrap();
+
+        network.handle_gossip_message(&payload);
+        network.handle_gossip_message(&payload);
+
+        assert!(inbound_rx.try_recv().is_ok());
+        assert!(inbound_rx.try_recv().is_err());
+    }
+}
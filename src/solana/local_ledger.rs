@@ -0,0 +1,233 @@
+//! 本地PoW锚定账本
+//!
+//! 当 Solana RPC 不可达时（见 [`crate::solana::consensus_deployer::SolanaDeployer`]
+//! 的回退逻辑），共识结果仍需要被防篡改地记录下来。这里实现一个经典的
+//! 工作量证明（PoW）区块链结构：每个区块持有上一区块的哈希，通过不断
+//! 尝试 `proof` 直到区块头的SHA-256哈希满足难度要求，从而把篡改成本
+//! 提高到重新完成后续所有区块的PoW。
+
+use crate::solana::consensus_deployer::AgentConsensusResult;
+use sha2::{Digest, Sha256};
+
+/// 账本中的一个区块
+#[derive(Debug, Clone)]
+pub struct Block {
+    /// 区块高度，从0（创世区块）开始
+    pub index: u64,
+    /// 区块时间戳（Unix秒）
+    pub timestamp: i64,
+    /// 上一区块的十六进制哈希
+    pub previous_hash: String,
+    /// 挖矿找到的随机数
+    pub proof: u64,
+    /// 承载的共识结果；创世区块为`None`
+    pub payload: Option<AgentConsensusResult>,
+}
+
+impl Block {
+    /// 对区块头的规范编码求SHA-256，返回十六进制字符串
+    pub fn hash(&self) -> String {
+        let mut buf = Vec::new();
+        buf.extend_from_slice(&self.index.to_le_bytes());
+        buf.extend_from_slice(&self.timestamp.to_le_bytes());
+        buf.extend_from_slice(self.previous_hash.as_bytes());
+        buf.extend_from_slice(&self.proof.to_le_bytes());
+        if let Some(payload) = &self.payload {
+            let payload_bytes = serde_json::to_vec(payload).unwrap_or_default();
+            buf.extend_from_slice(&payload_bytes);
+        }
+
+        let mut hasher = Sha256::new();
+        hasher.update(&buf);
+        hex_encode(hasher.finalize().as_slice())
+    }
+}
+
+fn hex_encode(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+/// 本地PoW锚定账本
+///
+/// 在Solana RPC不可达时充当离线/降级链，提供与真实区块链类似的防篡改保证：
+/// 每个区块通过 [`Self::mine_block`] 链接前一个区块，[`Self::validate_chain`]
+/// 可以独立重新校验整条链，[`Self::resolve`] 实现最长链规则用于多个账本副本
+/// 之间的合并。
+#[derive(Debug, Clone, Default)]
+pub struct LocalLedger {
+    chain: Vec<Block>,
+}
+
+impl LocalLedger {
+    /// 创建一个只含创世区块的新账本；创世区块不参与PoW校验
+    pub fn new() -> Self {
+        let genesis = Block {
+            index: 0,
+            timestamp: 0,
+            previous_hash: "0".repeat(64),
+            proof: 0,
+            payload: None,
+        };
+        Self {
+            chain: vec![genesis],
+        }
+    }
+
+    /// 链上最新一个区块
+    pub fn last_block(&self) -> &Block {
+        self.chain.last().expect("账本至少包含创世区块")
+    }
+
+    /// 账本中的区块数量（含创世区块）
+    pub fn len(&self) -> usize {
+        self.chain.len()
+    }
+
+    /// 账本是否只剩创世区块之外再无区块（理论上账本永远非空，此方法仅为
+    /// 满足惯用的 `len`/`is_empty` 配对）
+    pub fn is_empty(&self) -> bool {
+        self.chain.is_empty()
+    }
+
+    /// 挖出并追加一个新区块：递增 `proof` 直到区块哈希的十六进制表示以
+    /// `difficulty` 个前导零开头，然后通过 `previous_hash` 链接到当前链尾。
+    pub fn mine_block(&mut self, payload: AgentConsensusResult, difficulty: usize, timestamp: i64) -> &Block {
+        let previous_hash = self.last_block().hash();
+        let index = self.chain.len() as u64;
+        let target = "0".repeat(difficulty);
+
+        let mut proof = 0u64;
+        let block = loop {
+            let candidate = Block {
+                index,
+                timestamp,
+                previous_hash: previous_hash.clone(),
+                proof,
+                payload: Some(payload.clone()),
+            };
+            if candidate.hash().starts_with(&target) {
+                break candidate;
+            }
+            proof += 1;
+        };
+
+        self.chain.push(block);
+        self.last_block()
+    }
+
+    /// 校验整条链：对每个区块重新计算哈希，检查其满足PoW难度要求
+    /// （创世区块除外），并且与前一区块的哈希正确链接。
+    pub fn validate_chain(&self, difficulty: usize) -> bool {
+        let target = "0".repeat(difficulty);
+
+        for (i, block) in self.chain.iter().enumerate() {
+            if block.index != i as u64 {
+                return false;
+            }
+
+            if i == 0 {
+                continue;
+            }
+
+            if !block.hash().starts_with(&target) {
+                return false;
+            }
+
+            if block.previous_hash != self.chain[i - 1].hash() {
+                return false;
+            }
+        }
+
+        true
+    }
+
+    /// 最长链规则：如果 `other` 比当前链更长且自身是一条有效链，就用它
+    /// 替换当前链，返回是否发生了替换。
+    pub fn resolve(&mut self, other: &LocalLedger, difficulty: usize) -> bool {
+        if other.len() > self.len() && other.validate_chain(difficulty) {
+            self.chain = other.chain.clone();
+            true
+        } else {
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solana::consensus_deployer::create_consensus_result;
+
+    fn sample_payload(id: &str) -> AgentConsensusResult {
+        create_consensus_result(
+            id.to_string(),
+            "场景".to_string(),
+            "干预".to_string(),
+            vec!["agent_1".to_string()],
+            vec![],
+            100.0,
+            0.85,
+            0.66,
+            vec![],
+        )
+    }
+
+    #[test]
+    fn test_mined_block_satisfies_difficulty_and_links_to_previous_hash() {
+        let mut ledger = LocalLedger::new();
+        let genesis_hash = ledger.last_block().hash();
+
+        let block = ledger.mine_block(sample_payload("c1"), 2, 1000);
+        assert!(block.hash().starts_with("00"));
+        assert_eq!(block.previous_hash, genesis_hash);
+    }
+
+    #[test]
+    fn test_chain_of_blocks_validates() {
+        let mut ledger = LocalLedger::new();
+        ledger.mine_block(sample_payload("c1"), 1, 1000);
+        ledger.mine_block(sample_payload("c2"), 1, 1001);
+        ledger.mine_block(sample_payload("c3"), 1, 1002);
+
+        assert_eq!(ledger.len(), 4); // 创世区块 + 3个挖出的区块
+        assert!(ledger.validate_chain(1));
+    }
+
+    #[test]
+    fn test_tampering_with_a_block_invalidates_the_chain() {
+        let mut ledger = LocalLedger::new();
+        ledger.mine_block(sample_payload("c1"), 1, 1000);
+        ledger.mine_block(sample_payload("c2"), 1, 1001);
+
+        ledger.chain[1].timestamp += 1; // 篡改已挖出的区块
+        assert!(!ledger.validate_chain(1));
+    }
+
+    #[test]
+    fn test_resolve_adopts_longer_valid_chain() {
+        let mut short_ledger = LocalLedger::new();
+        short_ledger.mine_block(sample_payload("c1"), 1, 1000);
+
+        let mut long_ledger = LocalLedger::new();
+        long_ledger.mine_block(sample_payload("c1"), 1, 1000);
+        long_ledger.mine_block(sample_payload("c2"), 1, 1001);
+
+        let replaced = short_ledger.resolve(&long_ledger, 1);
+        assert!(replaced);
+        assert_eq!(short_ledger.len(), long_ledger.len());
+    }
+
+    #[test]
+    fn test_resolve_ignores_shorter_or_invalid_chain() {
+        let mut long_ledger = LocalLedger::new();
+        long_ledger.mine_block(sample_payload("c1"), 1, 1000);
+        long_ledger.mine_block(sample_payload("c2"), 1, 1001);
+
+        let mut short_ledger = LocalLedger::new();
+        short_ledger.mine_block(sample_payload("c1"), 1, 1000);
+
+        let replaced = long_ledger.resolve(&short_ledger, 1);
+        assert!(!replaced);
+        assert_eq!(long_ledger.len(), 3);
+    }
+}
@@ -0,0 +1,163 @@
+//! 二叉Merkle树
+//!
+//! 为避免把整批数据内联进交易（容易超出Solana交易大小限制），这里提供一个
+//! 通用的二叉Merkle树实现：把任意一组叶子哈希两两拼接、逐层哈希直到只剩
+//! 一个根哈希（奇数个节点时复制最后一个节点补齐），调用方只需要把32字节的
+//! 根哈希放上链，随后可以为任意叶子生成兄弟节点路径（`proof`），让验证者
+//! 在不下载全部数据的情况下确认某个叶子确实属于该承诺。
+
+use sha2::{Digest, Sha256};
+
+/// 32字节的SHA-256哈希
+pub type Hash = [u8; 32];
+
+/// 对任意字节串求叶子哈希
+pub fn hash_leaf(data: &[u8]) -> Hash {
+    let mut hasher = Sha256::new();
+    hasher.update(data);
+    hasher.finalize().into()
+}
+
+/// 对一对兄弟节点求父节点哈希
+fn hash_pair(left: &Hash, right: &Hash) -> Hash {
+    let mut hasher = Sha256::new();
+    hasher.update(left);
+    hasher.update(right);
+    hasher.finalize().into()
+}
+
+/// 二叉Merkle树：按层保存每一层的节点哈希，`layers[0]` 是叶子层，
+/// 最后一层只有一个元素，即根哈希
+pub struct MerkleTree {
+    layers: Vec<Vec<Hash>>,
+}
+
+impl MerkleTree {
+    /// 从一组叶子哈希构建Merkle树；空输入会得到空字节串的哈希作为根
+    pub fn from_leaves(leaves: Vec<Hash>) -> Self {
+        if leaves.is_empty() {
+            return Self {
+                layers: vec![vec![hash_leaf(&[])]],
+            };
+        }
+        if leaves.len() == 1 {
+            // 单叶子也要经过一轮哈希（与“奇数个节点复制最后一个节点补齐”
+            // 的规则一致），否则根哈希会和叶子哈希本身相同，泄露叶子原始
+            // 哈希且无法与两层及以上的树共用同一套校验逻辑
+            let leaf = leaves[0];
+            return Self {
+                layers: vec![leaves, vec![hash_pair(&leaf, &leaf)]],
+            };
+        }
+
+        let mut layers = vec![leaves];
+        while layers.last().expect("至少有一层").len() > 1 {
+            let prev = layers.last().expect("至少有一层");
+            let mut next = Vec::with_capacity(prev.len().div_ceil(2));
+
+            let mut i = 0;
+            while i < prev.len() {
+                let left = prev[i];
+                // 奇数个节点时复制最后一个节点补齐
+                let right = if i + 1 < prev.len() { prev[i + 1] } else { prev[i] };
+                next.push(hash_pair(&left, &right));
+                i += 2;
+            }
+
+            layers.push(next);
+        }
+
+        Self { layers }
+    }
+
+    /// Merkle根哈希
+    pub fn root(&self) -> Hash {
+        *self
+            .layers
+            .last()
+            .expect("至少有一层")
+            .last()
+            .expect("根层至少有一个节点")
+    }
+
+    /// 为给定叶子索引生成自底向上的兄弟节点路径
+    ///
+    /// 每一步返回 `(兄弟哈希, 兄弟是否在右侧)`，验证者据此按顺序拼接哈希即可
+    /// 重新计算出根哈希。
+    pub fn proof(&self, mut index: usize) -> Option<Vec<(Hash, bool)>> {
+        if index >= self.layers[0].len() {
+            return None;
+        }
+
+        let mut proof = Vec::new();
+        for layer in &self.layers[..self.layers.len() - 1] {
+            let is_even = index % 2 == 0;
+            let sibling_index = if is_even { index + 1 } else { index - 1 };
+            let sibling = if sibling_index < layer.len() {
+                layer[sibling_index]
+            } else {
+                layer[index]
+            };
+            // 兄弟在右侧，当且仅当当前节点是偶数下标（左孩子）
+            proof.push((sibling, is_even));
+            index /= 2;
+        }
+
+        Some(proof)
+    }
+}
+
+/// 验证一条Merkle证明：沿路径拼接哈希重建根，并与给定的根比较
+pub fn verify_proof(root: Hash, leaf: Hash, proof: &[(Hash, bool)]) -> bool {
+    let mut computed = leaf;
+    for (sibling, sibling_is_right) in proof {
+        computed = if *sibling_is_right {
+            hash_pair(&computed, sibling)
+        } else {
+            hash_pair(sibling, &computed)
+        };
+    }
+    computed == root
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_single_leaf_tree_root_is_the_leaf_itself_hashed_with_itself() {
+        let leaf = hash_leaf(b"agent-1");
+        let tree = MerkleTree::from_leaves(vec![leaf]);
+
+        assert_eq!(tree.root(), hash_pair(&leaf, &leaf));
+    }
+
+    #[test]
+    fn test_proof_verifies_for_every_leaf_including_odd_count() {
+        let leaves: Vec<Hash> = (0..5u8).map(|i| hash_leaf(&[i])).collect();
+        let tree = MerkleTree::from_leaves(leaves.clone());
+        let root = tree.root();
+
+        for (i, leaf) in leaves.iter().enumerate() {
+            let proof = tree.proof(i).expect("应能生成证明");
+            assert!(verify_proof(root, *leaf, &proof));
+        }
+    }
+
+    #[test]
+    fn test_proof_rejects_wrong_leaf() {
+        let leaves: Vec<Hash> = (0..4u8).map(|i| hash_leaf(&[i])).collect();
+        let tree = MerkleTree::from_leaves(leaves.clone());
+        let root = tree.root();
+
+        let proof = tree.proof(0).expect("应能生成证明");
+        let wrong_leaf = hash_leaf(b"not-a-member");
+        assert!(!verify_proof(root, wrong_leaf, &proof));
+    }
+
+    #[test]
+    fn test_empty_tree_has_deterministic_root() {
+        let tree = MerkleTree::from_leaves(vec![]);
+        assert_eq!(tree.root(), hash_leaf(&[]));
+    }
+}
@@ -8,7 +8,13 @@ pub mod rpc_client;
 pub mod identity_registry;
 #[cfg(feature = "solana")]
 pub mod types;
+pub mod consensus_account;
 pub mod consensus_deployer;
+pub mod consensus_history;
+pub mod consensus_network;
+pub mod local_ledger;
+pub mod merkle;
+pub mod pda;
 // pub mod real_consensus_deployer;
 pub mod simple_solana_deployer;
 pub mod true_solana_deployer;
@@ -20,7 +26,13 @@ pub use rpc_client::SolanaRpcClient;
 pub use identity_registry::IdentityRegistryClient;
 #[cfg(feature = "solana")]
 pub use types::*;
+pub use consensus_account::{derive_consensus_pda, ConsensusAccount};
 pub use consensus_deployer::*;
+pub use consensus_history::{ConsensusHistory, ConsensusRecord};
+pub use consensus_network::ConsensusNetwork;
+pub use local_ledger::{Block, LocalLedger};
+pub use merkle::{verify_proof, Hash, MerkleTree};
+pub use pda::find_program_address;
 // pub use real_consensus_deployer::*;
 pub use simple_solana_deployer::*;
 pub use true_solana_deployer::*;
@@ -0,0 +1,71 @@
+//! 程序派生地址（PDA）推导
+//!
+//! Solana的PDA不对应任何ed25519私钥：地址由种子、bump和程序ID通过SHA-256
+//! 派生，只有当派生出的32字节结果*不在*ed25519曲线上时才是合法的PDA——这样
+//! 才能保证没有人能持有对应的私钥、从而伪造签名。算法与`solana-program`的
+//! `Pubkey::find_program_address`一致：bump从255开始递减尝试，直到落在
+//! 曲线外为止。
+
+use curve25519_dalek::edwards::CompressedEdwardsY;
+use sha2::{Digest, Sha256};
+
+/// 用作派生地址后缀的固定盐值，与Solana运行时保持一致
+const PDA_MARKER: &[u8] = b"ProgramDerivedAddress";
+
+/// 给定一组种子和程序ID，推导出一个合法的程序派生地址及其bump seed
+///
+/// 返回 `(address, bump)`；若255个bump都落在曲线上（概率约为 `2^-255`，
+/// 实践中不会发生）则panic
+pub fn find_program_address(seeds: &[&[u8]], program_id: &[u8; 32]) -> ([u8; 32], u8) {
+    for bump in (0..=u8::MAX).rev() {
+        let candidate = create_program_address(seeds, &[bump], program_id);
+        if CompressedEdwardsY(candidate).decompress().is_none() {
+            return (candidate, bump);
+        }
+    }
+    panic!("无法为给定种子找到合法的程序派生地址bump seed")
+}
+
+/// 对给定种子、bump和程序ID求SHA-256，得到候选地址（不检查是否落在曲线外）
+fn create_program_address(seeds: &[&[u8]], bump: &[u8], program_id: &[u8; 32]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    for seed in seeds {
+        hasher.update(seed);
+    }
+    hasher.update(bump);
+    hasher.update(program_id);
+    hasher.update(PDA_MARKER);
+    hasher.finalize().into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_program_address_is_deterministic() {
+        let program_id = [7u8; 32];
+        let (addr_1, bump_1) = find_program_address(&[b"consensus", b"abc"], &program_id);
+        let (addr_2, bump_2) = find_program_address(&[b"consensus", b"abc"], &program_id);
+
+        assert_eq!(addr_1, addr_2);
+        assert_eq!(bump_1, bump_2);
+    }
+
+    #[test]
+    fn test_find_program_address_differs_per_seed() {
+        let program_id = [7u8; 32];
+        let (addr_a, _) = find_program_address(&[b"consensus", b"abc"], &program_id);
+        let (addr_b, _) = find_program_address(&[b"consensus", b"xyz"], &program_id);
+
+        assert_ne!(addr_a, addr_b);
+    }
+
+    #[test]
+    fn test_derived_address_is_off_curve() {
+        let program_id = [42u8; 32];
+        let (addr, _) = find_program_address(&[b"consensus", b"off-curve-check"], &program_id);
+
+        assert!(CompressedEdwardsY(addr).decompress().is_none());
+    }
+}
@@ -0,0 +1,689 @@
+//! 可观测性指标定义 DSL
+//!
+//! 取代 [`super::visualization`] 中硬编码的权重与聚合逻辑（`calculate_overall_score`
+//! 及 `ReputationAlgorithms` 里的打分辅助函数），提供一个小型声明式指标定义
+//! 语言，参照流式可观测性分析 DSL 的思路：运营者可以写
+//!
+//! ```text
+//! score = avg(accuracy) * 0.3 + p95(response_time_ms) * 0.2
+//! component_grade = weighted_sum(network * 0.25, consensus * 0.30) filter tier == "core"
+//! ```
+//!
+//! 这些定义会被编译为对 [`TestResults`](crate::test::TestResults) 历史记录或
+//! 信誉分数历史（统一抽象为 [`Row`] 的集合）的求值计划。模块提供聚合原语
+//! （`avg`/`sum`/`p50`/`p95`/`p99`/`rate`/`stddev`/`count`/`weighted_sum`）、
+//! 按值截断的 `max`/`min`（例如 `max(1 - avg(latency) / 1000, 0)` 防止分数
+//! 为负）、按层级（`tier`）分组与 `filter` 过滤谓词，以及解析器、带类型的
+//! AST 与求值器，使用户无需重新编译 crate 即可调整总体评分与派生信誉信号。
+
+use std::collections::HashMap;
+use std::fmt;
+
+/// 指标表中的一个字段值
+#[derive(Debug, Clone, PartialEq)]
+pub enum MetricValue {
+    /// 数值型
+    Number(f64),
+    /// 字符串型（通常用于 `tier` 这类分组/过滤字段）
+    String(String),
+}
+
+/// 一行指标样本，例如一次测试运行、一次信誉分数更新
+pub type Row = HashMap<String, MetricValue>;
+
+/// DSL 编译/求值过程中的错误
+#[derive(Debug, Clone, PartialEq)]
+pub enum DslError {
+    /// 解析阶段的语法错误
+    Syntax(String),
+    /// 求值阶段的类型错误（例如裸列引用出现在聚合函数之外）
+    Type(String),
+    /// 引用了未知的聚合函数
+    UnknownFunction(String),
+    /// 引用了不存在的列
+    UnknownColumn(String),
+}
+
+impl fmt::Display for DslError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DslError::Syntax(msg) => write!(f, "语法错误: {}", msg),
+            DslError::Type(msg) => write!(f, "类型错误: {}", msg),
+            DslError::UnknownFunction(name) => write!(f, "未知的聚合函数: {}", name),
+            DslError::UnknownColumn(name) => write!(f, "未知的列: {}", name),
+        }
+    }
+}
+
+impl std::error::Error for DslError {}
+
+// ---------------------------------------------------------------------
+// 词法分析
+// ---------------------------------------------------------------------
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    Number(f64),
+    Str(String),
+    Plus,
+    Minus,
+    Star,
+    Slash,
+    LParen,
+    RParen,
+    Comma,
+    Eq,
+    EqEq,
+    Filter,
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>, DslError> {
+    let mut tokens = Vec::new();
+    let chars: Vec<char> = input.chars().collect();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+
+        if c.is_whitespace() {
+            i += 1;
+            continue;
+        }
+
+        match c {
+            '+' => {
+                tokens.push(Token::Plus);
+                i += 1;
+            }
+            '-' => {
+                tokens.push(Token::Minus);
+                i += 1;
+            }
+            '*' => {
+                tokens.push(Token::Star);
+                i += 1;
+            }
+            '/' => {
+                tokens.push(Token::Slash);
+                i += 1;
+            }
+            '(' => {
+                tokens.push(Token::LParen);
+                i += 1;
+            }
+            ')' => {
+                tokens.push(Token::RParen);
+                i += 1;
+            }
+            ',' => {
+                tokens.push(Token::Comma);
+                i += 1;
+            }
+            '=' => {
+                if chars.get(i + 1) == Some(&'=') {
+                    tokens.push(Token::EqEq);
+                    i += 2;
+                } else {
+                    tokens.push(Token::Eq);
+                    i += 1;
+                }
+            }
+            '"' => {
+                let mut s = String::new();
+                i += 1;
+                while i < chars.len() && chars[i] != '"' {
+                    s.push(chars[i]);
+                    i += 1;
+                }
+                if i >= chars.len() {
+                    return Err(DslError::Syntax("未闭合的字符串字面量".to_string()));
+                }
+                i += 1; // 跳过收尾的引号
+                tokens.push(Token::Str(s));
+            }
+            _ if c.is_ascii_digit() || c == '.' => {
+                let start = i;
+                while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                    i += 1;
+                }
+                let text: String = chars[start..i].iter().collect();
+                let value = text
+                    .parse::<f64>()
+                    .map_err(|_| DslError::Syntax(format!("非法的数字字面量: {}", text)))?;
+                tokens.push(Token::Number(value));
+            }
+            _ if c.is_alphabetic() || c == '_' => {
+                let start = i;
+                while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                    i += 1;
+                }
+                let text: String = chars[start..i].iter().collect();
+                if text == "filter" {
+                    tokens.push(Token::Filter);
+                } else {
+                    tokens.push(Token::Ident(text));
+                }
+            }
+            other => return Err(DslError::Syntax(format!("无法识别的字符: '{}'", other))),
+        }
+    }
+
+    Ok(tokens)
+}
+
+// ---------------------------------------------------------------------
+// 带类型的 AST
+// ---------------------------------------------------------------------
+
+/// 二元算术运算符
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BinOp {
+    /// 加法
+    Add,
+    /// 减法
+    Sub,
+    /// 乘法
+    Mul,
+    /// 除法
+    Div,
+}
+
+/// `filter` 谓词：`列 == 值`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Condition {
+    /// 过滤的列名
+    pub field: String,
+    /// 要求相等的值
+    pub value: MetricValue,
+}
+
+impl Condition {
+    fn matches(&self, row: &Row) -> bool {
+        row.get(&self.field) == Some(&self.value)
+    }
+}
+
+/// 指标表达式的 AST
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    /// 数值字面量
+    Number(f64),
+    /// 字符串字面量（仅出现在 `filter` 条件中）
+    String(String),
+    /// 裸列引用：只能作为聚合函数的参数
+    Column(String),
+    /// 聚合/组合函数调用，例如 `avg(accuracy)`、`weighted_sum(a, b)`
+    Call(String, Vec<Expr>),
+    /// 二元算术运算
+    BinaryOp(Box<Expr>, BinOp, Box<Expr>),
+    /// `expr filter 列 == 值`：先过滤样本行，再对子表达式求值
+    Filter(Box<Expr>, Condition),
+}
+
+/// 一条编译后的指标定义：`名称 = 表达式`
+#[derive(Debug, Clone, PartialEq)]
+pub struct MetricDef {
+    /// 指标名称
+    pub name: String,
+    /// 指标表达式
+    pub expr: Expr,
+}
+
+// ---------------------------------------------------------------------
+// 语法分析（递归下降）
+// ---------------------------------------------------------------------
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn new(tokens: Vec<Token>) -> Self {
+        Self { tokens, pos: 0 }
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn advance(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn expect(&mut self, expected: &Token) -> Result<(), DslError> {
+        match self.advance() {
+            Some(ref t) if t == expected => Ok(()),
+            other => Err(DslError::Syntax(format!(
+                "期望 {:?}，但得到 {:?}",
+                expected, other
+            ))),
+        }
+    }
+
+    fn parse_metric_def(&mut self) -> Result<MetricDef, DslError> {
+        let name = match self.advance() {
+            Some(Token::Ident(name)) => name,
+            other => {
+                return Err(DslError::Syntax(format!(
+                    "指标定义必须以标识符开头，得到 {:?}",
+                    other
+                )))
+            }
+        };
+
+        self.expect(&Token::Eq)?;
+        let expr = self.parse_expr()?;
+
+        Ok(MetricDef { name, expr })
+    }
+
+    /// expr := term (('+' | '-') term)* ('filter' condition)?
+    fn parse_expr(&mut self) -> Result<Expr, DslError> {
+        let mut expr = self.parse_term()?;
+
+        loop {
+            match self.peek() {
+                Some(Token::Plus) => {
+                    self.advance();
+                    let rhs = self.parse_term()?;
+                    expr = Expr::BinaryOp(Box::new(expr), BinOp::Add, Box::new(rhs));
+                }
+                Some(Token::Minus) => {
+                    self.advance();
+                    let rhs = self.parse_term()?;
+                    expr = Expr::BinaryOp(Box::new(expr), BinOp::Sub, Box::new(rhs));
+                }
+                _ => break,
+            }
+        }
+
+        if matches!(self.peek(), Some(Token::Filter)) {
+            self.advance();
+            let condition = self.parse_condition()?;
+            expr = Expr::Filter(Box::new(expr), condition);
+        }
+
+        Ok(expr)
+    }
+
+    /// term := factor (('*' | '/') factor)*
+    fn parse_term(&mut self) -> Result<Expr, DslError> {
+        let mut expr = self.parse_factor()?;
+
+        loop {
+            match self.peek() {
+                Some(Token::Star) => {
+                    self.advance();
+                    let rhs = self.parse_factor()?;
+                    expr = Expr::BinaryOp(Box::new(expr), BinOp::Mul, Box::new(rhs));
+                }
+                Some(Token::Slash) => {
+                    self.advance();
+                    let rhs = self.parse_factor()?;
+                    expr = Expr::BinaryOp(Box::new(expr), BinOp::Div, Box::new(rhs));
+                }
+                _ => break,
+            }
+        }
+
+        Ok(expr)
+    }
+
+    /// factor := NUMBER | STRING | IDENT ['(' args ')'] | '(' expr ')'
+    fn parse_factor(&mut self) -> Result<Expr, DslError> {
+        match self.advance() {
+            Some(Token::Number(n)) => Ok(Expr::Number(n)),
+            Some(Token::Str(s)) => Ok(Expr::String(s)),
+            Some(Token::LParen) => {
+                let expr = self.parse_expr()?;
+                self.expect(&Token::RParen)?;
+                Ok(expr)
+            }
+            Some(Token::Ident(name)) => {
+                if matches!(self.peek(), Some(Token::LParen)) {
+                    self.advance();
+                    let args = self.parse_args()?;
+                    self.expect(&Token::RParen)?;
+                    Ok(Expr::Call(name, args))
+                } else {
+                    Ok(Expr::Column(name))
+                }
+            }
+            other => Err(DslError::Syntax(format!(
+                "期望数字、字符串、标识符或括号表达式，得到 {:?}",
+                other
+            ))),
+        }
+    }
+
+    fn parse_args(&mut self) -> Result<Vec<Expr>, DslError> {
+        let mut args = Vec::new();
+
+        if matches!(self.peek(), Some(Token::RParen)) {
+            return Ok(args);
+        }
+
+        args.push(self.parse_expr()?);
+        while matches!(self.peek(), Some(Token::Comma)) {
+            self.advance();
+            args.push(self.parse_expr()?);
+        }
+
+        Ok(args)
+    }
+
+    /// condition := IDENT '==' (STRING | NUMBER)
+    fn parse_condition(&mut self) -> Result<Condition, DslError> {
+        let field = match self.advance() {
+            Some(Token::Ident(name)) => name,
+            other => {
+                return Err(DslError::Syntax(format!(
+                    "filter 条件必须以列名开头，得到 {:?}",
+                    other
+                )))
+            }
+        };
+
+        self.expect(&Token::EqEq)?;
+
+        let value = match self.advance() {
+            Some(Token::Str(s)) => MetricValue::String(s),
+            Some(Token::Number(n)) => MetricValue::Number(n),
+            other => {
+                return Err(DslError::Syntax(format!(
+                    "filter 条件的值必须是字符串或数字，得到 {:?}",
+                    other
+                )))
+            }
+        };
+
+        Ok(Condition { field, value })
+    }
+}
+
+/// 解析一条形如 `名称 = 表达式 [filter 列 == 值]` 的指标定义
+pub fn parse_metric_def(source: &str) -> Result<MetricDef, DslError> {
+    let tokens = tokenize(source)?;
+    let mut parser = Parser::new(tokens);
+    let def = parser.parse_metric_def()?;
+
+    if parser.pos != parser.tokens.len() {
+        return Err(DslError::Syntax("表达式结尾存在多余的符号".to_string()));
+    }
+
+    Ok(def)
+}
+
+/// 解析多条以换行分隔的指标定义
+pub fn parse_metric_defs(source: &str) -> Result<Vec<MetricDef>, DslError> {
+    source
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(parse_metric_def)
+        .collect()
+}
+
+// ---------------------------------------------------------------------
+// 求值器
+// ---------------------------------------------------------------------
+
+/// 在一组样本行上求值指标定义
+pub struct Evaluator<'a> {
+    rows: &'a [Row],
+}
+
+impl<'a> Evaluator<'a> {
+    /// 创建在给定样本行上求值的求值器
+    pub fn new(rows: &'a [Row]) -> Self {
+        Self { rows }
+    }
+
+    /// 求值单个 [`MetricDef`]
+    pub fn evaluate(&self, def: &MetricDef) -> Result<f64, DslError> {
+        self.eval_expr(&def.expr, self.rows)
+    }
+
+    /// 按 `group_by` 列的取值分组，分别求值同一个表达式
+    pub fn evaluate_grouped(
+        &self,
+        def: &MetricDef,
+        group_by: &str,
+    ) -> Result<HashMap<String, f64>, DslError> {
+        let mut groups: HashMap<String, Vec<Row>> = HashMap::new();
+
+        for row in self.rows {
+            let key = match row.get(group_by) {
+                Some(MetricValue::String(s)) => s.clone(),
+                Some(MetricValue::Number(n)) => n.to_string(),
+                None => continue,
+            };
+            groups.entry(key).or_default().push(row.clone());
+        }
+
+        groups
+            .into_iter()
+            .map(|(key, rows)| {
+                let value = self.eval_expr(&def.expr, &rows)?;
+                Ok((key, value))
+            })
+            .collect()
+    }
+
+    fn eval_expr(&self, expr: &Expr, rows: &[Row]) -> Result<f64, DslError> {
+        match expr {
+            Expr::Number(n) => Ok(*n),
+            Expr::String(s) => Err(DslError::Type(format!(
+                "字符串字面量 \"{}\" 不能作为数值表达式的结果",
+                s
+            ))),
+            Expr::Column(name) => Err(DslError::Type(format!(
+                "列 `{}` 只能作为聚合函数的参数出现，不能单独作为表达式的结果",
+                name
+            ))),
+            Expr::BinaryOp(lhs, op, rhs) => {
+                let l = self.eval_expr(lhs, rows)?;
+                let r = self.eval_expr(rhs, rows)?;
+                Ok(match op {
+                    BinOp::Add => l + r,
+                    BinOp::Sub => l - r,
+                    BinOp::Mul => l * r,
+                    BinOp::Div => l / r,
+                })
+            }
+            Expr::Filter(inner, condition) => {
+                let filtered: Vec<Row> = rows
+                    .iter()
+                    .filter(|row| condition.matches(row))
+                    .cloned()
+                    .collect();
+                self.eval_expr(inner, &filtered)
+            }
+            Expr::Call(name, args) => self.eval_call(name, args, rows),
+        }
+    }
+
+    fn eval_call(&self, name: &str, args: &[Expr], rows: &[Row]) -> Result<f64, DslError> {
+        match name {
+            "count" => Ok(rows.len() as f64),
+            "weighted_sum" => {
+                let mut total = 0.0;
+                for arg in args {
+                    total += self.eval_expr(arg, rows)?;
+                }
+                Ok(total)
+            }
+            "max" | "min" => {
+                let [lhs, rhs] = Self::two_arg(name, args)?;
+                let l = self.eval_expr(lhs, rows)?;
+                let r = self.eval_expr(rhs, rows)?;
+                Ok(if name == "max" { l.max(r) } else { l.min(r) })
+            }
+            "avg" | "sum" | "p50" | "p95" | "p99" | "stddev" | "rate" => {
+                let column = Self::single_column_arg(name, args)?;
+                let values = self.column_values(rows, &column)?;
+                Ok(match name {
+                    "avg" => mean(&values),
+                    "sum" => values.iter().sum(),
+                    "p50" => percentile(&values, 0.50),
+                    "p95" => percentile(&values, 0.95),
+                    "p99" => percentile(&values, 0.99),
+                    "stddev" => stddev(&values),
+                    "rate" => {
+                        if values.is_empty() {
+                            0.0
+                        } else {
+                            values.iter().filter(|v| **v != 0.0).count() as f64 / values.len() as f64
+                        }
+                    }
+                    _ => unreachable!(),
+                })
+            }
+            other => Err(DslError::UnknownFunction(other.to_string())),
+        }
+    }
+
+    fn single_column_arg(fn_name: &str, args: &[Expr]) -> Result<String, DslError> {
+        match args {
+            [Expr::Column(name)] => Ok(name.clone()),
+            _ => Err(DslError::Syntax(format!(
+                "{} 需要恰好一个列名参数",
+                fn_name
+            ))),
+        }
+    }
+
+    /// 取恰好两个参数，供 `max`/`min` 这类按值而非按列求值的函数使用
+    fn two_arg<'e>(fn_name: &str, args: &'e [Expr]) -> Result<[&'e Expr; 2], DslError> {
+        match args {
+            [a, b] => Ok([a, b]),
+            _ => Err(DslError::Syntax(format!("{} 需要恰好两个参数", fn_name))),
+        }
+    }
+
+    fn column_values(&self, rows: &[Row], column: &str) -> Result<Vec<f64>, DslError> {
+        let mut values = Vec::with_capacity(rows.len());
+        for row in rows {
+            match row.get(column) {
+                Some(MetricValue::Number(n)) => values.push(*n),
+                Some(MetricValue::String(_)) => {
+                    return Err(DslError::Type(format!("列 `{}` 不是数值类型", column)))
+                }
+                None => return Err(DslError::UnknownColumn(column.to_string())),
+            }
+        }
+        Ok(values)
+    }
+}
+
+fn mean(values: &[f64]) -> f64 {
+    if values.is_empty() {
+        return 0.0;
+    }
+    values.iter().sum::<f64>() / values.len() as f64
+}
+
+fn stddev(values: &[f64]) -> f64 {
+    if values.len() < 2 {
+        return 0.0;
+    }
+    let m = mean(values);
+    let variance = values.iter().map(|v| (v - m).powi(2)).sum::<f64>() / values.len() as f64;
+    variance.sqrt()
+}
+
+fn percentile(values: &[f64], p: f64) -> f64 {
+    if values.is_empty() {
+        return 0.0;
+    }
+    let mut sorted = values.to_vec();
+    sorted.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    let rank = (p * (sorted.len() - 1) as f64).round() as usize;
+    sorted[rank.min(sorted.len() - 1)]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(accuracy: f64, response_time_ms: f64, tier: &str) -> Row {
+        let mut row = HashMap::new();
+        row.insert("accuracy".to_string(), MetricValue::Number(accuracy));
+        row.insert(
+            "response_time_ms".to_string(),
+            MetricValue::Number(response_time_ms),
+        );
+        row.insert("tier".to_string(), MetricValue::String(tier.to_string()));
+        row
+    }
+
+    #[test]
+    fn test_parse_and_evaluate_weighted_expression() {
+        let def = parse_metric_def("score = avg(accuracy) * 0.3 + p95(response_time_ms) * 0.2")
+            .expect("应能解析表达式");
+
+        let rows = vec![row(0.9, 100.0, "core"), row(0.8, 200.0, "core")];
+        let evaluator = Evaluator::new(&rows);
+        let score = evaluator.evaluate(&def).expect("应能求值");
+
+        let expected = mean(&[0.9, 0.8]) * 0.3 + percentile(&[100.0, 200.0], 0.95) * 0.2;
+        assert!((score - expected).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_filter_restricts_rows_before_aggregation() {
+        let def = parse_metric_def("core_accuracy = avg(accuracy) filter tier == \"core\"")
+            .expect("应能解析带 filter 的表达式");
+
+        let rows = vec![row(1.0, 0.0, "core"), row(0.0, 0.0, "validator")];
+        let evaluator = Evaluator::new(&rows);
+        let score = evaluator.evaluate(&def).expect("应能求值");
+
+        assert_eq!(score, 1.0);
+    }
+
+    #[test]
+    fn test_unknown_function_is_reported() {
+        let def = parse_metric_def("bogus = median(accuracy)").expect("应能解析");
+        let rows = vec![row(1.0, 0.0, "core")];
+        let evaluator = Evaluator::new(&rows);
+
+        assert_eq!(
+            evaluator.evaluate(&def),
+            Err(DslError::UnknownFunction("median".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_max_clamps_negative_result_to_zero() {
+        let def = parse_metric_def("score = max(1 - avg(response_time_ms) / 1000, 0)")
+            .expect("应能解析 max 表达式");
+
+        let rows = vec![row(1.0, 2000.0, "core")];
+        let evaluator = Evaluator::new(&rows);
+        let score = evaluator.evaluate(&def).expect("应能求值");
+
+        assert_eq!(score, 0.0);
+    }
+
+    #[test]
+    fn test_evaluate_grouped_by_tier() {
+        let def = parse_metric_def("score = avg(accuracy)").expect("应能解析");
+        let rows = vec![
+            row(1.0, 0.0, "core"),
+            row(0.5, 0.0, "core"),
+            row(0.0, 0.0, "validator"),
+        ];
+        let evaluator = Evaluator::new(&rows);
+        let grouped = evaluator
+            .evaluate_grouped(&def, "tier")
+            .expect("应能分组求值");
+
+        assert_eq!(grouped.get("core"), Some(&0.75));
+        assert_eq!(grouped.get("validator"), Some(&0.0));
+    }
+}
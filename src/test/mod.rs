@@ -6,6 +6,7 @@ use std::collections::HashMap;
 
 pub mod config;
 pub mod local_node_manager;
+pub mod metrics_dsl;
 pub mod preconfigured_reputation;
 pub mod simple_prompt_support;
 pub mod visualization;
@@ -13,9 +14,10 @@ pub mod visualization;
 // 重新导出
 pub use config::LocalTestConfig;
 pub use local_node_manager::LocalTestNodeManager;
+pub use metrics_dsl::{DslError, Evaluator, MetricDef, MetricValue, Row};
 pub use preconfigured_reputation::PreconfiguredReputation;
 pub use simple_prompt_support::SimplePromptSupport;
-pub use visualization::visualize_test_results;
+pub use visualization::{calculate_overall_score_with_definitions, visualize_test_results};
 
 /// 测试结果类型
 #[derive(Debug, Clone)]
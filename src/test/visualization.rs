@@ -2,8 +2,8 @@
 //! 
 //! 提供分层架构测试结果的可视化输出
 
+use crate::test::metrics_dsl::{self, Evaluator, MetricDef, MetricValue, Row};
 use crate::test::{TestResults, NetworkTestResult, ConsensusTestResult, DiapTestResult, GatewayTestResult, PromptTestResult};
-use std::collections::HashMap;
 
 /// 可视化测试结果
 pub fn visualize_test_results(results: &TestResults) {
@@ -169,80 +169,149 @@ fn visualize_prompt_results(results: &PromptTestResult) {
     }
 }
 
-/// 计算总体评分
+/// 默认的指标定义集合：复刻此前硬编码的组件评分公式与总体加权组合。
+///
+/// 每一条定义的计算结果都会被写回样本行，供后续定义通过 `avg(...)` 引用，
+/// 形成一条求值链（组件评分 -> 总体评分）。运营者可以编写自己的定义文本
+/// 并通过 [`calculate_overall_score_with_definitions`] 传入，在不重新编译
+/// crate 的前提下调整权重或新增派生指标。
+const DEFAULT_METRIC_DEFINITIONS: &str = r#"
+network_score = avg(network_connection_success_rate) * 0.7 + max(1 - avg(network_average_latency_ms) / 1000, 0) * 0.3
+consensus_score = avg(consensus_success_rate) * 0.6 + max(1 - avg(consensus_average_time_ms) / 500, 0) * 0.4
+diap_score = avg(diap_verification_success_rate) * 0.5 + avg(diap_registration_success_rate) * 0.5
+gateway_score = avg(gateway_connection_success_rate) * 0.4 + avg(gateway_fault_recovery_success_rate) * 0.3 + max(1 - avg(gateway_average_response_time_ms) / 200, 0) * 0.3
+prompt_score = avg(prompt_success_rate)
+overall_score = avg(network_score) * 0.25 + avg(consensus_score) * 0.30 + avg(diap_score) * 0.20 + avg(gateway_score) * 0.15 + avg(prompt_score) * 0.10
+"#;
+
+/// 将内置指标名映射为展示用的中文组件名
+fn metric_display_name(metric_name: &str) -> &str {
+    match metric_name {
+        "network_score" => "网络",
+        "consensus_score" => "共识",
+        "diap_score" => "身份认证",
+        "gateway_score" => "网关",
+        "prompt_score" => "交互",
+        other => other,
+    }
+}
+
+/// 将测试结果打平为指标 DSL 求值所需的单行样本
+fn test_results_to_row(results: &TestResults) -> Row {
+    let mut row = Row::new();
+    row.insert(
+        "network_connection_success_rate".to_string(),
+        MetricValue::Number(results.network_test.connection_success_rate),
+    );
+    row.insert(
+        "network_average_latency_ms".to_string(),
+        MetricValue::Number(results.network_test.average_latency_ms),
+    );
+    row.insert(
+        "consensus_success_rate".to_string(),
+        MetricValue::Number(results.consensus_test.consensus_success_rate),
+    );
+    row.insert(
+        "consensus_average_time_ms".to_string(),
+        MetricValue::Number(results.consensus_test.average_consensus_time_ms),
+    );
+    row.insert(
+        "diap_verification_success_rate".to_string(),
+        MetricValue::Number(results.diap_test.verification_success_rate),
+    );
+    row.insert(
+        "diap_registration_success_rate".to_string(),
+        MetricValue::Number(results.diap_test.identity_registration_success_rate),
+    );
+    row.insert(
+        "gateway_connection_success_rate".to_string(),
+        MetricValue::Number(results.gateway_test.connection_success_rate),
+    );
+    row.insert(
+        "gateway_fault_recovery_success_rate".to_string(),
+        MetricValue::Number(results.gateway_test.fault_recovery_success_rate),
+    );
+    row.insert(
+        "gateway_average_response_time_ms".to_string(),
+        MetricValue::Number(results.gateway_test.average_response_time_ms),
+    );
+    row.insert(
+        "prompt_success_rate".to_string(),
+        MetricValue::Number(results.prompt_test.prompt_success_rate),
+    );
+    row
+}
+
+/// 计算总体评分，使用内置的默认指标定义
 fn calculate_overall_score(results: &TestResults) {
+    let definitions = metrics_dsl::parse_metric_defs(DEFAULT_METRIC_DEFINITIONS)
+        .expect("内置默认指标定义应始终可解析");
+    calculate_overall_score_with_definitions(results, &definitions);
+}
+
+/// 使用自定义指标定义计算并打印总体评分
+///
+/// `definitions` 按顺序依次求值，每一条的结果都会写回样本行，供后续定义
+/// 通过 `avg(其名称)` 引用。除最后一条外的定义都会作为"组件评分"展示星级，
+/// 最后一条（通常命名为 `overall_score`）作为总体评分展示。
+pub fn calculate_overall_score_with_definitions(results: &TestResults, definitions: &[MetricDef]) {
     println!("⭐ 总体评分");
     println!("----------");
-    
-    let weights = HashMap::from([
-        ("network", 0.25),
-        ("consensus", 0.30),
-        ("diap", 0.20),
-        ("gateway", 0.15),
-        ("prompt", 0.10),
-    ]);
-    
-    let mut weighted_score = 0.0;
+
+    let mut rows = vec![test_results_to_row(results)];
     let mut component_scores = Vec::new();
-    
-    // 网络组件评分
-    let network_score = results.network_test.connection_success_rate * 0.7 + 
-                       (1.0 - results.network_test.average_latency_ms / 1000.0).max(0.0) * 0.3;
-    weighted_score += network_score * weights["network"];
-    component_scores.push(("网络", network_score));
-    
-    // 共识组件评分
-    let consensus_score = results.consensus_test.consensus_success_rate * 0.6 +
-                         (1.0 - results.consensus_test.average_consensus_time_ms / 500.0).max(0.0) * 0.4;
-    weighted_score += consensus_score * weights["consensus"];
-    component_scores.push(("共识", consensus_score));
-    
-    // DIAP组件评分
-    let diap_score = results.diap_test.verification_success_rate * 0.5 +
-                    results.diap_test.identity_registration_success_rate * 0.5;
-    weighted_score += diap_score * weights["diap"];
-    component_scores.push(("身份认证", diap_score));
-    
-    // 网关组件评分
-    let gateway_score = results.gateway_test.connection_success_rate * 0.4 +
-                       results.gateway_test.fault_recovery_success_rate * 0.3 +
-                       (1.0 - results.gateway_test.average_response_time_ms / 200.0).max(0.0) * 0.3;
-    weighted_score += gateway_score * weights["gateway"];
-    component_scores.push(("网关", gateway_score));
-    
-    // Prompt组件评分
-    let prompt_score = results.prompt_test.prompt_success_rate;
-    weighted_score += prompt_score * weights["prompt"];
-    component_scores.push(("交互", prompt_score));
-    
+    let mut overall_score = 0.0;
+    let last_index = definitions.len().saturating_sub(1);
+
+    for (i, def) in definitions.iter().enumerate() {
+        let value = {
+            let evaluator = Evaluator::new(&rows);
+            match evaluator.evaluate(def) {
+                Ok(value) => value,
+                Err(e) => {
+                    println!("  ⚠️ 指标 `{}` 求值失败: {}", def.name, e);
+                    continue;
+                }
+            }
+        };
+        rows[0].insert(def.name.clone(), MetricValue::Number(value));
+
+        if i == last_index {
+            overall_score = value;
+        } else {
+            component_scores.push((metric_display_name(&def.name), value));
+        }
+    }
+
     // 显示组件评分
     println!("组件评分:");
     for (component, score) in component_scores {
-        let stars = "★".repeat((score * 5.0).round() as usize);
-        let empty_stars = "☆".repeat(5 - stars.len());
+        let star_count = (score * 5.0).round().max(0.0) as usize;
+        let stars = "★".repeat(star_count);
+        let empty_stars = "☆".repeat(5usize.saturating_sub(star_count));
         println!("  {}: {:.1}/5.0 {}{}", component, score * 5.0, stars, empty_stars);
     }
-    
+
     println!();
-    
+
     // 总体评分
-    let overall_score = weighted_score * 100.0;
-    let grade = match overall_score {
+    let overall_score_pct = overall_score * 100.0;
+    let grade = match overall_score_pct {
         s if s >= 90.0 => "A+ (优秀)",
         s if s >= 80.0 => "A (良好)",
         s if s >= 70.0 => "B (中等)",
         s if s >= 60.0 => "C (及格)",
         _ => "D (需要改进)",
     };
-    
-    println!("总体评分: {:.1}/100.0", overall_score);
+
+    println!("总体评分: {:.1}/100.0", overall_score_pct);
     println!("等级: {}", grade);
-    
+
     // 进度条显示
     let progress_width = 50;
-    let filled = (overall_score / 100.0 * progress_width as f64).round() as usize;
+    let filled = ((overall_score_pct / 100.0 * progress_width as f64).round() as usize).min(progress_width);
     let empty = progress_width - filled;
-    
+
     print!("进度: [");
     for _ in 0..filled {
         print!("█");
@@ -250,7 +319,7 @@ fn calculate_overall_score(results: &TestResults) {
     for _ in 0..empty {
         print!("░");
     }
-    println!("] {:.1}%", overall_score);
+    println!("] {:.1}%", overall_score_pct);
 }
 
 /// 打印网络拓扑图